@@ -27,6 +27,12 @@ pub struct BaseCli {
         help = "Enable or disable analytics (this will be saved to the config so you won't need it in subsequent runs)"
     )]
     pub analytics: Option<bool>,
+    #[arg(
+        global = true,
+        long = "offline",
+        help = "Don't fetch the remote database, use the copy saved the last time it was fetched instead"
+    )]
+    pub offline: bool,
     #[arg(global = true, long = "debug", help = "Enable debug output")]
     pub debug: bool,
 }
@@ -44,6 +50,12 @@ pub enum Commands {
             long = "prerelease"
         )]
         prerelease: bool,
+        #[arg(
+            help = "The folder Outer Wilds is installed in, if not given we'll try to find it",
+            short = 'g',
+            long = "game-path"
+        )]
+        game_path: Option<PathBuf>,
     },
     #[command(
         about = "View the current database alert (if there is one)",
@@ -59,6 +71,32 @@ pub enum Commands {
         )]
         dry: bool,
     },
+    #[command(about = "Pin a mod so it isn't updated")]
+    Pin {
+        #[arg(help = "The unique name of the mod to pin", value_hint = ValueHint::Other)]
+        unique_name: String,
+        #[arg(
+            help = "The exact version to keep the mod on, if not given the mod is held at whatever version is installed",
+            value_hint = ValueHint::Other
+        )]
+        version: Option<String>,
+    },
+    #[command(about = "Unpin a mod so it's updated again")]
+    Unpin {
+        #[arg(help = "The unique name of the mod to unpin", value_hint = ValueHint::Other)]
+        unique_name: String,
+    },
+    #[command(about = "Roll a mod back to the version it was at before it was last updated")]
+    Rollback {
+        #[arg(help = "The unique name of the mod to roll back", value_hint = ValueHint::Other)]
+        unique_name: String,
+        #[arg(
+            help = "Don't roll back, just list the backups of the mod",
+            short = 'l',
+            long = "list"
+        )]
+        list: bool,
+    },
     #[command(
         about = "List local (installed) or remote (in the database) mods",
         alias = "ls"
@@ -69,6 +107,24 @@ pub enum Commands {
         #[arg(global = true, help = "Filter by a specific tags", long="tag", short='t', num_args(0..), value_hint = ValueHint::Other)]
         tag: Option<Vec<String>>,
     },
+    #[command(about = "Show the dependency tree of installed mods")]
+    Tree {
+        #[arg(help = "Only show the tree for this mod", value_hint = ValueHint::Other)]
+        unique_name: Option<String>,
+        #[arg(
+            long = "dot",
+            help = "Print the whole graph in Graphviz DOT format",
+            conflicts_with = "json"
+        )]
+        dot: bool,
+        #[arg(long = "json", help = "Print the whole graph as JSON")]
+        json: bool,
+    },
+    #[command(about = "Show which installed mods require a mod")]
+    Why {
+        #[arg(help = "The unique name of the mod", value_hint = ValueHint::Other)]
+        unique_name: String,
+    },
     #[command(about = "Get the tags you can use in `owmods search` or `owmods ls`")]
     Tags,
     #[command(about = "Search the remote database for mods")]
@@ -98,6 +154,12 @@ pub enum Commands {
     Disable {
         #[arg(help = "The unique name of the mod to disable", value_hint = ValueHint::Other)]
         unique_name: String,
+        #[arg(
+            short = 'c',
+            long = "cascade",
+            help = "Also disable enabled mods that depend on this one instead of refusing"
+        )]
+        cascade: bool,
     },
     #[command(
         about = "Install a mod (use -r to auto-install dependencies)",
@@ -118,6 +180,12 @@ pub enum Commands {
             help = "Install the prerelease of this mod"
         )]
         prerelease: bool,
+        #[arg(
+            short = 'd',
+            long = "dry-run",
+            help = "Don't install anything, just show what would be installed"
+        )]
+        dry: bool,
     },
     #[command(
         about = "Install a mod from a .zip file (-r not supported)",
@@ -129,16 +197,39 @@ pub enum Commands {
     },
     #[command(about = "Install a mod from a URL (-r not supported)", alias = "iu")]
     InstallUrl {
-        #[arg(help = "The URL to install the mod from", value_hint = ValueHint::Url)]
+        #[arg(help = "The URL to install the mod from (a `#sha256=<hash>` fragment will be used as --sha256)", value_hint = ValueHint::Url)]
         url: String,
+        #[arg(
+            long = "sha256",
+            help = "The SHA-256 hash the archive should have, the install fails if it doesn't match",
+            value_hint = ValueHint::Other
+        )]
+        sha256: Option<String>,
     },
     #[command(
-        about = "Uninstall a mod (use -r to uninstall dependencies too)",
+        about = "Uninstall a mod (use -r to uninstall dependencies no other mods need too)",
         alias = "rm"
     )]
     Uninstall {
         #[arg(help = "The unique name of the mod to uninstall", value_hint = ValueHint::Other)]
         unique_name: String,
+        #[arg(
+            short = 'c',
+            long = "cascade",
+            help = "Also uninstall enabled mods that depend on this one instead of refusing"
+        )]
+        cascade: bool,
+    },
+    #[command(
+        about = "Uninstall mods that were installed as dependencies but aren't needed anymore"
+    )]
+    Autoremove {
+        #[arg(
+            help = "Don't uninstall anything, just list mods that would be removed",
+            short = 'd',
+            long = "dry-run"
+        )]
+        dry: bool,
     },
     #[command(about = "Export enabled mods to stdout as JSON")]
     Export,
@@ -155,12 +246,49 @@ pub enum Commands {
         )]
         disable_missing: bool,
     },
+    #[command(
+        about = "Lock enabled mods and OWML to their exact versions and hashes, outputs the lockfile to stdout as JSON"
+    )]
+    Lock,
+    #[command(
+        about = "Reproduce the mods in a lockfile exactly (installs locked versions, enables them, and disables all others)"
+    )]
+    InstallLock {
+        #[arg(help = "The path to the lockfile", value_hint = ValueHint::FilePath)]
+        file_path: PathBuf,
+        #[arg(
+            short = 's',
+            long = "strict",
+            help = "Refuse to install if OWML or the database have drifted from the lockfile"
+        )]
+        strict: bool,
+    },
+    #[command(about = "Manage the cache of downloaded mod archives")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheActions,
+    },
+    #[command(about = "Save, apply, and manage named sets of enabled mods")]
+    Profile {
+        #[command(subcommand)]
+        action: ProfileActions,
+    },
+    #[command(about = "View and change the settings of a mod")]
+    Settings {
+        #[command(subcommand)]
+        action: SettingsActions,
+    },
+    #[command(about = "Save, compare, restore, and share snapshots of mod settings")]
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotActions,
+    },
     #[command(about = "Run the game")]
     Run {
         #[arg(
             short = 'f',
             long = "force",
-            help = "Force the game to run even if there's conflicting mods or missing dependencies"
+            help = "Launch even if the pre-launch checks find errors (like conflicting mods or missing dependencies)"
         )]
         force: bool,
         #[arg(
@@ -183,6 +311,23 @@ pub enum Commands {
             value_hint = ValueHint::Other
         )]
         port: u16,
+        #[arg(
+            long = "filter",
+            help = "Only show logs matching a query, like `type:error,fatal sender:NewHorizons -\"shader\" /regex/`",
+            value_hint = ValueHint::Other
+        )]
+        filter: Option<String>,
+        #[arg(
+            long = "overflow",
+            help = "What to do when logs come in faster than they can be shown, defaults to the config's setting",
+            value_parser = ["block", "spill", "drop"]
+        )]
+        overflow: Option<String>,
+    },
+    #[command(about = "View game log sessions recorded from previous launches")]
+    Logs {
+        #[command(subcommand)]
+        action: LogsActions,
     },
     #[command(about = "Run a server to listen for log messages on")]
     LogServer {
@@ -194,6 +339,18 @@ pub enum Commands {
             value_hint = ValueHint::Other
         )]
         port: u16,
+        #[arg(
+            long = "filter",
+            help = "Only show logs matching a query, like `type:error,fatal sender:NewHorizons -\"shader\" /regex/`",
+            value_hint = ValueHint::Other
+        )]
+        filter: Option<String>,
+        #[arg(
+            long = "overflow",
+            help = "What to do when logs come in faster than they can be shown, defaults to the config's setting",
+            value_parser = ["block", "spill", "drop"]
+        )]
+        overflow: Option<String>,
     },
     #[command(about = "Quickly open something")]
     Open {
@@ -218,6 +375,25 @@ pub enum Commands {
         #[arg(short = 'f', long = "fix-deps", help = "Try to fix dependency issues")]
         fix: bool,
     },
+    #[command(
+        about = "Zip up configs, installed mods, and logs to attach to an issue (anonymized unless --no-anonymize is passed)"
+    )]
+    Bundle {
+        #[arg(
+            help = "Where to save the bundle, defaults to owmods-bundle-<time>.zip in the current directory",
+            value_hint = ValueHint::FilePath
+        )]
+        output: Option<PathBuf>,
+        #[arg(
+            long = "no-anonymize",
+            help = "Keep your home folder and username in the bundle instead of replacing them"
+        )]
+        no_anonymize: bool,
+    },
+    #[command(
+        about = "Check OWML, the game install, and installed mods for problems without launching the game"
+    )]
+    Doctor,
     #[command(about = "Clear which mod warnings were already shown")]
     ClearWarnings,
     #[command(about = "Generate auto completions for the given shell")]
@@ -256,3 +432,156 @@ pub enum ModListTypes {
     #[command(about = "Show all mods in the database (may want to use grep/find with this!)")]
     Remote,
 }
+
+#[derive(Subcommand)]
+pub enum ProfileActions {
+    #[command(about = "Save the currently enabled mods (and their settings) as a profile")]
+    Save {
+        #[arg(help = "The name of the profile, overwrites any profile with the same name", value_hint = ValueHint::Other)]
+        name: String,
+        #[arg(
+            short = 'n',
+            long = "no-settings",
+            help = "Don't save the settings of each mod, only which mods are enabled"
+        )]
+        no_settings: bool,
+    },
+    #[command(
+        about = "Enable the mods in a profile, disable all others, and install any that are missing"
+    )]
+    Apply {
+        #[arg(help = "The name of the profile to apply", value_hint = ValueHint::Other)]
+        name: String,
+    },
+    #[command(about = "List saved profiles", alias = "ls")]
+    List,
+    #[command(about = "Delete a saved profile", alias = "rm")]
+    Delete {
+        #[arg(help = "The name of the profile to delete", value_hint = ValueHint::Other)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SettingsActions {
+    #[command(about = "Show a mod's settings, or a single setting")]
+    Get {
+        #[arg(help = "The unique name of the mod", value_hint = ValueHint::Other)]
+        unique_name: String,
+        #[arg(help = "The key of the setting to show, shows all settings if not given", value_hint = ValueHint::Other)]
+        key: Option<String>,
+    },
+    #[command(about = "Change a setting of a mod")]
+    Set {
+        #[arg(help = "The unique name of the mod", value_hint = ValueHint::Other)]
+        unique_name: String,
+        #[arg(help = "The key of the setting to change", value_hint = ValueHint::Other)]
+        key: String,
+        #[arg(help = "The new value, checked against the setting's type", value_hint = ValueHint::Other)]
+        value: String,
+    },
+    #[command(about = "Reset a setting of a mod to its default")]
+    Reset {
+        #[arg(help = "The unique name of the mod", value_hint = ValueHint::Other)]
+        unique_name: String,
+        #[arg(help = "The key of the setting to reset, resets all settings if not given", value_hint = ValueHint::Other)]
+        key: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotActions {
+    #[command(about = "Save the settings of some or all installed mods as a snapshot")]
+    Save {
+        #[arg(help = "The name of the snapshot, overwrites any snapshot with the same name", value_hint = ValueHint::Other)]
+        name: String,
+        #[arg(help = "The unique names of the mods to include, includes all installed mods if not given", value_hint = ValueHint::Other)]
+        unique_names: Vec<String>,
+    },
+    #[command(about = "Write the settings in a snapshot back to each mod's config")]
+    Restore {
+        #[arg(help = "The name of the snapshot to restore", value_hint = ValueHint::Other)]
+        name: String,
+    },
+    #[command(about = "Show the settings that differ between a snapshot and the current configs")]
+    Diff {
+        #[arg(help = "The name of the snapshot to compare", value_hint = ValueHint::Other)]
+        name: String,
+    },
+    #[command(about = "List saved snapshots", alias = "ls")]
+    List,
+    #[command(about = "Delete a saved snapshot", alias = "rm")]
+    Delete {
+        #[arg(help = "The name of the snapshot to delete", value_hint = ValueHint::Other)]
+        name: String,
+    },
+    #[command(about = "Export a snapshot to a file to share it")]
+    Export {
+        #[arg(help = "The name of the snapshot to export", value_hint = ValueHint::Other)]
+        name: String,
+        #[arg(help = "The file to write the snapshot to", value_hint = ValueHint::FilePath)]
+        file_path: PathBuf,
+    },
+    #[command(about = "Import a snapshot from a file")]
+    Import {
+        #[arg(help = "The file to read the snapshot from", value_hint = ValueHint::FilePath)]
+        file_path: PathBuf,
+        #[arg(
+            short = 'n',
+            long = "name",
+            help = "Save the snapshot under this name instead of the one in the file",
+            value_hint = ValueHint::Other
+        )]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LogsActions {
+    #[command(about = "List recorded log sessions, newest first", alias = "ls")]
+    List,
+    #[command(about = "Print the messages from a recorded log session")]
+    Show {
+        #[arg(
+            help = "The ID of the session to show, or \"latest\" for the most recent one",
+            default_value = "latest",
+            value_hint = ValueHint::Other
+        )]
+        session: String,
+    },
+    #[command(about = "Replay a recorded log session as if the game was sending it")]
+    Replay {
+        #[arg(
+            help = "The ID of the session to replay, \"latest\" for the most recent one, or a path to a session file",
+            default_value = "latest",
+            value_hint = ValueHint::Other
+        )]
+        session: String,
+        #[arg(
+            short = 'i',
+            long = "instant",
+            help = "Send every message right away instead of at the speed they were recorded"
+        )]
+        instant: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheActions {
+    #[command(about = "List the archives in the cache", alias = "ls")]
+    List,
+    #[command(about = "Remove every archive from the cache")]
+    Clean,
+    #[command(
+        about = "Remove archives for mods and versions that aren't installed, locked, or in a saved profile, then shrink the cache to its size limit"
+    )]
+    Prune {
+        #[arg(
+            short = 'l',
+            long = "lockfile",
+            help = "Also keep the versions locked in this lockfile, can be passed more than once",
+            value_hint = ValueHint::FilePath
+        )]
+        lockfiles: Vec<PathBuf>,
+    },
+}
@@ -1,31 +1,189 @@
-use std::io::{stdin, Read};
+use std::{
+    collections::BTreeMap,
+    io::{stdin, Read},
+    path::Path,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use log::{debug, error, info, warn};
 use owmods_core::{
-    alerts::get_warnings,
     config::Config,
     db::LocalDatabase,
+    diagnostics::{SessionAnalyzer, SessionReport, SuspectReason},
+    exceptions::{ExceptionGrouper, GroupedMessage, LogException},
     game::launch_game,
-    socket::{LogServer, SocketMessage, SocketMessageType},
+    game_paths::{
+        find_game_paths, is_game_dir, validate_game_path, GamePathProblem, GamePathSource,
+    },
+    log_query::LogQuery,
+    owml::OWMLConfig,
+    prelaunch::{PrelaunchCheck, PrelaunchReport},
+    sessions::{replay_session, RecordedMessage, ReplayPacing, SessionRecorder},
+    socket::{LogOverflowPolicy, LogServer, SocketMessage, SocketMessageType},
+    validate::ModValidationError,
 };
-use tokio::{sync::mpsc, try_join};
+use tokio::{sync::mpsc, time::timeout, try_join};
+
+use crate::logging::{log_mod_validation_errors, log_validation_errors};
+
+// How long to wait for more of an exception's stack trace before printing it
+const EXCEPTION_FLUSH_DELAY: Duration = Duration::from_millis(250);
+
+pub fn parse_log_filter(filter: Option<&str>) -> Result<Option<LogQuery>> {
+    filter
+        .map(|f| LogQuery::parse(f).context("Invalid log filter"))
+        .transpose()
+}
+
+pub fn parse_overflow_policy(overflow: Option<&str>, config: &Config) -> Result<LogOverflowPolicy> {
+    match overflow {
+        None => Ok(config.log_overflow_policy),
+        Some("block") => Ok(LogOverflowPolicy::Block),
+        Some("spill") => Ok(LogOverflowPolicy::Spill),
+        Some("drop") => Ok(LogOverflowPolicy::Drop),
+        Some(other) => Err(anyhow!("Unknown overflow policy \"{other}\"")),
+    }
+}
+
+fn show_game_log(message: &GroupedMessage, filter: Option<&LogQuery>) {
+    if filter.is_none_or(|f| f.matches(&message.message)) {
+        handle_game_log(&message.message, None);
+    }
+}
 
-fn handle_game_log(message: &SocketMessage) {
+pub fn log_exception_counts(counts: &BTreeMap<String, u32>) {
+    if !counts.is_empty() {
+        let total: u32 = counts.values().sum();
+        warn!("{total} exception(s) were logged:");
+        for (source, count) in counts {
+            warn!("  {source}: {count}");
+        }
+    }
+}
+
+fn format_exception(exception: &LogException) -> String {
+    let mut out = if exception.message.is_empty() {
+        exception.exception_type.clone()
+    } else {
+        format!("{}: {}", exception.exception_type, exception.message)
+    };
+    for frame in &exception.frames {
+        out.push_str(&format!("\n  at {}", frame.method));
+        if let Some(location) = &frame.location {
+            out.push_str(&format!(" ({location})"));
+        }
+    }
+    out
+}
+
+async fn print_game_logs(
+    mut rx: mpsc::Receiver<SocketMessage>,
+    mut recorder: Option<SessionRecorder>,
+    filter: Option<&LogQuery>,
+    mut analyzer: Option<SessionAnalyzer<'_>>,
+) -> Result<Option<SessionReport>> {
+    let mut grouper = ExceptionGrouper::default();
+    loop {
+        match timeout(EXCEPTION_FLUSH_DELAY, rx.recv()).await {
+            Ok(Some(msg)) => {
+                record_game_log(&mut recorder, &msg);
+                if let Some(analyzer) = analyzer.as_mut() {
+                    analyzer.add_message(&msg);
+                }
+                for grouped in grouper.push(msg) {
+                    show_game_log(&grouped, filter);
+                }
+            }
+            Ok(None) => break,
+            Err(_) => {
+                if let Some(grouped) = grouper.flush() {
+                    show_game_log(&grouped, filter);
+                }
+            }
+        }
+    }
+    if let Some(grouped) = grouper.flush() {
+        show_game_log(&grouped, filter);
+    }
+    log_exception_counts(grouper.counts());
+    Ok(analyzer.map(SessionAnalyzer::finish))
+}
+
+fn describe_reason(reason: &SuspectReason) -> &'static str {
+    match reason {
+        SuspectReason::SentFatal => "sent the first fatal error",
+        SuspectReason::SentFirstError => "sent the first error",
+        SuspectReason::MostErrors => "sent the most errors",
+        SuspectReason::ValidationIssues => "had issues when the game was launched",
+    }
+}
+
+fn log_session_report(report: &SessionReport, local_db: &LocalDatabase) {
+    if !report.has_problems() {
+        info!(
+            "Session finished with no errors ({} message(s) logged)",
+            report.message_count
+        );
+        return;
+    }
+    warn!("====== Session Report ======");
+    if let Some(message) = &report.first_fatal {
+        let sender = message.sender_name.as_deref().unwrap_or("Unknown");
+        error!("First fatal error, from {sender}: {}", message.message);
+    }
+    if let Some(message) = &report.first_error {
+        let sender = message.sender_name.as_deref().unwrap_or("Unknown");
+        error!("First error, from {sender}: {}", message.message);
+    }
+    if !report.mod_errors.is_empty() {
+        warn!("Errors per mod:");
+        for count in &report.mod_errors {
+            warn!(
+                "  {}: {} error(s), {} fatal, {} exception(s)",
+                count.sender_name, count.errors, count.fatal, count.exceptions
+            );
+        }
+    }
+    if !report.suspects.is_empty() {
+        warn!("Mods that may have caused problems:");
+        for suspect in &report.suspects {
+            let reasons = suspect
+                .reasons
+                .iter()
+                .map(describe_reason)
+                .collect::<Vec<_>>()
+                .join(", ");
+            warn!("  {} ({}): {reasons}", suspect.name, suspect.unique_name);
+        }
+    }
+    if !report.validation_issues.is_empty() {
+        warn!("Issues found when the game was launched:");
+        for issues in &report.validation_issues {
+            if let Some(local_mod) = local_db.get_mod_unsafe(&issues.unique_name) {
+                log_mod_validation_errors(local_mod, local_db);
+            }
+        }
+    }
+}
+
+pub fn handle_game_log(message: &SocketMessage, time: Option<&str>) {
     let unknown = &"Unknown".to_string();
     let log_header = format!(
-        "[{}::{}][{:?}] ",
+        "{}[{}::{}][{:?}] ",
+        time.map(|t| format!("[{t}]")).unwrap_or_default(),
         message.sender_name.as_ref().unwrap_or(unknown),
         message.sender_type.as_ref().unwrap_or(unknown),
         message.message_type
     );
     let spacing = " ".repeat(log_header.len());
-    let out_message = if message.message.trim().is_empty() {
+    let text = LogException::parse(message)
+        .map(|e| format_exception(&e))
+        .unwrap_or_else(|| message.message.clone());
+    let out_message = if text.trim().is_empty() {
         log_header
     } else {
-        message
-            .message
-            .lines()
+        text.lines()
             .enumerate()
             .map(|(i, l)| {
                 format!(
@@ -55,32 +213,234 @@ fn handle_game_log(message: &SocketMessage) {
     }
 }
 
-pub async fn start_just_logs(port: &u16) -> Result<()> {
-    let server = LogServer::new(*port).await?;
-    let (tx, mut rx) = mpsc::channel(32);
+fn start_recording(config: &Config) -> Option<SessionRecorder> {
+    SessionRecorder::start(config)
+        .map_err(|e| warn!("Couldn't start recording logs: {e:?}"))
+        .ok()
+        .flatten()
+}
+
+fn record_game_log(recorder: &mut Option<SessionRecorder>, message: &SocketMessage) {
+    if let Some(why) = recorder.as_mut().and_then(|r| r.record(message).err()) {
+        warn!("Couldn't record log, no more logs will be recorded this session: {why:?}");
+        *recorder = None;
+    }
+}
+
+pub async fn start_just_logs(
+    port: &u16,
+    config: &Config,
+    filter: Option<&LogQuery>,
+    overflow: LogOverflowPolicy,
+) -> Result<()> {
+    let mut server = LogServer::new(*port).await?;
+    server.overflow_policy = overflow;
+    let (tx, rx) = mpsc::channel(32);
+    let recorder = start_recording(config);
+
+    try_join!(
+        server.listen(tx, false),
+        print_game_logs(rx, recorder, filter, None)
+    )?;
+
+    Ok(())
+}
+
+pub async fn replay_logs(messages: Vec<RecordedMessage>, instant: bool) -> Result<()> {
+    let pacing = if instant {
+        ReplayPacing::Instant
+    } else {
+        ReplayPacing::RealTime
+    };
+    let (tx, rx) = mpsc::channel(32);
+
+    try_join!(
+        replay_session(messages, tx, pacing),
+        print_game_logs(rx, None, None, None)
+    )?;
+
+    Ok(())
+}
+
+fn describe_game_source(source: GamePathSource) -> &'static str {
+    match source {
+        GamePathSource::Steam => "Steam",
+        GamePathSource::SteamFlatpak => "Flatpak Steam",
+        GamePathSource::Epic => "Epic Games Launcher",
+        GamePathSource::Heroic => "Heroic Games Launcher",
+        GamePathSource::Common => "common install location",
+    }
+}
+
+pub fn describe_game_problems(problems: &[GamePathProblem]) -> String {
+    problems
+        .iter()
+        .map(|p| match p {
+            GamePathProblem::NotFound => "the folder doesn't exist",
+            GamePathProblem::OuterWorlds => "it's The Outer Worlds, not Outer Wilds",
+            GamePathProblem::MissingExe => "OuterWilds.exe is missing",
+            GamePathProblem::MissingData => "the OuterWilds_Data folder is missing",
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    try_join!(server.listen(tx, false), async {
-        while let Some(msg) = rx.recv().await {
-            handle_game_log(&msg);
+// Problems with the game path are logged with the pre-launch findings, this shows details or suggests a fix
+pub fn log_game_install(config: &Config) {
+    let game_path = match OWMLConfig::get(config) {
+        Ok(owml_config) => owml_config.game_path,
+        Err(why) => {
+            warn!("Couldn't read OWML's config: {why:#}");
+            return;
+        }
+    };
+    if game_path.trim().is_empty() {
+        info!("The game path isn't set, OWML will try to find the game when launching");
+    } else {
+        let install = validate_game_path(Path::new(&game_path));
+        if install.is_valid() {
+            info!("Outer Wilds found at {}", install.path);
+            info!(
+                "Unity version: {}",
+                install.unity_version.as_deref().unwrap_or("Unknown")
+            );
+            info!(
+                "Echoes of the Eye DLC content: {}",
+                if install.has_dlc {
+                    "Found"
+                } else {
+                    "Not found"
+                }
+            );
+            return;
         }
-        Ok(())
-    })?;
+    }
+    for candidate in find_game_paths() {
+        info!(
+            "Found Outer Wilds at {} ({}), run `owmods setup -g \"{}\"` to use it",
+            candidate.path,
+            describe_game_source(candidate.source),
+            candidate.path
+        );
+    }
+}
 
+// Set OWML's game path to the given folder, or pick one of the installs we can find if the current one isn't valid
+pub fn setup_game_path(config: &Config, game_path: Option<&Path>) -> Result<()> {
+    let mut owml_config = OWMLConfig::get(config)?;
+    if let Some(game_path) = game_path {
+        if !is_game_dir(game_path) {
+            return Err(anyhow!(
+                "OuterWilds.exe or OuterWilds_Data Not Found In {}",
+                game_path.display()
+            ));
+        }
+        owml_config.game_path = game_path.to_str().unwrap().to_string();
+        owml_config.save(config)?;
+        info!("Game path set to {}", owml_config.game_path);
+        return Ok(());
+    }
+
+    let candidates = find_game_paths();
+    for candidate in candidates.iter() {
+        info!(
+            "Found Outer Wilds at {} ({})",
+            candidate.path,
+            describe_game_source(candidate.source)
+        );
+    }
+    if is_game_dir(Path::new(&owml_config.game_path)) {
+        info!("Using game path {}", owml_config.game_path);
+    } else if let Some(candidate) = candidates.first() {
+        owml_config.game_path = candidate.path.clone();
+        owml_config.save(config)?;
+        info!("Game path set to {}", owml_config.game_path);
+    } else {
+        warn!("Couldn't find Outer Wilds, run `owmods setup -g /path/to/game` to set where it's installed");
+    }
     Ok(())
 }
 
+// Unseen warnings aren't logged here, they're shown one at a time right before launching
+pub fn log_prelaunch_findings(report: &PrelaunchReport, local_db: &LocalDatabase) {
+    let get_name = |unique_name: &str| {
+        local_db
+            .get_mod(unique_name)
+            .map(|m| m.manifest.name.clone())
+            .unwrap_or_else(|| unique_name.to_string())
+    };
+    for finding in report.findings.iter() {
+        match &finding.check {
+            PrelaunchCheck::UnseenWarning { .. } => {}
+            PrelaunchCheck::InvalidMod(issues) => {
+                if let Some(local_mod) = local_db.get_mod_unsafe(&issues.unique_name) {
+                    log_validation_errors(local_mod, &issues.errors, local_db);
+                }
+            }
+            PrelaunchCheck::Conflict {
+                unique_name,
+                conflicts_with,
+            } => {
+                if let Some(local_mod) = local_db.get_mod_unsafe(unique_name) {
+                    let conflict = ModValidationError::ConflictingMod(conflicts_with.clone());
+                    log_validation_errors(local_mod, [&conflict], local_db);
+                }
+            }
+            PrelaunchCheck::OutdatedMod {
+                unique_name,
+                installed,
+                latest,
+            } => {
+                info!(
+                    "{} has an update (v{installed} -> v{latest}), run `owmods update` to update it",
+                    get_name(unique_name)
+                );
+            }
+            PrelaunchCheck::MissingOwml => {
+                error!("OWML isn't installed, run `owmods setup` to install it");
+            }
+            PrelaunchCheck::OutdatedOwml { installed, latest } => {
+                warn!("OWML is outdated (v{installed} -> v{latest}), run `owmods update` to update it");
+            }
+            PrelaunchCheck::InvalidGamePath {
+                game_path,
+                problems,
+            } => {
+                if problems.contains(&GamePathProblem::OuterWorlds) {
+                    error!("OWML's game path ({game_path}) points to The Outer Worlds, not Outer Wilds! Change it in OWML's config or leave it blank to have OWML auto-detect it");
+                } else {
+                    warn!(
+                        "OWML's game path ({game_path}) doesn't look like an Outer Wilds install: {}. Run `owmods setup -g /path/to/game` to fix it",
+                        describe_game_problems(problems)
+                    );
+                }
+            }
+            PrelaunchCheck::MissingDlc { unique_name } => {
+                warn!(
+                    "{} needs the Echoes of the Eye DLC, but it doesn't seem to be installed",
+                    get_name(unique_name)
+                );
+            }
+            PrelaunchCheck::MissingMono => {
+                error!("Couldn't find Mono, it's needed to launch the game (set MONO_BINARY if it's installed somewhere unusual)");
+            }
+        }
+    }
+}
+
 pub async fn start_game(
     local_db: &LocalDatabase,
+    report: &PrelaunchReport,
     config: &Config,
     port: Option<&u16>,
     new_window: bool,
+    filter: Option<&LogQuery>,
+    overflow: LogOverflowPolicy,
 ) -> Result<()> {
-    let names = config.viewed_alerts.iter().map(|n| n.as_str()).collect();
-    let warnings = get_warnings(local_db.active().collect(), names);
-
-    let mut config = config.clone();
+    // Re-read the config so options set on the command line aren't persisted
+    let mut config = Config::get(Some(config.path.clone()))?;
 
-    for (unique_name, warning) in warnings {
+    for (unique_name, warning) in report.unseen_warnings() {
         let start_banner = format!("====== Warning For {unique_name} ======");
         let end_banner = "=".repeat(start_banner.len());
         warn!(
@@ -88,27 +448,28 @@ pub async fn start_game(
             start_banner, warning.title, warning.body, end_banner
         );
         stdin().read_exact(&mut [0])?;
-        config.set_warning_shown(unique_name);
     }
+    report.mark_warnings_shown(&mut config);
 
     config.save()?;
 
     if let Some(port) = port {
-        let server = LogServer::new(*port).await?;
+        let mut server = LogServer::new(*port).await?;
+        server.overflow_policy = overflow;
         let port = server.port;
 
-        let (tx, mut rx) = mpsc::channel(32);
+        let (tx, rx) = mpsc::channel(32);
+        let recorder = start_recording(&config);
 
-        try_join!(
+        let (_, _, report) = try_join!(
             server.listen(tx, true),
             launch_game(&config, false, Some(&port)),
-            async {
-                while let Some(msg) = rx.recv().await {
-                    handle_game_log(&msg);
-                }
-                Ok(())
-            }
+            print_game_logs(rx, recorder, filter, Some(SessionAnalyzer::new(local_db)))
         )?;
+
+        if let Some(report) = report {
+            log_session_report(&report, local_db);
+        }
     } else if new_window && cfg!(windows) {
         launch_game(&config, true, None).await?;
     } else {
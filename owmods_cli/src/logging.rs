@@ -125,8 +125,16 @@ impl log::Log for Logger {
 }
 
 pub fn log_mod_validation_errors(local_mod: &UnsafeLocalMod, local_db: &LocalDatabase) {
+    log_validation_errors(local_mod, local_mod.get_errs(), local_db);
+}
+
+pub fn log_validation_errors<'a>(
+    local_mod: &UnsafeLocalMod,
+    errors: impl IntoIterator<Item = &'a ModValidationError>,
+    local_db: &LocalDatabase,
+) {
     let name: &str = local_mod.get_name();
-    for err in local_mod.get_errs() {
+    for err in errors {
         match err {
             ModValidationError::MissingDLL(path) => match path {
                 Some(path) => {
@@ -172,6 +180,19 @@ pub fn log_mod_validation_errors(local_mod: &UnsafeLocalMod, local_db: &LocalDat
                     "{name} is outdated, consider updating it (latest version is v{new_version})"
                 )
             }
+            ModValidationError::UnsatisfiedDepVersion(unique_name) => {
+                let dep = local_db.get_mod(unique_name);
+                let dep_name = dep.map(|m| &m.manifest.name).unwrap_or(unique_name);
+                let installed = dep.map(|m| m.manifest.version.as_str()).unwrap_or("?");
+                let required = match local_mod {
+                    UnsafeLocalMod::Valid(m) => m.manifest.get_dependency_version(unique_name),
+                    UnsafeLocalMod::Invalid(_) => None,
+                }
+                .unwrap_or("?");
+                error!(
+                    "{name} requires {dep_name} {required}, but {installed} is installed! (run \"owmods check --fix-deps\" to auto-fix)"
+                );
+            }
         }
     }
 }
@@ -1,4 +1,10 @@
-use std::{fmt::Write, path::PathBuf, process};
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser};
@@ -6,21 +12,37 @@ use colored::Colorize;
 use log::{error, info, warn, LevelFilter};
 use owmods_core::{
     alerts::fetch_alert,
+    autoremove::{autoremove, find_orphans},
+    backups::{list_backups, rollback},
+    bundle::create_bundle,
+    cache::{clean_cache, list_cache, prune_cache},
     config::Config,
-    constants::OWML_UNIQUE_NAME,
+    constants::{OWML_UNIQUE_NAME, PIN_HOLD},
     db::{LocalDatabase, RemoteDatabase},
     download::{
         download_and_install_owml, install_mod_from_db, install_mod_from_url, install_mod_from_zip,
+        install_prepared, prepare_install,
     },
+    exceptions::ExceptionGrouper,
     file::get_default_owml_path,
+    graph::{DependencyGraph, DependentsMode},
     io::{export_mods, import_mods},
+    lock::{generate_lockfile, install_from_lock, Lockfile},
     mods::{
         local::{LocalMod, UnsafeLocalMod},
         remote::RemoteMod,
     },
     open::{open_github, open_readme, open_shortcut},
-    protocol::{ProtocolPayload, ProtocolVerb},
+    prelaunch::{run_prelaunch_checks, PrelaunchSeverity},
+    profiles::{apply_profile, delete_profile, list_profiles, Profile},
+    protocol::{split_url_hash, ProtocolPayload, ProtocolVerb},
     remove::{remove_failed_mod, remove_mod},
+    resolve::{resolve_install, InstallPlan, PlanReason},
+    sessions::{get_session, list_sessions, read_session_file},
+    settings::{get_mod_setting, get_mod_settings, reset_mod_setting, set_mod_setting, ModSetting},
+    snapshots::{
+        delete_snapshot, import_snapshot, list_snapshots, restore_snapshot, SettingsSnapshot,
+    },
     toggle::toggle_mod,
     updates::update_all,
     validate::fix_deps,
@@ -30,8 +52,15 @@ mod cli;
 mod game;
 mod logging;
 
-use cli::{BaseCli, Commands, ModListTypes};
-use game::{start_game, start_just_logs};
+use cli::{
+    BaseCli, CacheActions, Commands, LogsActions, ModListTypes, ProfileActions, SettingsActions,
+    SnapshotActions,
+};
+use game::{
+    handle_game_log, log_exception_counts, log_game_install, log_prelaunch_findings,
+    parse_log_filter, parse_overflow_policy, replay_logs, setup_game_path, start_game,
+    start_just_logs,
+};
 use logging::{log_mod_validation_errors, show_pre_patcher_warning, Logger};
 
 async fn run_from_cli(cli: BaseCli) -> Result<()> {
@@ -48,12 +77,19 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
         }
     }
 
+    // Commands that save the config re-read it first so this isn't persisted
+    if cli.offline {
+        config.offline = true;
+    }
+
     let ran_setup = matches!(
         &cli.command,
         Commands::Setup {
             owml_path: _,
-            prerelease: _
+            prerelease: _,
+            game_path: _
         } | Commands::Version
+            | Commands::Doctor
     );
 
     if !config.check_owml() && !ran_setup {
@@ -76,13 +112,15 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
         Commands::Setup {
             owml_path,
             prerelease,
+            game_path,
         } => {
             if let Some(owml_path) = owml_path {
-                let mut new_config = config.clone();
+                let mut new_config = Config::get(None)?;
                 new_config.owml_path = owml_path.to_str().unwrap().to_string();
                 if new_config.check_owml() {
                     info!("Path to OWML is valid! Updating config...");
                     new_config.save()?;
+                    setup_game_path(&new_config, game_path.as_deref())?;
                     info!("Done! Happy Modding!");
                 } else {
                     error!(
@@ -93,11 +131,12 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             } else {
                 let mut config = config.clone();
                 config.owml_path = get_default_owml_path()?.to_str().unwrap().to_string();
-                let db = RemoteDatabase::fetch(&config.database_url).await?;
+                let db = RemoteDatabase::fetch_or_cached(&config).await?;
                 let owml = db
                     .get_owml()
                     .context("OWML not found, is the database URL correct?")?;
                 download_and_install_owml(&config, owml, *prerelease).await?;
+                setup_game_path(&config, game_path.as_deref())?;
                 info!("Done! Happy Modding!");
             }
         }
@@ -129,7 +168,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 let mut output = String::new();
                 let mut mods: Vec<&LocalMod> = db.valid().collect();
                 if let Some(tags) = tag {
-                    match RemoteDatabase::fetch(&config.database_url).await {
+                    match RemoteDatabase::fetch_or_cached(&config).await {
                         Ok(remote_db) => {
                             let remote_mods_matching: Vec<&str> = remote_db
                                 .matches_tags(tags.clone())
@@ -147,7 +186,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                     mods.len(),
                     config.owml_path
                 );
-                mods.sort_by(|a, b| b.enabled.cmp(&a.enabled));
+                mods.sort_by_key(|m| std::cmp::Reverse(m.enabled));
                 for local_mod in mods.iter() {
                     output += &format!(
                         "({}) {} v{} by {} ({})\n",
@@ -161,7 +200,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 info!("{}", &output);
             }
             Some(ModListTypes::Remote) => {
-                let db = RemoteDatabase::fetch(&config.database_url).await?;
+                let db = RemoteDatabase::fetch_or_cached(&config).await?;
                 let mods: Vec<&RemoteMod> = if let Some(tags) = tag {
                     db.matches_tags(tags.clone()).collect()
                 } else {
@@ -184,13 +223,13 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             }
         },
         Commands::Tags => {
-            let db = RemoteDatabase::fetch(&config.database_url).await?;
+            let db = RemoteDatabase::fetch_or_cached(&config).await?;
             for tag in db.get_tags() {
                 info!("- {tag}");
             }
         }
         Commands::Search { query, tag } => {
-            let db = RemoteDatabase::fetch(&config.database_url).await?;
+            let db = RemoteDatabase::fetch_or_cached(&config).await?;
             let mut mods = db.search(query);
             if let Some(tags) = tag {
                 let db_tags = db.get_tags();
@@ -212,7 +251,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             }
         }
         Commands::Info { unique_name } => {
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
             let local_mod = local_db.get_mod(unique_name);
             let remote_mod = remote_db.get_mod(unique_name);
@@ -279,8 +318,9 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             unique_name,
             overwrite,
             prerelease,
+            dry,
         } => {
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
             let local_mod = local_db.get_mod(unique_name);
             let mut flag = true;
@@ -299,7 +339,25 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 flag = false;
             }
 
-            if flag {
+            if flag && *dry {
+                let plan = resolve_install(
+                    std::slice::from_ref(unique_name),
+                    &config,
+                    &remote_db,
+                    &local_db,
+                    *prerelease,
+                );
+                log_install_plan(&plan);
+                if !plan.is_complete() {
+                    info!("Dependencies of mods that aren't downloaded yet will be found when installing");
+                }
+            } else if flag && r {
+                let prepared =
+                    prepare_install(unique_name, &config, &remote_db, &local_db, *prerelease)
+                        .await?;
+                log_install_plan(&prepared.plan);
+                install_prepared(prepared, &config, &local_db).await?;
+            } else if flag {
                 install_mod_from_db(unique_name, &config, &remote_db, &local_db, r, *prerelease)
                     .await
                     .map(|_| ())?
@@ -311,13 +369,19 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             let new_mod = install_mod_from_zip(zip_path, &config, &local_db)?;
             info!("Installed {}!", new_mod.manifest.name);
         }
-        Commands::InstallUrl { url } => {
+        Commands::InstallUrl { url, sha256 } => {
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let (url, url_hash) = split_url_hash(url);
+            let expected_hash = sha256.as_deref().or(url_hash);
             info!("Installing From {url}");
-            let new_mod = install_mod_from_url(url, None, &config, &local_db).await?;
+            let new_mod =
+                install_mod_from_url(url, None, expected_hash, &config, &local_db).await?;
             info!("Installed {}!", new_mod.manifest.name);
         }
-        Commands::Uninstall { unique_name } => {
+        Commands::Uninstall {
+            unique_name,
+            cascade,
+        } => {
             let db = LocalDatabase::fetch(&config.owml_path)?;
             if unique_name == "all" {
                 let mut answer = String::new();
@@ -332,7 +396,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                                 remove_failed_mod(local_mod)?;
                             }
                             UnsafeLocalMod::Valid(local_mod) => {
-                                remove_mod(local_mod, &db, false)?;
+                                remove_mod(local_mod, &db, false, DependentsMode::Ignore)?;
                             }
                         }
                     }
@@ -348,16 +412,97 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                         unique_name,
                         if r { " and dependencies" } else { "" }
                     );
-                    let show_warnings_for = remove_mod(local_mod, &db, r)?;
+                    let show_warnings_for =
+                        remove_mod(local_mod, &db, r, dependents_mode(*cascade))?;
                     for mod_name in show_warnings_for {
                         show_pre_patcher_warning(&mod_name);
                     }
                     info!("Done");
+                    let db = LocalDatabase::fetch(&config.owml_path)?;
+                    let orphans = find_orphans(&config, &db)?;
+                    if !orphans.is_empty() {
+                        info!(
+                            "{} dependencies aren't needed anymore, use `owmods autoremove` to uninstall them",
+                            orphans.len()
+                        );
+                    }
                 } else {
                     error!("Mod {unique_name} Is Not Installed");
                 }
             }
         }
+        Commands::Tree {
+            unique_name,
+            dot,
+            json,
+        } => {
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let graph = DependencyGraph::from_local_db(&local_db);
+            if *dot {
+                println!("{}", graph.to_dot());
+            } else if *json {
+                println!("{}", graph.to_json()?);
+            } else {
+                let roots = if let Some(unique_name) = unique_name {
+                    if graph.get_node(unique_name).is_none() {
+                        error!("{unique_name} isn't installed");
+                        return Ok(());
+                    }
+                    vec![unique_name.as_str()]
+                } else {
+                    graph.roots()
+                };
+                let mut output = String::new();
+                for root in roots {
+                    write_tree(&graph, root, 0, &mut vec![], &mut output);
+                }
+                info!("{}", output.trim_end());
+            }
+        }
+        Commands::Why { unique_name } => {
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let graph = DependencyGraph::from_local_db(&local_db);
+            let chains = graph.why(unique_name);
+            if local_db.get_mod(unique_name).is_none() {
+                warn!("{unique_name} isn't installed");
+            }
+            if chains.is_empty() {
+                info!("No installed mods require {unique_name}");
+            } else {
+                info!(
+                    "{} is required by:{}",
+                    unique_name.bold(),
+                    format_list(&chains.iter().map(|c| c.join(" -> ")).collect::<Vec<_>>())
+                );
+            }
+        }
+        Commands::Autoremove { dry } => {
+            let db = LocalDatabase::fetch(&config.owml_path)?;
+            if *dry {
+                let orphans = find_orphans(&config, &db)?;
+                if orphans.is_empty() {
+                    info!("No unused dependencies found");
+                } else {
+                    let names = orphans
+                        .iter()
+                        .map(|m| format!("{} ({})", m.manifest.name, m.manifest.unique_name))
+                        .collect::<Vec<_>>();
+                    info!("Would uninstall:{}", format_list(&names));
+                }
+            } else {
+                let removed = autoremove(&config, &db)?;
+                if removed.is_empty() {
+                    info!("No unused dependencies found");
+                } else {
+                    for removed_mod in removed.iter() {
+                        if removed_mod.manifest.patcher.is_some() {
+                            show_pre_patcher_warning(&removed_mod.manifest.name);
+                        }
+                    }
+                    info!("Uninstalled {} unused dependencies", removed.len());
+                }
+            }
+        }
         Commands::Export => {
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
             println!("{}", export_mods(&local_db)?);
@@ -366,12 +511,226 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             file_path,
             disable_missing,
         } => {
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
             import_mods(&config, &local_db, &remote_db, file_path, *disable_missing).await?;
         }
+        Commands::Lock => {
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let lock = generate_lockfile(&config, &local_db, &remote_db).await?;
+            println!("{}", serde_json::to_string_pretty(&lock)?);
+        }
+        Commands::InstallLock { file_path, strict } => {
+            let lock = Lockfile::load(file_path)?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let drift = install_from_lock(&lock, &config, &local_db, &remote_db, *strict).await?;
+            if drift.is_empty() {
+                info!("Installed {} mod(s) from lockfile", lock.mods.len());
+            } else {
+                warn!(
+                    "Installed {} mod(s) from lockfile with {} difference(s), run with --strict to refuse instead",
+                    lock.mods.len(),
+                    drift.len()
+                );
+            }
+        }
+        Commands::Cache { action } => match action {
+            CacheActions::List => {
+                let archives = list_cache(&config)?;
+                if archives.is_empty() {
+                    info!("The cache is empty");
+                } else {
+                    for archive in archives.iter() {
+                        info!(
+                            "{} {} ({}, {})",
+                            archive.unique_name.bold(),
+                            archive.version,
+                            format_size(archive.size),
+                            &archive.hash[..archive.hash.len().min(12)]
+                        );
+                    }
+                    let total: u64 = archives.iter().map(|a| a.size).sum();
+                    info!(
+                        "{} archive(s), {} of {} MB used",
+                        archives.len(),
+                        format_size(total),
+                        config.max_cache_size_mb
+                    );
+                }
+            }
+            CacheActions::Clean => {
+                let freed = clean_cache(&config)?;
+                info!("Cleaned the cache, freed {}", format_size(freed));
+            }
+            CacheActions::Prune { lockfiles } => {
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                let lockfiles = lockfiles
+                    .iter()
+                    .map(|path| Lockfile::load(path))
+                    .collect::<Result<Vec<_>>>()?;
+                let freed = prune_cache(&config, &local_db, &lockfiles)?;
+                info!("Pruned the cache, freed {}", format_size(freed));
+            }
+        },
+        Commands::Profile { action } => match action {
+            ProfileActions::Save { name, no_settings } => {
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                let profile = Profile::from_local_db(name, &local_db, !*no_settings)?;
+                profile.save(&config)?;
+                info!(
+                    "Saved profile {} with {} enabled mod(s)",
+                    profile.name,
+                    profile.enabled.len()
+                );
+            }
+            ProfileActions::Apply { name } => {
+                let profile = Profile::load(name, &config)?;
+                let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                info!("Applying profile {}", profile.name);
+                let show_warnings_for =
+                    apply_profile(&profile, &config, &local_db, &remote_db).await?;
+                for mod_name in show_warnings_for {
+                    show_pre_patcher_warning(&mod_name);
+                }
+                info!("Done!");
+            }
+            ProfileActions::List => {
+                let profiles = list_profiles(&config)?;
+                if profiles.is_empty() {
+                    info!("No profiles saved, use `owmods profile save <name>` to create one");
+                } else {
+                    for profile in profiles {
+                        info!(
+                            "{} ({} enabled mod(s))",
+                            profile.name.bold(),
+                            profile.enabled.len()
+                        );
+                    }
+                }
+            }
+            ProfileActions::Delete { name } => {
+                delete_profile(name, &config)?;
+                info!("Deleted profile {name}");
+            }
+        },
+        Commands::Settings { action } => {
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let unique_name = match action {
+                SettingsActions::Get { unique_name, .. }
+                | SettingsActions::Set { unique_name, .. }
+                | SettingsActions::Reset { unique_name, .. } => unique_name,
+            };
+            let local_mod = local_db
+                .get_mod(unique_name)
+                .with_context(|| format!("Mod {unique_name} Is Not Installed"))?;
+            match action {
+                SettingsActions::Get { key: Some(key), .. } => {
+                    log_setting(&get_mod_setting(local_mod, key)?);
+                }
+                SettingsActions::Get { key: None, .. } => {
+                    let settings = get_mod_settings(local_mod)?;
+                    if settings.is_empty() {
+                        info!("{} has no settings", local_mod.manifest.name);
+                    }
+                    for setting in settings {
+                        log_setting(&setting);
+                    }
+                }
+                SettingsActions::Set { key, value, .. } => {
+                    let setting = get_mod_setting(local_mod, key)?;
+                    let value = setting.parse_value(value)?;
+                    set_mod_setting(local_mod, key, value.clone())?;
+                    info!("Set {key} to {value}");
+                }
+                SettingsActions::Reset { key, .. } => {
+                    reset_mod_setting(local_mod, key.as_deref())?;
+                    if let Some(key) = key {
+                        info!("Reset {key} to its default");
+                    } else {
+                        info!("Reset all settings of {}", local_mod.manifest.name);
+                    }
+                }
+            }
+        }
+        Commands::Snapshot { action } => match action {
+            SnapshotActions::Save { name, unique_names } => {
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                let snapshot = SettingsSnapshot::take(name, &local_db, unique_names)?;
+                snapshot.save(&config)?;
+                info!(
+                    "Saved snapshot {} with the settings of {} mod(s)",
+                    snapshot.name,
+                    snapshot.mods.len()
+                );
+            }
+            SnapshotActions::Restore { name } => {
+                let snapshot = SettingsSnapshot::load(name, &config)?;
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                let skipped = restore_snapshot(&snapshot, &local_db)?;
+                info!(
+                    "Restored the settings of {} mod(s) from {}",
+                    snapshot.mods.len() - skipped.len(),
+                    snapshot.name
+                );
+            }
+            SnapshotActions::Diff { name } => {
+                let snapshot = SettingsSnapshot::load(name, &config)?;
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                let diffs = snapshot.diff(&local_db)?;
+                if diffs.is_empty() {
+                    info!(
+                        "No differences, the current settings match {}",
+                        snapshot.name
+                    );
+                }
+                let show = |v: &Option<serde_json::Value>| {
+                    v.as_ref()
+                        .map(|v| v.to_string())
+                        .unwrap_or("(unset)".to_string())
+                };
+                for diff in diffs {
+                    info!(
+                        "{} {}: {} -> {}",
+                        diff.unique_name.bold(),
+                        diff.key,
+                        show(&diff.current).red(),
+                        show(&diff.snapshot).green()
+                    );
+                }
+            }
+            SnapshotActions::List => {
+                let snapshots = list_snapshots(&config)?;
+                if snapshots.is_empty() {
+                    info!("No snapshots saved, use `owmods snapshot save <name>` to create one");
+                } else {
+                    for snapshot in snapshots {
+                        info!("{} ({} mod(s))", snapshot.name.bold(), snapshot.mods.len());
+                    }
+                }
+            }
+            SnapshotActions::Delete { name } => {
+                delete_snapshot(name, &config)?;
+                info!("Deleted snapshot {name}");
+            }
+            SnapshotActions::Export { name, file_path } => {
+                let snapshot = SettingsSnapshot::load(name, &config)?;
+                snapshot.export(file_path)?;
+                info!("Exported {} to {}", snapshot.name, file_path.display());
+            }
+            SnapshotActions::Import { file_path, name } => {
+                let snapshot = import_snapshot(file_path, name.as_deref(), &config)?;
+                info!(
+                    "Imported snapshot {} with the settings of {} mod(s)",
+                    snapshot.name,
+                    snapshot.mods.len()
+                );
+            }
+        },
         Commands::Update { dry } => {
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
             let updated = update_all(&config, &local_db, &remote_db, *dry).await?;
             if updated {
@@ -382,9 +741,52 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 info!("No Updates Available!");
             }
         }
-        Commands::Enable { unique_name } | Commands::Disable { unique_name } => {
+        Commands::Pin {
+            unique_name,
+            version,
+        } => {
+            let mut new_config = Config::get(None)?;
+            let pin = version.clone().unwrap_or_else(|| PIN_HOLD.to_string());
+            new_config.pins.insert(unique_name.clone(), pin.clone());
+            new_config.save()?;
+            info!("Pinned {unique_name} to {pin}");
+        }
+        Commands::Unpin { unique_name } => {
+            let mut new_config = Config::get(None)?;
+            if new_config.pins.remove(unique_name).is_some() {
+                new_config.save()?;
+                info!("Unpinned {unique_name}");
+            } else {
+                warn!("{unique_name} isn't pinned");
+            }
+        }
+        Commands::Rollback { unique_name, list } => {
+            if *list {
+                let backups = list_backups(&config, Some(unique_name))?;
+                if backups.is_empty() {
+                    info!("No backups of {unique_name} found");
+                }
+                for backup in backups {
+                    let age = backup.created_time().elapsed().unwrap_or_default();
+                    info!(
+                        "{} ({} hour(s) ago)",
+                        backup.version,
+                        age.as_secs() / (60 * 60)
+                    );
+                }
+            } else {
+                let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                let restored = rollback(&config, &local_db, unique_name)?;
+                info!(
+                    "Rolled back {} to {}",
+                    restored.manifest.name, restored.manifest.version
+                );
+            }
+        }
+        Commands::Enable { unique_name } | Commands::Disable { unique_name, .. } => {
             let db = LocalDatabase::fetch(&config.owml_path)?;
-            let enable = matches!(cli.command, Commands::Enable { unique_name: _ });
+            let enable = matches!(cli.command, Commands::Enable { .. });
+            let cascade = matches!(cli.command, Commands::Disable { cascade: true, .. });
             let mut show_warnings_for: Vec<String> = vec![];
             if unique_name == "*" || unique_name == "all" {
                 for local_mod in db.valid() {
@@ -393,39 +795,100 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                         &db,
                         enable,
                         false,
+                        DependentsMode::Ignore,
                     )?);
                 }
             } else {
-                show_warnings_for = toggle_mod(unique_name, &db, enable, r)?;
+                show_warnings_for =
+                    toggle_mod(unique_name, &db, enable, r, dependents_mode(cascade))?;
             }
             for mod_name in show_warnings_for {
                 show_pre_patcher_warning(&mod_name);
             }
         }
-        Commands::LogServer { port } => {
-            start_just_logs(port).await?;
+        Commands::LogServer {
+            port,
+            filter,
+            overflow,
+        } => {
+            let filter = parse_log_filter(filter.as_deref())?;
+            let overflow = parse_overflow_policy(overflow.as_deref(), &config)?;
+            start_just_logs(port, &config, filter.as_ref(), overflow).await?;
         }
+        Commands::Logs { action } => match action {
+            LogsActions::List => {
+                let sessions = list_sessions(&config)?;
+                if sessions.is_empty() {
+                    info!("No log sessions recorded yet");
+                }
+                for session in sessions {
+                    let age = session.started_time().elapsed().unwrap_or_default();
+                    info!(
+                        "{} ({} hour(s) ago, {})",
+                        session.id.bold(),
+                        age.as_secs() / (60 * 60),
+                        format_size(session.size)
+                    );
+                }
+            }
+            LogsActions::Show { session } => {
+                let session = get_session(&config, session)?;
+                let mut grouper = ExceptionGrouper::default();
+                // The time each message that hasn't been printed yet was received
+                let mut times = VecDeque::new();
+                for recorded in session.read_messages()? {
+                    let elapsed = recorded.timestamp.saturating_sub(session.started);
+                    let time = format!(
+                        "+{:02}:{:02}.{:03}",
+                        elapsed / 60000,
+                        (elapsed / 1000) % 60,
+                        elapsed % 1000
+                    );
+                    let was_grouping = grouper.is_grouping();
+                    let grouped = grouper.push(recorded.message);
+                    // Stack frames merged into an exception use the exception's time
+                    if !(was_grouping && grouped.is_empty()) {
+                        times.push_back(time);
+                    }
+                    for grouped in grouped {
+                        handle_game_log(&grouped.message, times.pop_front().as_deref());
+                    }
+                }
+                if let Some(grouped) = grouper.flush() {
+                    handle_game_log(&grouped.message, times.pop_front().as_deref());
+                }
+                log_exception_counts(grouper.counts());
+            }
+            LogsActions::Replay { session, instant } => {
+                let path = PathBuf::from(session);
+                let messages = if path.is_file() {
+                    read_session_file(&path)
+                        .with_context(|| format!("Couldn't read {}", path.display()))?
+                } else {
+                    get_session(&config, session)?.read_messages()?
+                };
+                info!("Replaying {} message(s)", messages.len());
+                replay_logs(messages, *instant).await?;
+            }
+        },
         Commands::Run {
             force,
             port,
             no_server,
             new_window,
+            filter,
+            overflow,
         } => {
+            let filter = parse_log_filter(filter.as_deref())?;
+            let overflow = parse_overflow_policy(overflow.as_deref(), &config)?;
             info!("Attempting to launch game...");
-            let mut local_db = LocalDatabase::fetch(&config.owml_path)?;
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await;
-            if let Ok(remote_db) = remote_db {
-                local_db.validate_updates(&remote_db);
-            }
-            let mut flag = false;
-            for local_mod in local_db.invalid() {
-                flag = true;
-                log_mod_validation_errors(local_mod, &local_db);
-            }
-            if !*force && flag {
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await.ok();
+            let report = run_prelaunch_checks(&config, &local_db, remote_db.as_ref());
+            log_prelaunch_findings(&report, &local_db);
+            if report.blocks_launch(*force) {
                 error!("Errors found, refusing to launch");
-                info!("Run `owmods validate` to see issues");
-                info!("...or run with -f to launch anyway");
+                info!("Fix the issues above or run with -f to launch anyway");
                 return Ok(());
             }
             let no_server = *no_server || *new_window;
@@ -433,7 +896,16 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 warn!("Skipping option --new-window as this is a Windows only flag");
             }
             let port = if no_server { None } else { Some(port) };
-            start_game(&local_db, &config, port, *new_window).await?;
+            start_game(
+                &local_db,
+                &report,
+                &config,
+                port,
+                *new_window,
+                filter.as_ref(),
+                overflow,
+            )
+            .await?;
         }
         Commands::Open { identifier } => {
             info!("Opening {identifier}");
@@ -442,18 +914,18 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
         }
         Commands::Readme { unique_name } => {
             info!("Opening README for {unique_name}");
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             open_readme(unique_name, &remote_db)?;
         }
         Commands::Github { unique_name } => {
             info!("Opening GitHub repo for {unique_name}");
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             open_github(unique_name, &remote_db)?;
         }
         Commands::Validate { fix } => {
             let mut local_db = LocalDatabase::fetch(&config.owml_path)?;
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
-            local_db.validate_updates(&remote_db);
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
+            local_db.validate_updates_with_pins(&remote_db, &config);
             if *fix {
                 info!("Trying to fix dependency issues...");
                 for local_mod in local_db.active() {
@@ -475,8 +947,60 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 info!("No issues found!");
             }
         }
+        Commands::Bundle {
+            output,
+            no_anonymize,
+        } => {
+            let output = output.clone().unwrap_or_else(|| {
+                let time = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                PathBuf::from(format!("owmods-bundle-{time}.zip"))
+            });
+            let local_db = LocalDatabase::fetch(&config.owml_path)?;
+            let files = create_bundle(&config, &local_db, &output, !*no_anonymize)?;
+            info!("Bundled {} into {}", files.join(", "), output.display());
+            if *no_anonymize {
+                warn!("This bundle isn't anonymized, it may contain your home folder and username");
+            }
+        }
+        Commands::Doctor => {
+            match LocalDatabase::get_owml(&config.owml_path) {
+                Some(owml) => info!("OWML v{} at {}", owml.manifest.version, config.owml_path),
+                None => error!("OWML isn't installed, run `owmods setup` to install it"),
+            }
+            let local_db = LocalDatabase::fetch(&config.owml_path).unwrap_or_default();
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await.ok();
+            let report = run_prelaunch_checks(&config, &local_db, remote_db.as_ref());
+            log_prelaunch_findings(&report, &local_db);
+            log_game_install(&config);
+            for (unique_name, warning) in report.unseen_warnings() {
+                let name = local_db
+                    .get_mod(unique_name)
+                    .map(|m| m.manifest.name.as_str())
+                    .unwrap_or(unique_name);
+                info!(
+                    "{name} has a warning that will be shown on the next launch: {}",
+                    warning.title
+                );
+            }
+            if report.has_errors() {
+                error!(
+                    "Found problems that will stop the game from launching, fix the issues above"
+                );
+            } else if report
+                .findings
+                .iter()
+                .any(|f| f.severity == PrelaunchSeverity::Warning)
+            {
+                warn!("Found some issues, but the game should still launch");
+            } else {
+                info!("No problems found!");
+            }
+        }
         Commands::ClearWarnings => {
-            let mut new_config = config.clone();
+            let mut new_config = Config::get(None)?;
             new_config.viewed_alerts = vec![];
             new_config.save()?;
             info!("Warnings Cleared");
@@ -487,7 +1011,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
             clap_complete::generate(*shell, &mut cmd, name, &mut std::io::stdout());
         }
         Commands::Protocol { uri } => {
-            let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+            let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
             let local_db = LocalDatabase::fetch(&config.owml_path)?;
             let payload = ProtocolPayload::parse(uri);
             match payload.verb {
@@ -512,7 +1036,8 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                         info!("Installing from {}", payload.payload);
                         match payload.verb {
                             ProtocolVerb::InstallURL => {
-                                install_mod_from_url(&payload.payload, None, &config, &local_db)
+                                let (url, expected_hash) = split_url_hash(&payload.payload);
+                                install_mod_from_url(url, None, expected_hash, &config, &local_db)
                                     .await?;
                             }
                             ProtocolVerb::InstallZip => {
@@ -533,11 +1058,35 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                     let target_mod = local_db.get_mod(&payload.payload);
                     if let Some(target_mod) = target_mod {
                         info!("Launching game with {}", target_mod.manifest.name);
-                        toggle_mod(&target_mod.manifest.unique_name, &local_db, true, true)?;
+                        toggle_mod(
+                            &target_mod.manifest.unique_name,
+                            &local_db,
+                            true,
+                            true,
+                            DependentsMode::Ignore,
+                        )?;
                     } else {
                         warn!("Mod {} not found, ignoring", payload.payload);
                     }
-                    start_game(&local_db, &config, None, false).await?;
+                    // Enabling the mod may have changed what's valid
+                    let local_db = LocalDatabase::fetch(&config.owml_path)?;
+                    let report = run_prelaunch_checks(&config, &local_db, None);
+                    log_prelaunch_findings(&report, &local_db);
+                    if report.blocks_launch(false) {
+                        error!("Errors found, refusing to launch");
+                        info!("Fix the issues above or run `owmods run -f` to launch anyway");
+                        return Ok(());
+                    }
+                    start_game(
+                        &local_db,
+                        &report,
+                        &config,
+                        None,
+                        false,
+                        None,
+                        config.log_overflow_policy,
+                    )
+                    .await?;
                 }
                 ProtocolVerb::Unknown => {
                     error!("Unknown install type, ignoring");
@@ -559,7 +1108,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 println!("{serialized}");
             }
             "remote" => {
-                let db = RemoteDatabase::fetch(&config.database_url).await?;
+                let db = RemoteDatabase::fetch_or_cached(&config).await?;
                 let mods = db.mods.values().collect::<Vec<_>>();
                 let serialized = if *minify {
                     serde_json::to_string(&mods)?
@@ -569,7 +1118,7 @@ async fn run_from_cli(cli: BaseCli) -> Result<()> {
                 println!("{serialized}");
             }
             _ => {
-                let remote_db = RemoteDatabase::fetch(&config.database_url).await?;
+                let remote_db = RemoteDatabase::fetch_or_cached(&config).await?;
                 let remote_mod = remote_db.get_mod(unique_name.as_ref().unwrap());
                 let serialized = if *minify {
                     serde_json::to_string(&remote_mod)?
@@ -590,6 +1139,112 @@ fn format_list(l: &[String]) -> String {
     })
 }
 
+fn format_size(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MB {
+        format!("{:.1} MB", bytes as f64 / MB)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+fn write_tree<'a>(
+    graph: &'a DependencyGraph,
+    unique_name: &'a str,
+    depth: usize,
+    path: &mut Vec<&'a str>,
+    out: &mut String,
+) {
+    let status = match graph.get_node(unique_name) {
+        Some(node) if !node.installed => " (missing)".red().to_string(),
+        Some(node) if !node.enabled => " (disabled)".yellow().to_string(),
+        _ => String::new(),
+    };
+    let cycle = if path.contains(&unique_name) {
+        " (cycle)".yellow().to_string()
+    } else {
+        String::new()
+    };
+    let _ = writeln!(out, "{}{unique_name}{status}{cycle}", "  ".repeat(depth));
+    if !cycle.is_empty() {
+        return;
+    }
+    path.push(unique_name);
+    for dep in graph.direct_dependencies(unique_name) {
+        write_tree(graph, dep, depth + 1, path, out);
+    }
+    path.pop();
+}
+
+fn log_install_plan(plan: &InstallPlan) {
+    for planned_mod in plan.mods.iter() {
+        let action = match planned_mod.installed_version.as_ref() {
+            Some(installed) if *installed == planned_mod.version => "Reinstall".to_string(),
+            Some(installed) => format!("Update from {installed}"),
+            None => "Install".to_string(),
+        };
+        let reason = match &planned_mod.reason {
+            PlanReason::Requested => String::new(),
+            PlanReason::Dependency(of) => format!(", required by {of}"),
+        };
+        let mut size = planned_mod
+            .size
+            .map(|s| format_size(s as u64))
+            .unwrap_or_else(|| "not downloaded".to_string());
+        if !planned_mod.dependencies_known {
+            size.push_str(", dependencies unknown");
+        }
+        info!(
+            "{action} {} {} ({size}{reason})",
+            planned_mod.unique_name.bold(),
+            planned_mod.version
+        );
+    }
+    let unknown = if plan.unknown_size_count == 0 {
+        String::new()
+    } else {
+        format!(" + {} mod(s) to download", plan.unknown_size_count)
+    };
+    info!(
+        "{} mod(s) to install, {} downloaded{unknown}",
+        plan.mods.len(),
+        format_size(plan.total_size as u64)
+    );
+    for problem in plan.problems.iter() {
+        if problem.is_blocking() {
+            error!("{problem}");
+        } else {
+            warn!("{problem}");
+        }
+    }
+}
+
+fn dependents_mode(cascade: bool) -> DependentsMode {
+    if cascade {
+        DependentsMode::Cascade
+    } else {
+        DependentsMode::Refuse
+    }
+}
+
+fn log_setting(setting: &ModSetting) {
+    let mut line = format!("{} = {}", setting.key.bold(), setting.value);
+    if !setting.is_default() {
+        line.push_str(&format!(" (default: {})", setting.default_value));
+    }
+    line.push_str(&format!(" [{:?}]", setting.kind).to_lowercase());
+    if let Some(options) = &setting.options {
+        line.push_str(&format!(" options: {}", options.join(", ")));
+    }
+    if let (Some(min), Some(max)) = (setting.min, setting.max) {
+        line.push_str(&format!(" range: {min} - {max}"));
+    }
+    info!("{line}");
+    if let Some(tooltip) = &setting.tooltip {
+        info!("    {tooltip}");
+    }
+}
+
 fn yes_no(v: bool) -> String {
     if v {
         "Yes".to_string()
@@ -0,0 +1,240 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use anyhow::Result;
+use log::{debug, info};
+
+use crate::{
+    config::Config,
+    constants::DEPENDENCY_INSTALLS_NAME,
+    db::LocalDatabase,
+    file::{deserialize_from_json, serialize_to_json},
+    graph::{DependencyGraph, DependentsMode},
+    mods::local::LocalMod,
+    remove::remove_mod,
+};
+
+/// Get the path to the file that records which mods were installed only as dependencies
+pub fn get_dependency_installs_path(config: &Config) -> PathBuf {
+    config.data_dir().join(DEPENDENCY_INSTALLS_NAME)
+}
+
+/// Get the unique names of every mod that was installed only as a dependency.
+///
+/// ## Errors
+///
+/// If the record exists but we can't read it.
+///
+pub fn get_dependency_installs(config: &Config) -> Result<BTreeSet<String>> {
+    let path = get_dependency_installs_path(config);
+    if path.is_file() {
+        deserialize_from_json(&path)
+    } else {
+        Ok(BTreeSet::new())
+    }
+}
+
+fn save_dependency_installs(config: &Config, installs: &BTreeSet<String>) -> Result<()> {
+    serialize_to_json(installs, &get_dependency_installs_path(config), true)
+}
+
+/// Record that the given mods were installed only because other mods depend on them,
+/// they'll be picked up by [autoremove] once nothing needs them anymore.
+///
+/// ## Errors
+///
+/// If we can't read or save the record.
+///
+pub fn record_dependency_installs(config: &Config, unique_names: &[String]) -> Result<()> {
+    if unique_names.is_empty() {
+        return Ok(());
+    }
+    let mut installs = get_dependency_installs(config)?;
+    installs.extend(unique_names.iter().cloned());
+    save_dependency_installs(config, &installs)
+}
+
+/// Record that the given mod was installed directly by the user, so [autoremove] should leave it alone.
+///
+/// ## Errors
+///
+/// If we can't read or save the record.
+///
+pub fn record_explicit_install(config: &Config, unique_name: &str) -> Result<()> {
+    let mut installs = get_dependency_installs(config)?;
+    if installs.remove(unique_name) {
+        debug!("{unique_name} was installed directly, no longer marking it as a dependency");
+        save_dependency_installs(config, &installs)?;
+    }
+    Ok(())
+}
+
+/// Find every mod that was installed only as a dependency and isn't needed by any other installed mod.
+/// Mods that are only needed by other orphans are included too.
+///
+/// ## Errors
+///
+/// If we can't read the record of dependency installs.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::autoremove::find_orphans;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// for orphan in find_orphans(&config, &local_db).unwrap() {
+///     println!("{} isn't needed anymore", orphan.manifest.name);
+/// }
+/// ```
+///
+pub fn find_orphans<'a>(config: &Config, local_db: &'a LocalDatabase) -> Result<Vec<&'a LocalMod>> {
+    let installs = get_dependency_installs(config)?;
+    let graph = DependencyGraph::from_local_db(local_db);
+    let mut orphans: BTreeSet<&str> = BTreeSet::new();
+    loop {
+        let new_orphans = installs
+            .iter()
+            .map(|m| m.as_str())
+            .filter(|m| !orphans.contains(m) && local_db.get_mod(m).is_some())
+            .filter(|m| {
+                graph
+                    .direct_dependents(m)
+                    .iter()
+                    .all(|dependent| orphans.contains(dependent))
+            })
+            .collect::<Vec<_>>();
+        if new_orphans.is_empty() {
+            break;
+        }
+        orphans.extend(new_orphans);
+    }
+    Ok(orphans
+        .into_iter()
+        .filter_map(|m| local_db.get_mod(m))
+        .collect())
+}
+
+/// Uninstall every mod found by [find_orphans], and forget about any recorded mods that aren't installed anymore.
+///
+/// ## Returns
+///
+/// The mods that were removed, mods in this list that have a `patcher` **should have a warning shown to the user**
+/// telling them to check the mod's README, see [remove_mod].
+///
+/// ## Errors
+///
+/// If we can't read or save the record, or can't remove one of the mods.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::autoremove::autoremove;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// let removed = autoremove(&config, &local_db).unwrap();
+/// println!("Removed {} unused dependencies", removed.len());
+/// ```
+///
+pub fn autoremove(config: &Config, local_db: &LocalDatabase) -> Result<Vec<LocalMod>> {
+    let orphans = find_orphans(config, local_db)?;
+    for orphan in orphans.iter() {
+        info!("Removing unused dependency {}", orphan.manifest.name);
+        remove_mod(orphan, local_db, false, DependentsMode::Ignore)?;
+    }
+    let removed = orphans.into_iter().cloned().collect::<Vec<_>>();
+    let mut installs = get_dependency_installs(config)?;
+    installs.retain(|m| {
+        local_db.get_mod(m).is_some() && !removed.iter().any(|r| &r.manifest.unique_name == m)
+    });
+    save_dependency_installs(config, &installs)?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::test_utils::TestContext;
+
+    use super::*;
+
+    fn insert_with_deps(ctx: &mut TestContext, num: u8, deps: &[u8]) {
+        let mut local_mod = LocalMod::get_test(num);
+        local_mod.manifest.dependencies =
+            Some(deps.iter().map(|d| format!("Example.TestMod{d}")).collect());
+        ctx.insert_test_mod(&local_mod);
+    }
+
+    #[test]
+    fn test_record_installs() {
+        let ctx = TestContext::new();
+        assert!(get_dependency_installs(&ctx.config).unwrap().is_empty());
+        record_dependency_installs(
+            &ctx.config,
+            &[
+                "Example.TestMod1".to_string(),
+                "Example.TestMod2".to_string(),
+            ],
+        )
+        .unwrap();
+        record_explicit_install(&ctx.config, "Example.TestMod1").unwrap();
+        let installs = get_dependency_installs(&ctx.config).unwrap();
+        assert_eq!(
+            installs.into_iter().collect::<Vec<_>>(),
+            vec!["Example.TestMod2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let mut ctx = TestContext::new();
+        // 1 -> 2 -> 3 and 5, 2, 3, and 5 were installed as dependencies but 1 still needs 2 and 3
+        insert_with_deps(&mut ctx, 1, &[2]);
+        insert_with_deps(&mut ctx, 2, &[3]);
+        insert_with_deps(&mut ctx, 3, &[]);
+        insert_with_deps(&mut ctx, 5, &[]);
+        record_dependency_installs(
+            &ctx.config,
+            &[
+                "Example.TestMod2".to_string(),
+                "Example.TestMod3".to_string(),
+                "Example.TestMod5".to_string(),
+            ],
+        )
+        .unwrap();
+        let orphans = find_orphans(&ctx.config, &ctx.local_db).unwrap();
+        let names = orphans
+            .iter()
+            .map(|m| m.manifest.unique_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Example.TestMod5"]);
+
+        ctx.local_db.mods.remove("Example.TestMod1");
+        let orphans = find_orphans(&ctx.config, &ctx.local_db).unwrap();
+        assert_eq!(orphans.len(), 3);
+    }
+
+    #[test]
+    fn test_autoremove() {
+        let mut ctx = TestContext::new();
+        let new_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        record_dependency_installs(
+            &ctx.config,
+            &[
+                new_mod.manifest.unique_name.clone(),
+                "Example.NotInstalled".to_string(),
+            ],
+        )
+        .unwrap();
+        let removed = autoremove(&ctx.config, &ctx.local_db).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!ctx.get_test_path("Bwc9876.TimeSaver").is_dir());
+        assert!(get_dependency_installs(&ctx.config).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    fs::{create_dir_all, read_dir, remove_dir, remove_dir_all, remove_file},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use glob::glob;
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    constants::BACKUPS_FOLDER_NAME,
+    db::LocalDatabase,
+    file::{check_file_matches_paths, copy_dir_all, sanitize_file_name},
+    mods::local::{get_paths_to_preserve, LocalMod},
+    remove::remove_old_mod_files,
+};
+
+/// A copy of a mod's folder taken before it was updated
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModBackup {
+    /// The unique name of the backed up mod
+    pub unique_name: String,
+    /// The version of the mod when it was backed up
+    pub version: String,
+    /// Where the backup is stored
+    pub path: PathBuf,
+    /// When the backup was taken, in milliseconds since the unix epoch
+    pub created: u64,
+}
+
+impl ModBackup {
+    fn read(path: PathBuf) -> Option<Self> {
+        let created = path.file_name()?.to_str()?.parse::<u64>().ok()?;
+        let backed_up = LocalDatabase::read_local_mod(&path.join("manifest.json")).ok()?;
+        Some(Self {
+            unique_name: backed_up.manifest.unique_name,
+            version: backed_up.manifest.version,
+            path,
+            created,
+        })
+    }
+
+    /// Get when this backup was taken
+    pub fn created_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.created)
+    }
+}
+
+/// Get the folder mod backups are stored in
+pub fn get_backups_dir(config: &Config) -> PathBuf {
+    config.data_dir().join(BACKUPS_FOLDER_NAME)
+}
+
+fn get_mod_backups_dir(config: &Config, unique_name: &str) -> PathBuf {
+    get_backups_dir(config).join(sanitize_file_name(unique_name))
+}
+
+/// List the backups of a mod, newest first. If `unique_name` is `None` backups of every mod are listed.
+///
+/// ## Errors
+///
+/// If we can't read the backups folder.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::backups::list_backups;
+///
+/// let config = Config::get(None).unwrap();
+/// for backup in list_backups(&config, Some("Bwc9876.TimeSaver")).unwrap() {
+///     println!("{} {}", backup.unique_name, backup.version);
+/// }
+/// ```
+///
+pub fn list_backups(config: &Config, unique_name: Option<&str>) -> Result<Vec<ModBackup>> {
+    let mod_dirs = if let Some(unique_name) = unique_name {
+        vec![get_mod_backups_dir(config, unique_name)]
+    } else {
+        let backups_dir = get_backups_dir(config);
+        if !backups_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        read_dir(&backups_dir)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<_, _>>()?
+    };
+    let mut backups: Vec<ModBackup> = vec![];
+    for mod_dir in mod_dirs.iter().filter(|d| d.is_dir()) {
+        for entry in read_dir(mod_dir)? {
+            if let Some(backup) = ModBackup::read(entry?.path()) {
+                if unique_name.is_none_or(|n| n == backup.unique_name) {
+                    backups.push(backup);
+                }
+            }
+        }
+    }
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created));
+    Ok(backups)
+}
+
+/// Whether backups are enabled, set `max_backups` to 0 in the config to disable them
+pub fn backups_enabled(config: &Config) -> bool {
+    config.max_backups != 0
+}
+
+/// Copy a mod's folder into the backups folder, then remove its oldest backups so only `max_backups` are kept.
+/// This is automatically called before a mod is updated.
+///
+/// ## Returns
+///
+/// The new backup, or `None` if backups are disabled.
+///
+/// ## Errors
+///
+/// If we can't copy the mod's folder.
+///
+pub fn backup_mod(config: &Config, local_mod: &LocalMod) -> Result<Option<ModBackup>> {
+    if !backups_enabled(config) {
+        return Ok(None);
+    }
+    let backup = copy_to_backups(config, local_mod)?;
+    remove_old_backups(config, &local_mod.manifest.unique_name)?;
+    Ok(Some(backup))
+}
+
+fn copy_to_backups(config: &Config, local_mod: &LocalMod) -> Result<ModBackup> {
+    let unique_name = &local_mod.manifest.unique_name;
+    let mut created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let mod_backups_dir = get_mod_backups_dir(config, unique_name);
+    while mod_backups_dir.join(created.to_string()).exists() {
+        created += 1;
+    }
+    let target = mod_backups_dir.join(created.to_string());
+    debug!(
+        "Backing up {} {} to {}",
+        unique_name,
+        local_mod.manifest.version,
+        target.display()
+    );
+    create_dir_all(&target)?;
+    if let Err(why) = copy_dir_all(Path::new(&local_mod.mod_path), &target) {
+        remove_dir_all(&target).ok();
+        return Err(why.context(format!("Failed to back up {unique_name}")));
+    }
+    Ok(ModBackup {
+        unique_name: unique_name.clone(),
+        version: local_mod.manifest.version.clone(),
+        path: target,
+        created,
+    })
+}
+
+fn remove_old_backups(config: &Config, unique_name: &str) -> Result<()> {
+    for old_backup in list_backups(config, Some(unique_name))?
+        .iter()
+        .skip(config.max_backups as usize)
+    {
+        delete_backup(old_backup)?;
+    }
+    Ok(())
+}
+
+/// Delete a backup
+///
+/// ## Errors
+///
+/// If we can't delete the backup's folder
+///
+pub fn delete_backup(backup: &ModBackup) -> Result<()> {
+    remove_dir_all(&backup.path)
+        .with_context(|| format!("Failed to remove backup {}", backup.path.display()))?;
+    if let Some(parent) = backup.path.parent() {
+        // Only removes the folder if it's empty
+        remove_dir(parent).ok();
+    }
+    Ok(())
+}
+
+/// Restore a backup over an installed mod, files the mod wants to preserve (like `config.json`) are kept as they are now.
+///
+/// ## Returns
+///
+/// The restored mod
+///
+/// ## Errors
+///
+/// If we can't remove the mod's current files or copy the backup.
+///
+pub fn restore_backup(backup: &ModBackup, local_mod: &LocalMod) -> Result<LocalMod> {
+    let mod_path = PathBuf::from(&local_mod.mod_path);
+    // Stage the backup first so a missing or broken backup doesn't leave the mod wiped
+    let temp_dir = tempfile::TempDir::new()?;
+    copy_dir_all(&backup.path, temp_dir.path())
+        .with_context(|| format!("Failed to read backup {}", backup.path.display()))?;
+    let preserve_paths = get_paths_to_preserve(Some(local_mod));
+    for entry in
+        glob(temp_dir.path().join("**").join("*").to_str().unwrap())?.filter_map(Result::ok)
+    {
+        let relative_path = entry.strip_prefix(temp_dir.path())?;
+        let target = mod_path.join(relative_path);
+        if entry.is_file()
+            && check_file_matches_paths(relative_path, &preserve_paths)
+            && target.exists()
+        {
+            remove_file(&entry)?;
+        }
+    }
+    remove_old_mod_files(local_mod)?;
+    copy_dir_all(temp_dir.path(), &mod_path)?;
+    temp_dir.close()?;
+    LocalDatabase::read_local_mod(&mod_path.join("manifest.json"))
+}
+
+/// Roll a mod back to the version it was at before it was last updated.
+/// The newest backup with a different version than the one installed is restored.
+/// The installed version is backed up first so the rollback can be undone, then old backups are removed so only `max_backups` are kept.
+///
+/// ## Returns
+///
+/// The restored mod
+///
+/// ## Errors
+///
+/// If the mod isn't installed, there's no backup of a different version, or we can't restore the backup.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::backups::rollback;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// let restored = rollback(&config, &local_db, "Bwc9876.TimeSaver").unwrap();
+/// println!("Rolled back to {}", restored.manifest.version);
+/// ```
+///
+pub fn rollback(config: &Config, local_db: &LocalDatabase, unique_name: &str) -> Result<LocalMod> {
+    let local_mod = local_db
+        .get_mod(unique_name)
+        .with_context(|| format!("Mod {unique_name} is not installed"))?;
+    let backup = list_backups(config, Some(unique_name))?
+        .into_iter()
+        .find(|b| b.version != local_mod.manifest.version)
+        .with_context(|| {
+            format!(
+                "No backup of {unique_name} from before version {} found",
+                local_mod.manifest.version
+            )
+        })?;
+    info!(
+        "Rolling back {} from {} to {}",
+        local_mod.manifest.name, local_mod.manifest.version, backup.version
+    );
+    if backups_enabled(config) {
+        copy_to_backups(config, local_mod)?;
+    }
+    let restored = restore_backup(&backup, local_mod)?;
+    remove_old_backups(config, unique_name)?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs::{read_to_string, write};
+
+    use crate::{file::serialize_to_json, test_utils::TestContext};
+
+    use super::*;
+
+    fn set_version(local_mod: &LocalMod, version: &str) -> LocalMod {
+        let mut manifest = local_mod.manifest.clone();
+        manifest.version = version.to_string();
+        serialize_to_json(
+            &manifest,
+            &PathBuf::from(&local_mod.mod_path).join("manifest.json"),
+            false,
+        )
+        .unwrap();
+        let mut new_mod = local_mod.clone();
+        new_mod.manifest = manifest;
+        new_mod
+    }
+
+    #[test]
+    fn test_backup_and_list() {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        let backup = backup_mod(&ctx.config, &local_mod).unwrap().unwrap();
+        assert!(backup.path.join("manifest.json").is_file());
+        let backups = list_backups(&ctx.config, Some("Bwc9876.TimeSaver")).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].version, local_mod.manifest.version);
+        assert_eq!(list_backups(&ctx.config, None).unwrap().len(), 1);
+        assert!(list_backups(&ctx.config, Some("Example.Other"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_backup_retention() {
+        let mut ctx = TestContext::new();
+        ctx.config.max_backups = 2;
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        for version in ["0.0.1", "0.0.2", "0.0.3"] {
+            let local_mod = set_version(&local_mod, version);
+            backup_mod(&ctx.config, &local_mod).unwrap();
+        }
+        let backups = list_backups(&ctx.config, Some("Bwc9876.TimeSaver")).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].version, "0.0.3");
+        assert_eq!(backups[1].version, "0.0.2");
+    }
+
+    #[test]
+    fn test_backups_disabled() {
+        let mut ctx = TestContext::new();
+        ctx.config.max_backups = 0;
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        assert!(backup_mod(&ctx.config, &local_mod).unwrap().is_none());
+        assert!(list_backups(&ctx.config, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        let old_version = local_mod.manifest.version.clone();
+        backup_mod(&ctx.config, &local_mod).unwrap();
+        let mod_path = PathBuf::from(&local_mod.mod_path);
+        write(mod_path.join("config.json"), "{\"enabled\":false}").unwrap();
+        write(mod_path.join("new_file.txt"), "new").unwrap();
+        set_version(&local_mod, "9.9.9");
+        ctx.fetch_local_db();
+        let restored = rollback(&ctx.config, &ctx.local_db, "Bwc9876.TimeSaver").unwrap();
+        assert_eq!(restored.manifest.version, old_version);
+        assert!(!mod_path.join("new_file.txt").exists());
+        assert_eq!(
+            read_to_string(mod_path.join("config.json")).unwrap(),
+            "{\"enabled\":false}"
+        );
+        let backups = list_backups(&ctx.config, None).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].version, "9.9.9");
+        assert!(backups[0].path.join("new_file.txt").is_file());
+        assert_eq!(backups[1].version, old_version);
+    }
+
+    #[test]
+    fn test_rollback_retention() {
+        let mut ctx = TestContext::new();
+        ctx.config.max_backups = 1;
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        let old_version = local_mod.manifest.version.clone();
+        backup_mod(&ctx.config, &local_mod).unwrap();
+        set_version(&local_mod, "9.9.9");
+        ctx.fetch_local_db();
+        let restored = rollback(&ctx.config, &ctx.local_db, "Bwc9876.TimeSaver").unwrap();
+        assert_eq!(restored.manifest.version, old_version);
+        let backups = list_backups(&ctx.config, None).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].version, "9.9.9");
+    }
+
+    #[test]
+    fn test_restore_missing_backup() {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        let backup = backup_mod(&ctx.config, &local_mod).unwrap().unwrap();
+        remove_dir_all(&backup.path).unwrap();
+        assert!(restore_backup(&backup, &local_mod).is_err());
+        assert!(PathBuf::from(&local_mod.mod_path)
+            .join("manifest.json")
+            .is_file());
+    }
+
+    #[test]
+    fn test_rollback_no_backup() {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        backup_mod(&ctx.config, &local_mod).unwrap();
+        // The only backup is of the installed version
+        assert!(rollback(&ctx.config, &ctx.local_db, "Bwc9876.TimeSaver").is_err());
+        assert!(rollback(&ctx.config, &ctx.local_db, "Example.Missing").is_err());
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    fs::{read_to_string, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use directories::BaseDirs;
+use glob::glob;
+use log::debug;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    config::Config, constants::MANAGER_LOGS_FOLDER_NAME, db::LocalDatabase, file::get_app_path,
+    mods::local::UnsafeLocalMod, owml::OWMLConfig, sessions::list_sessions,
+    validate::ModValidationError,
+};
+
+const REDACTED: &str = "<redacted>";
+
+/// Replaces the user's home folder and username in text so it can be shared.
+/// The username is only replaced where it's a whole folder in a path, so mod names and log messages that happen to contain it are left alone.
+struct Anonymizer {
+    home: Vec<String>,
+    user: Option<String>,
+    path_component: Regex,
+}
+
+impl Anonymizer {
+    fn new(home: Option<&Path>, user: Option<&str>) -> Self {
+        let home = home
+            .and_then(|h| h.to_str())
+            .filter(|h| h.len() > 1)
+            .map(|h| {
+                // Paths can use either separator, and backslashes are escaped in JSON
+                let forward = h.replace('\\', "/");
+                let back = forward.replace('/', "\\");
+                let escaped = back.replace('\\', "\\\\");
+                let mut home = vec![escaped, back, forward];
+                home.dedup();
+                home
+            })
+            .unwrap_or_default();
+        let user = user
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string);
+        // A path separator followed by everything up to the next separator or the end of the path
+        let path_component = Regex::new(r#"([/\\])([^/\\\s"']+)"#).unwrap();
+        Self {
+            home,
+            user,
+            path_component,
+        }
+    }
+
+    fn from_env() -> Self {
+        let dirs = BaseDirs::new();
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok();
+        Self::new(dirs.as_ref().map(|d| d.home_dir()), user.as_deref())
+    }
+
+    fn apply(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for home in self.home.iter() {
+            out = out.replace(home.as_str(), "~");
+        }
+        if let Some(user) = &self.user {
+            out = self
+                .path_component
+                .replace_all(&out, |caps: &Captures| {
+                    if caps[2].eq_ignore_ascii_case(user) {
+                        format!("{}<user>", &caps[1])
+                    } else {
+                        caps[0].to_string()
+                    }
+                })
+                .into_owned();
+        }
+        out
+    }
+}
+
+/// An installed mod as it's listed in a support bundle
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BundledMod {
+    unique_name: String,
+    name: String,
+    version: Option<String>,
+    enabled: bool,
+    errors: Vec<ModValidationError>,
+}
+
+impl From<&UnsafeLocalMod> for BundledMod {
+    fn from(local_mod: &UnsafeLocalMod) -> Self {
+        let version = match local_mod {
+            UnsafeLocalMod::Valid(m) => Some(m.manifest.version.clone()),
+            UnsafeLocalMod::Invalid(_) => None,
+        };
+        Self {
+            unique_name: local_mod.get_unique_name().clone(),
+            name: local_mod.get_name().clone(),
+            version,
+            enabled: local_mod.get_enabled(),
+            errors: local_mod.get_errs().into_iter().cloned().collect(),
+        }
+    }
+}
+
+fn get_latest_manager_log(logs_dir: &Path) -> Option<PathBuf> {
+    let pattern = logs_dir.join("*").join("*.log");
+    // Logs are stored as YYYY-MM-DD/HH-MM-SS.log so sorting the paths sorts them by when they were made
+    glob(pattern.to_str()?).ok()?.filter_map(|p| p.ok()).max()
+}
+
+struct BundleWriter {
+    zip: ZipWriter<File>,
+    anonymizer: Option<Anonymizer>,
+    entries: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl BundleWriter {
+    fn add(&mut self, name: &str, contents: &str) -> Result<()> {
+        let contents = match &self.anonymizer {
+            Some(anonymizer) => anonymizer.apply(contents),
+            None => contents.to_string(),
+        };
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip.start_file(name, options)?;
+        self.zip.write_all(contents.as_bytes())?;
+        self.entries.push(name.to_string());
+        Ok(())
+    }
+
+    // Something missing from the bundle shouldn't stop us from making it, note why it's missing instead
+    fn add_or_note(&mut self, name: &str, contents: Result<String>) -> Result<()> {
+        match contents {
+            Ok(contents) => self.add(name, &contents),
+            Err(why) => {
+                debug!("Not including {name} in bundle: {why:?}");
+                self.notes.push(format!("{name} wasn't included: {why:#}"));
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_bundle(
+    config: &Config,
+    local_db: &LocalDatabase,
+    manager_logs_dir: &Path,
+    output: &Path,
+    anonymizer: Option<Anonymizer>,
+) -> Result<Vec<String>> {
+    let file = File::create(output)
+        .with_context(|| format!("Couldn't create bundle at {}", output.display()))?;
+    let mut writer = BundleWriter {
+        zip: ZipWriter::new(file),
+        anonymizer,
+        entries: vec![],
+        notes: vec![],
+    };
+
+    let mut redacted_config = config.clone();
+    redacted_config.owml_path = REDACTED.to_string();
+    writer.add(
+        "config.json",
+        &serde_json::to_string_pretty(&redacted_config)?,
+    )?;
+
+    let owml_config = OWMLConfig::get(config)
+        .and_then(|c| Ok(serde_json::to_string_pretty(&c)?))
+        .context("Couldn't read OWML's config");
+    writer.add_or_note("owml_config.json", owml_config)?;
+
+    let mut mods = local_db.all().map(BundledMod::from).collect::<Vec<_>>();
+    mods.sort_by(|a, b| a.unique_name.cmp(&b.unique_name));
+    writer.add("mods.json", &serde_json::to_string_pretty(&mods)?)?;
+
+    let manager_log = get_latest_manager_log(manager_logs_dir)
+        .context("No manager logs found")
+        .and_then(|p| Ok(read_to_string(p)?));
+    writer.add_or_note("manager.log", manager_log)?;
+
+    let game_log = list_sessions(config)
+        .and_then(|s| s.into_iter().next().context("No game log sessions found"))
+        .and_then(|s| Ok(read_to_string(s.path)?));
+    writer.add_or_note("game_log.jsonl", game_log)?;
+
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut readme = format!(
+        "Outer Wilds Mod Manager support bundle\nCore version: {}\nPlatform: {} ({})\nCreated: {created} (seconds since the Unix epoch)\nAnonymized: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        writer.anonymizer.is_some()
+    );
+    for note in writer.notes.iter() {
+        readme.push_str(&format!("\n{note}"));
+    }
+    writer.add("README.txt", &readme)?;
+
+    writer.zip.finish()?;
+    Ok(writer.entries)
+}
+
+/// Make a zip with everything needed to help someone debug their setup, so it can be attached to an issue.
+///
+/// The bundle contains:
+///
+/// - `config.json`: The manager's config, with the OWML path redacted
+/// - `owml_config.json`: OWML's config
+/// - `mods.json`: Every installed mod with its version, whether it's enabled, and its validation errors
+/// - `manager.log`: The newest log file the GUI wrote
+/// - `game_log.jsonl`: The newest recorded game log session
+/// - `README.txt`: The manager version and platform, plus why anything above is missing
+///
+/// If `anonymize` is set, the user's home folder is replaced with `~` and their username with `<user>` wherever it's a folder in a path, in every file.
+/// Anything that can't be read is left out and noted in `README.txt` instead of failing.
+///
+/// ## Returns
+///
+/// The names of the files in the bundle.
+///
+/// ## Errors
+///
+/// If we can't write to `output`.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::bundle::create_bundle;
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use std::path::Path;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// let files = create_bundle(&config, &local_db, Path::new("bundle.zip"), true).unwrap();
+/// println!("Bundled {}", files.join(", "));
+/// ```
+///
+pub fn create_bundle(
+    config: &Config,
+    local_db: &LocalDatabase,
+    output: &Path,
+    anonymize: bool,
+) -> Result<Vec<String>> {
+    let manager_logs_dir = get_app_path()?.join(MANAGER_LOGS_FOLDER_NAME);
+    let anonymizer = anonymize.then(Anonymizer::from_env);
+    write_bundle(config, local_db, &manager_logs_dir, output, anonymizer)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        fs::{create_dir_all, write},
+        io::Read,
+    };
+
+    use zip::ZipArchive;
+
+    use crate::{mods::local::LocalMod, test_utils::TestContext};
+
+    use super::*;
+
+    fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> String {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_anonymizer() {
+        let anonymizer = Anonymizer::new(Some(Path::new("/home/alice")), Some("alice"));
+        assert_eq!(
+            anonymizer.apply("Loaded /home/alice/mods and /mnt/Alice/Games for alice"),
+            "Loaded ~/mods and /mnt/<user>/Games for alice"
+        );
+        let anonymizer = Anonymizer::new(Some(Path::new(r"C:\Users\bob")), None);
+        assert_eq!(
+            anonymizer.apply(r#"{"gamePath": "C:\\Users\\bob\\Game"} C:\Users\bob\Game"#),
+            r#"{"gamePath": "~\\Game"} ~\Game"#
+        );
+    }
+
+    #[test]
+    fn test_anonymizer_short_username() {
+        // A username that's also a common word or part of a mod name
+        let anonymizer = Anonymizer::new(Some(Path::new("/home/time")), Some("time"));
+        assert_eq!(
+            anonymizer.apply(
+                r#"Bwc9876.TimeSaver: Time saved, took no time. Game at D:\\Users\\time\\Outer Wilds, /media/time"#
+            ),
+            r#"Bwc9876.TimeSaver: Time saved, took no time. Game at D:\\Users\\<user>\\Outer Wilds, /media/<user>"#
+        );
+        assert_eq!(
+            anonymizer.apply("/home/time/mods/Example.Time/time.dll"),
+            "~/mods/Example.Time/time.dll"
+        );
+    }
+
+    #[test]
+    fn test_write_bundle() {
+        let mut ctx = TestContext::new();
+        ctx.insert_test_mod(&LocalMod::get_test(1));
+        let logs_dir = ctx.temp_dir.path().join("logs");
+        create_dir_all(logs_dir.join("2024-01-01")).unwrap();
+        create_dir_all(logs_dir.join("2024-01-02")).unwrap();
+        write(logs_dir.join("2024-01-01").join("10-00-00.log"), "old").unwrap();
+        write(
+            logs_dir.join("2024-01-02").join("09-00-00.log"),
+            format!("new {}", ctx.temp_dir.path().display()),
+        )
+        .unwrap();
+
+        let output = ctx.temp_dir.path().join("bundle.zip");
+        let anonymizer = Anonymizer::new(Some(ctx.temp_dir.path()), None);
+        let entries = write_bundle(
+            &ctx.config,
+            &ctx.local_db,
+            &logs_dir,
+            &output,
+            Some(anonymizer),
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec!["config.json", "mods.json", "manager.log", "README.txt"]
+        );
+
+        let mut archive = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        let config = read_entry(&mut archive, "config.json");
+        assert!(config.contains(REDACTED));
+        assert!(!config.contains(&ctx.config.owml_path));
+        let mods = read_entry(&mut archive, "mods.json");
+        assert!(mods.contains("Example.TestMod1"));
+        assert_eq!(read_entry(&mut archive, "manager.log"), "new ~");
+        let readme = read_entry(&mut archive, "README.txt");
+        assert!(readme.contains("owml_config.json wasn't included"));
+        assert!(readme.contains("game_log.jsonl wasn't included"));
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    collections::HashSet,
+    fs::{copy, create_dir_all, read_dir, remove_dir, remove_dir_all, remove_file, File},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    constants::CACHE_FOLDER_NAME,
+    db::LocalDatabase,
+    file::{get_file_hash, sanitize_file_name},
+    lock::Lockfile,
+    profiles::list_profiles,
+};
+
+// Cached archives handed to installs that haven't extracted them yet, these are never evicted
+static IN_USE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// Keeps a cached archive from being evicted by this process until it's dropped
+#[derive(Debug)]
+pub(crate) struct ArchiveLease(PathBuf);
+
+impl ArchiveLease {
+    fn new(path: PathBuf) -> Self {
+        IN_USE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(path.clone());
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ArchiveLease {
+    fn drop(&mut self) {
+        let mut in_use = IN_USE.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(i) = in_use.iter().position(|p| p == &self.0) {
+            in_use.swap_remove(i);
+        }
+    }
+}
+
+fn is_in_use(archive: &CachedArchive) -> bool {
+    IN_USE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&archive.path)
+}
+
+/// An archive stored in the cache
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedArchive {
+    /// The unique name of the mod the archive is for
+    pub unique_name: String,
+    /// The version of the mod in the archive
+    pub version: String,
+    /// The SHA-256 hash of the archive
+    pub hash: String,
+    /// Where the archive is stored
+    pub path: PathBuf,
+    /// The size of the archive in bytes
+    pub size: u64,
+    /// When the archive was last added or installed from
+    pub last_used: SystemTime,
+}
+
+/// Get the folder cached archives are stored in
+pub fn get_cache_dir(config: &Config) -> PathBuf {
+    config.data_dir().join(CACHE_FOLDER_NAME)
+}
+
+fn get_archive_path(config: &Config, unique_name: &str, version: &str, hash: &str) -> PathBuf {
+    get_cache_dir(config)
+        .join(sanitize_file_name(unique_name))
+        .join(format!("{}_{hash}.zip", sanitize_file_name(version)))
+}
+
+fn read_archive(unique_name: &str, path: PathBuf) -> Option<CachedArchive> {
+    if path.extension().is_none_or(|e| e != "zip") {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let (version, hash) = stem.rsplit_once('_')?;
+    let metadata = path.metadata().ok()?;
+    Some(CachedArchive {
+        unique_name: unique_name.to_string(),
+        version: version.to_string(),
+        hash: hash.to_string(),
+        size: metadata.len(),
+        last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        path,
+    })
+}
+
+fn mark_used(path: &Path) {
+    let res = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()));
+    if let Err(why) = res {
+        debug!(
+            "Couldn't update last used time of {}: {why:?}",
+            path.display()
+        );
+    }
+}
+
+/// Whether the cache is enabled, set `max_cache_size_mb` to 0 in the config to disable it
+pub fn cache_enabled(config: &Config) -> bool {
+    config.max_cache_size_mb != 0
+}
+
+/// List every archive in the cache, most recently used first.
+///
+/// ## Errors
+///
+/// If we can't read the cache folder.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::cache::list_cache;
+///
+/// let config = Config::get(None).unwrap();
+/// for archive in list_cache(&config).unwrap() {
+///     println!("{} {} ({} bytes)", archive.unique_name, archive.version, archive.size);
+/// }
+/// ```
+///
+pub fn list_cache(config: &Config) -> Result<Vec<CachedArchive>> {
+    let cache_dir = get_cache_dir(config);
+    if !cache_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut archives: Vec<CachedArchive> = vec![];
+    for mod_dir in read_dir(&cache_dir)? {
+        let mod_dir = mod_dir?.path();
+        let Some(unique_name) = mod_dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !mod_dir.is_dir() {
+            continue;
+        }
+        for entry in read_dir(&mod_dir)? {
+            if let Some(archive) = read_archive(unique_name, entry?.path()) {
+                archives.push(archive);
+            }
+        }
+    }
+    archives.sort_by_key(|a| std::cmp::Reverse(a.last_used));
+    Ok(archives)
+}
+
+/// Look for an archive in the cache without checking its hash or marking it as used.
+/// If `hash` is `None` the most recently used archive for that version is returned.
+/// Use [get_cached_archive] when actually installing from the archive.
+///
+/// ## Returns
+///
+/// The cached archive, if there is one.
+///
+pub fn find_cached_archive(
+    config: &Config,
+    unique_name: &str,
+    version: &str,
+    hash: Option<&str>,
+) -> Option<CachedArchive> {
+    if !cache_enabled(config) {
+        return None;
+    }
+    if let Some(hash) = hash {
+        let path = get_archive_path(config, unique_name, version, &hash.to_ascii_lowercase());
+        read_archive(&sanitize_file_name(unique_name), path)
+    } else {
+        list_cache(config).ok()?.into_iter().find(|a| {
+            a.unique_name == sanitize_file_name(unique_name)
+                && a.version == sanitize_file_name(version)
+        })
+    }
+}
+
+/// Look for an archive in the cache. If `hash` is `None` the most recently used archive for that version is used.
+/// Archives that no longer match their hash are removed.
+///
+/// ## Returns
+///
+/// The path to the cached archive, if there is a valid one.
+///
+pub fn get_cached_archive(
+    config: &Config,
+    unique_name: &str,
+    version: &str,
+    hash: Option<&str>,
+) -> Option<PathBuf> {
+    lease_cached_archive(config, unique_name, version, hash).map(|l| l.path().to_path_buf())
+}
+
+// Same as get_cached_archive, but the archive won't be evicted until the lease is dropped
+pub(crate) fn lease_cached_archive(
+    config: &Config,
+    unique_name: &str,
+    version: &str,
+    hash: Option<&str>,
+) -> Option<ArchiveLease> {
+    lease_verified(find_cached_archive(config, unique_name, version, hash)?)
+}
+
+// Look for an archive with the given hash from any mod, used when we don't know what mod or version an archive is for
+pub(crate) fn lease_cached_archive_by_hash(config: &Config, hash: &str) -> Option<ArchiveLease> {
+    if !cache_enabled(config) {
+        return None;
+    }
+    let hash = hash.to_ascii_lowercase();
+    let archive = list_cache(config)
+        .ok()?
+        .into_iter()
+        .find(|a| a.hash == hash)?;
+    lease_verified(archive)
+}
+
+fn lease_verified(archive: CachedArchive) -> Option<ArchiveLease> {
+    // Take the lease before hashing so the archive can't be evicted in between
+    let lease = ArchiveLease::new(archive.path.clone());
+    match get_file_hash(&archive.path) {
+        Ok(actual) if actual == archive.hash => {
+            mark_used(&archive.path);
+            Some(lease)
+        }
+        _ => {
+            warn!(
+                "Cached archive {} is corrupted, removing it",
+                archive.path.display()
+            );
+            remove_file(&archive.path).ok();
+            None
+        }
+    }
+}
+
+/// Copy an archive into the cache, then prune the cache down to the size limit in the config.
+///
+/// ## Returns
+///
+/// The path to the archive in the cache
+///
+/// ## Errors
+///
+/// If we can't copy the archive.
+///
+pub fn add_to_cache(
+    config: &Config,
+    archive_path: &Path,
+    unique_name: &str,
+    version: &str,
+    hash: &str,
+) -> Result<PathBuf> {
+    let target = get_archive_path(config, unique_name, version, hash);
+    if let Some(parent) = target.parent() {
+        create_dir_all(parent)?;
+    }
+    copy(archive_path, &target).context("Failed to copy archive into the cache")?;
+    mark_used(&target);
+    enforce_size_limit(config)?;
+    Ok(target)
+}
+
+fn remove_archive(archive: &CachedArchive) -> Result<u64> {
+    remove_file(&archive.path)
+        .with_context(|| format!("Failed to remove {}", archive.path.display()))?;
+    if let Some(parent) = archive.path.parent() {
+        // Only removes the folder if it's empty
+        remove_dir(parent).ok();
+    }
+    Ok(archive.size)
+}
+
+fn enforce_size_limit(config: &Config) -> Result<u64> {
+    let limit = config.max_cache_size_mb as u64 * 1024 * 1024;
+    let mut freed = 0;
+    let mut total = 0;
+    for (i, archive) in list_cache(config)?.iter().enumerate() {
+        total += archive.size;
+        // Always keep the most recently used archive, even if it's too big on its own
+        if i != 0 && total > limit && !is_in_use(archive) {
+            freed += remove_archive(archive)?;
+            total -= archive.size;
+        }
+    }
+    Ok(freed)
+}
+
+/// Remove everything from the cache
+///
+/// ## Returns
+///
+/// The amount of bytes freed
+///
+/// ## Errors
+///
+/// If we can't delete the cache folder
+///
+pub fn clean_cache(config: &Config) -> Result<u64> {
+    let freed = list_cache(config)?.iter().map(|a| a.size).sum();
+    let cache_dir = get_cache_dir(config);
+    if cache_dir.is_dir() {
+        remove_dir_all(&cache_dir).context("Failed to remove cache folder")?;
+    }
+    Ok(freed)
+}
+
+/// Remove archives that aren't needed anymore, then remove the least recently used archives until the cache fits in the size limit.
+/// These archives are kept:
+/// - The installed version of each mod
+/// - The versions locked in any of the given lockfiles
+/// - The most recently used archive of each mod enabled in a saved profile, as profiles don't record versions
+/// - Archives an install in this process is about to extract
+///
+/// ## Returns
+///
+/// The amount of bytes freed
+///
+/// ## Errors
+///
+/// If we can't read the cache or delete an archive
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::cache::prune_cache;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let freed = prune_cache(&config, &local_db, &[]).unwrap();
+/// println!("Freed {freed} bytes");
+/// ```
+///
+pub fn prune_cache(
+    config: &Config,
+    local_db: &LocalDatabase,
+    lockfiles: &[Lockfile],
+) -> Result<u64> {
+    let owml = LocalDatabase::get_owml(&config.owml_path);
+    let mut keep_versions = local_db
+        .valid()
+        .chain(owml.iter())
+        .map(|m| (&m.manifest.unique_name, &m.manifest.version))
+        .chain(
+            lockfiles
+                .iter()
+                .flat_map(|l| l.mods.iter())
+                .map(|m| (&m.unique_name, &m.version)),
+        )
+        .map(|(unique_name, version)| {
+            (sanitize_file_name(unique_name), sanitize_file_name(version))
+        })
+        .collect::<HashSet<_>>();
+    let mut profile_mods = list_profiles(config)?
+        .into_iter()
+        .flat_map(|p| p.enabled)
+        .map(|unique_name| sanitize_file_name(&unique_name))
+        .collect::<HashSet<_>>();
+
+    let mut freed = 0;
+    // Most recently used first, so the first archive of a profile mod is the one kept
+    for archive in list_cache(config)? {
+        let key = (archive.unique_name.clone(), archive.version.clone());
+        if keep_versions.contains(&key) || is_in_use(&archive) {
+            continue;
+        }
+        if profile_mods.remove(&archive.unique_name) {
+            keep_versions.insert(key);
+            continue;
+        }
+        freed += remove_archive(&archive)?;
+    }
+    freed += enforce_size_limit(config)?;
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{thread::sleep, time::Duration};
+
+    use crate::{
+        file::get_file_hash,
+        lock::{LockedMod, LOCKFILE_VERSION},
+        profiles::Profile,
+        test_utils::{get_test_file, TestContext},
+    };
+
+    use super::*;
+
+    fn cache_test_zip(ctx: &TestContext, name: &str, version: &str) -> PathBuf {
+        let zip = get_test_file(&format!("{name}.zip"));
+        let hash = get_file_hash(&zip).unwrap();
+        add_to_cache(&ctx.config, &zip, name, version, &hash).unwrap()
+    }
+
+    #[test]
+    fn test_add_and_get_cached_archive() {
+        let ctx = TestContext::new();
+        let path = cache_test_zip(&ctx, "Bwc9876.TimeSaver", "1.1.1");
+        assert!(path.is_file());
+        let hash = get_file_hash(&path).unwrap();
+        assert_eq!(
+            get_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.1", Some(&hash)),
+            Some(path.clone())
+        );
+        assert_eq!(
+            get_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.1", None),
+            Some(path)
+        );
+        assert!(
+            get_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.1", Some("abc")).is_none()
+        );
+        assert!(get_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.0", None).is_none());
+    }
+
+    #[test]
+    fn test_cache_disabled() {
+        let mut ctx = TestContext::new();
+        cache_test_zip(&ctx, "Bwc9876.TimeSaver", "1.1.1");
+        ctx.config.max_cache_size_mb = 0;
+        assert!(get_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.1", None).is_none());
+    }
+
+    #[test]
+    fn test_corrupted_archive_removed() {
+        let ctx = TestContext::new();
+        let path = cache_test_zip(&ctx, "Bwc9876.TimeSaver", "1.1.1");
+        std::fs::write(&path, "corrupted").unwrap();
+        assert!(get_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.1", None).is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_list_and_clean_cache() {
+        let ctx = TestContext::new();
+        cache_test_zip(&ctx, "Bwc9876.TimeSaver", "1.1.1");
+        sleep(Duration::from_millis(10));
+        cache_test_zip(&ctx, "Bwc9876.SaveEditor", "0.1.0");
+        let archives = list_cache(&ctx.config).unwrap();
+        assert_eq!(archives.len(), 2);
+        assert_eq!(archives[0].unique_name, "Bwc9876.SaveEditor");
+        assert_eq!(archives[0].version, "0.1.0");
+        let freed = clean_cache(&ctx.config).unwrap();
+        assert_eq!(freed, archives.iter().map(|a| a.size).sum::<u64>());
+        assert!(list_cache(&ctx.config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_prune_cache() {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        cache_test_zip(&ctx, "Bwc9876.TimeSaver", &local_mod.manifest.version);
+        cache_test_zip(&ctx, "Bwc9876.TimeSaver", "0.0.1");
+        cache_test_zip(&ctx, "Bwc9876.SaveEditor", "0.1.0");
+        prune_cache(&ctx.config, &ctx.local_db, &[]).unwrap();
+        let archives = list_cache(&ctx.config).unwrap();
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].version, local_mod.manifest.version);
+    }
+
+    #[test]
+    fn test_prune_cache_keeps_profiles_and_lockfiles() {
+        let ctx = TestContext::new();
+        cache_test_zip(&ctx, "Bwc9876.TimeSaver", "0.0.1");
+        sleep(Duration::from_millis(10));
+        cache_test_zip(&ctx, "Bwc9876.TimeSaver", "0.0.2");
+        cache_test_zip(&ctx, "Bwc9876.SaveEditor", "0.1.0");
+        cache_test_zip(&ctx, "Bwc9876.SaveEditor", "0.2.0");
+        Profile {
+            name: "Test".to_string(),
+            enabled: vec!["Bwc9876.TimeSaver".to_string()],
+            settings: Default::default(),
+        }
+        .save(&ctx.config)
+        .unwrap();
+        let lockfile = Lockfile {
+            lockfile_version: LOCKFILE_VERSION,
+            owml_version: None,
+            mods: vec![LockedMod {
+                unique_name: "Bwc9876.SaveEditor".to_string(),
+                version: "0.1.0".to_string(),
+                download_url: String::new(),
+                hash: String::new(),
+            }],
+        };
+        prune_cache(&ctx.config, &ctx.local_db, &[lockfile]).unwrap();
+        let mut kept = list_cache(&ctx.config)
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.unique_name, a.version))
+            .collect::<Vec<_>>();
+        kept.sort();
+        assert_eq!(
+            kept,
+            vec![
+                ("Bwc9876.SaveEditor".to_string(), "0.1.0".to_string()),
+                ("Bwc9876.TimeSaver".to_string(), "0.0.2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_in_use_archive_not_evicted() {
+        let mut ctx = TestContext::new();
+        let path = cache_test_zip(&ctx, "Bwc9876.TimeSaver", "1.1.1");
+        let lease = lease_cached_archive(&ctx.config, "Bwc9876.TimeSaver", "1.1.1", None).unwrap();
+        sleep(Duration::from_millis(10));
+        ctx.config.max_cache_size_mb = 1;
+        let big = ctx.temp_dir.path().join("big.zip");
+        std::fs::write(&big, vec![0_u8; 1024 * 1024]).unwrap();
+        add_to_cache(&ctx.config, &big, "Example.Big", "1.0.0", "abc").unwrap();
+        assert!(path.is_file());
+        assert!(prune_cache(&ctx.config, &ctx.local_db, &[]).is_ok());
+        assert!(path.is_file());
+        drop(lease);
+        prune_cache(&ctx.config, &ctx.local_db, &[]).unwrap();
+        assert!(!path.is_file());
+    }
+}
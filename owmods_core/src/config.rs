@@ -1,4 +1,8 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use anyhow::Result;
 use log::debug;
@@ -11,12 +15,74 @@ use crate::{
         OWML_DEFAULT_CONFIG_NAME, OWML_EXE_NAME, OWML_MANIFEST_NAME,
     },
     file::{deserialize_from_json, get_app_path, get_default_owml_path, serialize_to_json},
+    socket::LogOverflowPolicy,
 };
 
 const fn _default_true() -> bool {
     true
 }
 
+const fn _default_cache_size() -> u32 {
+    1024
+}
+
+const fn _default_max_backups() -> u32 {
+    3
+}
+
+const fn _default_max_log_sessions() -> u32 {
+    20
+}
+
+const fn _default_max_log_session_age_days() -> u32 {
+    30
+}
+
+/// How downloads are retried when they fail because of a transient error (dropped connections, server errors, etc.)
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// How many times to retry a download before giving up, 0 disables retrying
+    pub max_retries: u32,
+    /// How long to wait before the first retry in milliseconds, this doubles with each retry
+    pub initial_backoff_ms: u32,
+    /// The longest we'll ever wait between retries in milliseconds
+    pub max_backoff_ms: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Get how long to wait before the given retry (starting at 1)
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use owmods_core::config::RetryPolicy;
+    /// use std::time::Duration;
+    ///
+    /// let policy = RetryPolicy::default();
+    /// assert_eq!(policy.get_backoff(1), Duration::from_millis(1000));
+    /// assert_eq!(policy.get_backoff(2), Duration::from_millis(2000));
+    /// assert_eq!(policy.get_backoff(10), Duration::from_millis(30000));
+    /// ```
+    ///
+    pub fn get_backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u64.saturating_pow(attempt.saturating_sub(1));
+        let backoff = (self.initial_backoff_ms as u64).saturating_mul(factor);
+        Duration::from_millis(backoff.min(self.max_backoff_ms as u64))
+    }
+}
+
 /// Represents the core config, contains critical info needed by the core API
 #[typeshare]
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,6 +101,31 @@ pub struct Config {
     /// Whether or not to send analytics events
     #[serde(default = "_default_true")]
     pub send_analytics: bool,
+    /// How downloads should be retried if they fail
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// The most space downloaded archives can take up in the cache in megabytes, 0 disables the cache
+    #[serde(default = "_default_cache_size")]
+    pub max_cache_size_mb: u32,
+    /// How many backups to keep for each mod when updating, 0 disables backups
+    #[serde(default = "_default_max_backups")]
+    pub max_backups: u32,
+    /// How many recorded game log sessions to keep, 0 disables recording
+    #[serde(default = "_default_max_log_sessions")]
+    pub max_log_sessions: u32,
+    /// How many days to keep recorded game log sessions for, 0 keeps them regardless of age
+    #[serde(default = "_default_max_log_session_age_days")]
+    pub max_log_session_age_days: u32,
+    /// What the log server does when the game sends logs faster than they can be shown
+    #[serde(default)]
+    pub log_overflow_policy: LogOverflowPolicy,
+    /// Mods that shouldn't be updated, maps unique names to either the exact version the mod should stay on
+    /// or `"hold"` to keep whatever version is installed
+    #[serde(default)]
+    pub pins: HashMap<String, String>,
+    /// Whether to skip fetching the remote database and use the last fetched copy instead
+    #[serde(default)]
+    pub offline: bool,
     /// Where the config is saved, this is not serialized
     #[serde(skip)]
     pub path: PathBuf,
@@ -74,6 +165,14 @@ impl Config {
             alert_url: String::from(DEFAULT_ALERT_URL),
             last_viewed_db_alert: None,
             send_analytics: true,
+            retry_policy: RetryPolicy::default(),
+            max_cache_size_mb: _default_cache_size(),
+            max_backups: _default_max_backups(),
+            max_log_sessions: _default_max_log_sessions(),
+            max_log_session_age_days: _default_max_log_session_age_days(),
+            log_overflow_policy: LogOverflowPolicy::default(),
+            pins: HashMap::new(),
+            offline: false,
             viewed_alerts: vec![],
             path,
         })
@@ -136,6 +235,25 @@ impl Config {
         self.viewed_alerts.push(unique_name.to_string());
     }
 
+    /// Get the directory the config file is saved in.
+    /// Other data the manager persists (profiles, caches, etc.) is stored in here as well.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// println!("Data Dir: {}", config.data_dir().display());
+    /// ```
+    ///
+    pub fn data_dir(&self) -> PathBuf {
+        self.path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default()
+    }
+
     fn read(path: &Path) -> Result<Self> {
         debug!("Reading Config From {}", path.to_str().unwrap());
         let mut new_conf: Config = deserialize_from_json(path)?;
@@ -267,6 +385,30 @@ mod tests {
         assert_eq!(config.owml_path, "/different/path");
     }
 
+    #[test]
+    pub fn test_config_missing_retry_policy() {
+        let ctx = TestContext::new();
+        let path = ctx.temp_dir.path().join("settings.json");
+        let mut raw = serde_json::to_value(&ctx.config).unwrap();
+        raw.as_object_mut().unwrap().remove("retryPolicy");
+        std::fs::write(&path, raw.to_string()).unwrap();
+        let config = Config::get(Some(path)).unwrap();
+        assert_eq!(config.retry_policy, RetryPolicy::default());
+    }
+
+    #[test]
+    pub fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 350,
+        };
+        assert_eq!(policy.get_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.get_backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.get_backoff(u32::MAX), Duration::from_millis(350));
+    }
+
     #[test]
     pub fn test_config_migrate_alert() {
         let mut ctx = TestContext::new();
@@ -38,3 +38,30 @@ pub const OLD_MANAGER_FOLDER_NAME: &str = "OuterWildsModManager";
 
 /// Name of the tag used to indicate a mod requires DLC
 pub const REQUIRES_DLC_TAG: &str = "requires-dlc";
+
+/// The value of a pin in the config that holds a mod at whatever version is installed
+pub const PIN_HOLD: &str = "hold";
+
+/// The name of the folder saved profiles are stored in, relative to the config's directory
+pub const PROFILES_FOLDER_NAME: &str = "profiles";
+
+/// The name of the folder settings snapshots are stored in, relative to the config's directory
+pub const SNAPSHOTS_FOLDER_NAME: &str = "snapshots";
+
+/// The name of the folder recorded log sessions are stored in, relative to the config's directory
+pub const LOG_SESSIONS_FOLDER_NAME: &str = "log_sessions";
+
+/// The name of the folder the GUI writes its own logs to, relative to the app's data directory
+pub const MANAGER_LOGS_FOLDER_NAME: &str = "logs";
+
+/// The name of the folder downloaded archives are cached in, relative to the config's directory
+pub const CACHE_FOLDER_NAME: &str = "cache";
+
+/// The name of the file the last fetched remote database is saved to, relative to the config's directory
+pub const REMOTE_DB_CACHE_NAME: &str = "remote_database.json";
+
+/// The name of the folder mod backups are stored in, relative to the config's directory
+pub const BACKUPS_FOLDER_NAME: &str = "backups";
+
+/// The name of the file that records which mods were installed only as dependencies, relative to the config's directory
+pub const DEPENDENCY_INSTALLS_NAME: &str = "dependency_installs.json";
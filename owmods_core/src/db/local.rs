@@ -9,12 +9,13 @@ use log::{debug, warn};
 use tokio::sync::Mutex;
 
 use crate::{
+    config::Config,
     download::ModDeduper,
     file::deserialize_from_json,
     mods::local::{FailedMod, LocalMod, ModManifest, UnsafeLocalMod},
     search::search_list,
     toggle::get_mod_enabled,
-    updates::check_mod_needs_update,
+    updates::{check_mod_held_back, check_mod_needs_update},
     validate::{check_mod, ModValidationError},
 };
 
@@ -273,7 +274,8 @@ impl LocalDatabase {
 
     /// Returns an iterator over all mods that are dependent on the given mod
     ///
-    /// Please note this only checks direct dependence, it doesn't go up the dependency tree and add every parent
+    /// Please note this only checks direct dependence, it doesn't go up the dependency tree and add every parent,
+    /// use [crate::graph::DependencyGraph::dependents] for that
     ///
     /// ## Returns
     ///
@@ -396,6 +398,29 @@ impl LocalDatabase {
     /// ```
     ///
     pub fn validate_updates(&mut self, db: &RemoteDatabase) {
+        self.mark_outdated(db, None);
+    }
+
+    /// Same as [LocalDatabase::validate_updates], but mods with an update that's blocked by a pin in the config aren't marked as outdated.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::db::{RemoteDatabase, LocalDatabase};
+    /// use owmods_core::config::Config;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let mut db = LocalDatabase::fetch(&config.owml_path).unwrap();
+    /// let remote_db = RemoteDatabase::fetch_blocking(&config.database_url).unwrap();
+    ///
+    /// db.validate_updates_with_pins(&remote_db, &config);
+    /// ```
+    ///
+    pub fn validate_updates_with_pins(&mut self, db: &RemoteDatabase, config: &Config) {
+        self.mark_outdated(db, Some(config));
+    }
+
+    fn mark_outdated(&mut self, db: &RemoteDatabase, pins: Option<&Config>) {
         for local_mod in self.mods.iter_mut().filter_map(|m| {
             if let UnsafeLocalMod::Valid(m) = m.1 {
                 Some(m)
@@ -404,7 +429,10 @@ impl LocalDatabase {
             }
         }) {
             let (needs_update, remote) = check_mod_needs_update(local_mod, db);
+            let held_back =
+                pins.is_some_and(|config| check_mod_held_back(local_mod, db, config).is_some());
             if needs_update
+                && !held_back
                 && !local_mod
                     .errors
                     .iter()
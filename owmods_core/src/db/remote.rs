@@ -1,10 +1,20 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::Result;
-use log::debug;
-use serde::Deserialize;
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
 
-use crate::{constants::OWML_UNIQUE_NAME, mods::remote::RemoteMod, search::search_list};
+use crate::{
+    config::Config,
+    constants::{OWML_UNIQUE_NAME, REMOTE_DB_CACHE_NAME},
+    file::{deserialize_from_json, serialize_to_json},
+    mods::remote::RemoteMod,
+    search::search_list,
+};
 
 use super::fix_version;
 
@@ -15,6 +25,17 @@ struct RawRemoteDatabase {
     pub releases: Vec<RemoteMod>,
 }
 
+/// The last fetched database as it's saved on disk
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedRemoteDatabase {
+    /// When the database was fetched, in seconds since the unix epoch
+    pub fetched_at: u64,
+    /// The URL the database was fetched from
+    pub database_url: String,
+    pub releases: Vec<RemoteMod>,
+}
+
 /// Represents the remote (on the website) database of mods.
 #[derive(Debug, Default, Clone)]
 pub struct RemoteDatabase {
@@ -22,6 +43,10 @@ pub struct RemoteDatabase {
     pub mods: HashMap<String, RemoteMod>,
     /// OWML, if it exists
     pub owml: Option<RemoteMod>,
+    /// When this database was fetched from the website, if known
+    pub fetched_at: Option<SystemTime>,
+    /// Whether this database was loaded from the copy saved on disk instead of being fetched
+    pub offline: bool,
 }
 
 impl From<RawRemoteDatabase> for RemoteDatabase {
@@ -39,7 +64,12 @@ impl From<RawRemoteDatabase> for RemoteDatabase {
             })
             .collect::<HashMap<_, _>>();
         let owml = mods.remove(OWML_UNIQUE_NAME);
-        Self { mods, owml }
+        Self {
+            mods,
+            owml,
+            fetched_at: None,
+            offline: false,
+        }
     }
 }
 
@@ -76,7 +106,9 @@ impl RemoteDatabase {
         let resp = reqwest::get(url).await?;
         let raw_db: RawRemoteDatabase = resp.json().await?;
         debug!("Success, Constructing Remote Mod Map");
-        Ok(Self::from(raw_db))
+        let mut db = Self::from(raw_db);
+        db.fetched_at = Some(SystemTime::now());
+        Ok(db)
     }
 
     /// Fetch the database but block the current thread while doing so
@@ -108,7 +140,148 @@ impl RemoteDatabase {
         let resp = reqwest::blocking::get(url)?;
         let raw_db: RawRemoteDatabase = resp.json()?;
         debug!("Success, Constructing Remote Mod Map");
-        Ok(Self::from(raw_db))
+        let mut db = Self::from(raw_db);
+        db.fetched_at = Some(SystemTime::now());
+        Ok(db)
+    }
+
+    /// Fetch the database, saving it to disk so it can be used later when we can't connect.
+    /// If `offline` is set in the config or fetching fails, the last saved copy is loaded instead.
+    ///
+    /// ## Returns
+    ///
+    /// The remote database, check [RemoteDatabase::offline] to see if it came from the saved copy.
+    ///
+    /// ## Errors
+    ///
+    /// If we can't fetch the database and there's no saved copy to fall back to.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::db::RemoteDatabase;
+    /// use owmods_core::config::Config;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let config = Config::get(None).unwrap();
+    /// let db = RemoteDatabase::fetch_or_cached(&config).await.unwrap();
+    ///
+    /// if db.offline {
+    ///     println!("Using a copy of the database from {:?} ago", db.age().unwrap());
+    /// }
+    /// # });
+    /// ```
+    ///
+    pub async fn fetch_or_cached(config: &Config) -> Result<RemoteDatabase> {
+        if config.offline {
+            return Self::load_cached(config)
+                .context("Offline mode is enabled but there's no saved copy of the database");
+        }
+        match Self::fetch(&config.database_url).await {
+            Ok(db) => {
+                if let Err(why) = db.save_cached(config) {
+                    warn!("Couldn't save a copy of the remote database: {why:?}");
+                }
+                Ok(db)
+            }
+            Err(why) => {
+                let db = Self::load_cached(config).map_err(|_| why)?;
+                warn!("Couldn't fetch the remote database, using the copy saved on disk");
+                Ok(db)
+            }
+        }
+    }
+
+    /// Load the copy of the database that was saved the last time it was fetched with [RemoteDatabase::fetch_or_cached].
+    ///
+    /// ## Returns
+    ///
+    /// The saved database, marked as [RemoteDatabase::offline] with [RemoteDatabase::fetched_at] set to when it was fetched.
+    ///
+    /// ## Errors
+    ///
+    /// If there's no saved copy or we can't read it.
+    ///
+    pub fn load_cached(config: &Config) -> Result<RemoteDatabase> {
+        let path = Self::get_cache_path(config);
+        debug!("Loading Saved Remote DB From {}", path.display());
+        let cached: CachedRemoteDatabase = deserialize_from_json(&path)?;
+        if cached.database_url != config.database_url {
+            warn!(
+                "The saved remote database was fetched from {}, not {}",
+                cached.database_url, config.database_url
+            );
+        }
+        let mut db = Self::from(RawRemoteDatabase {
+            releases: cached.releases,
+        });
+        db.fetched_at = Some(UNIX_EPOCH + Duration::from_secs(cached.fetched_at));
+        db.offline = true;
+        Ok(db)
+    }
+
+    /// Get the path the last fetched database is saved to
+    pub fn get_cache_path(config: &Config) -> PathBuf {
+        config.data_dir().join(REMOTE_DB_CACHE_NAME)
+    }
+
+    /// Save this database to disk so it can be loaded with [RemoteDatabase::load_cached] later.
+    ///
+    /// ## Errors
+    ///
+    /// If we can't write the file.
+    ///
+    pub fn save_cached(&self, config: &Config) -> Result<()> {
+        let fetched_at = self
+            .fetched_at
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let cached = CachedRemoteDatabase {
+            fetched_at,
+            database_url: config.database_url.clone(),
+            releases: self
+                .mods
+                .values()
+                .chain(self.owml.iter())
+                .cloned()
+                .collect(),
+        };
+        serialize_to_json(&cached, &Self::get_cache_path(config), true)?;
+        Ok(())
+    }
+
+    /// Get how long ago this database was fetched.
+    ///
+    /// ## Returns
+    ///
+    /// The time since this database was fetched, or `None` if we don't know when it was fetched.
+    ///
+    pub fn age(&self) -> Option<Duration> {
+        self.fetched_at
+            .map(|t| SystemTime::now().duration_since(t).unwrap_or_default())
+    }
+
+    /// Check if this database is older than `max_age`, a database that we don't know the age of is always stale.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::db::RemoteDatabase;
+    /// use owmods_core::config::Config;
+    /// use std::time::Duration;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let db = RemoteDatabase::load_cached(&config).unwrap();
+    ///
+    /// if db.is_stale(Duration::from_secs(60 * 60 * 24)) {
+    ///     println!("The saved database is over a day old");
+    /// }
+    /// ```
+    ///
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age().is_none_or(|age| age > max_age)
     }
 
     /// Get a mod by unique name, **will not return OWML**.
@@ -318,7 +491,7 @@ impl RemoteDatabase {
 
 #[cfg(test)]
 mod tests {
-    use crate::constants::DEFAULT_DB_URL;
+    use crate::{constants::DEFAULT_DB_URL, test_utils::TestContext};
 
     use super::*;
 
@@ -399,4 +572,62 @@ mod tests {
         });
         assert!(db.get_mod(OWML_UNIQUE_NAME).is_none());
     }
+
+    #[test]
+    fn test_remote_db_save_and_load_cached() {
+        let ctx = TestContext::new();
+        let mut owml = RemoteMod::get_test(2);
+        owml.unique_name = OWML_UNIQUE_NAME.to_string();
+        let mut db = RemoteDatabase::from(RawRemoteDatabase {
+            releases: vec![RemoteMod::get_test(1), owml],
+        });
+        db.fetched_at = Some(SystemTime::now() - Duration::from_secs(120));
+        db.save_cached(&ctx.config).unwrap();
+        let cached = RemoteDatabase::load_cached(&ctx.config).unwrap();
+        assert!(cached.offline);
+        assert!(cached.get_mod("Example.TestMod1").is_some());
+        assert!(cached.get_owml().is_some());
+        assert!(cached.age().unwrap() >= Duration::from_secs(120));
+        assert!(cached.is_stale(Duration::from_secs(60)));
+        assert!(!cached.is_stale(Duration::from_secs(60 * 60)));
+    }
+
+    #[test]
+    fn test_remote_db_load_cached_missing() {
+        let ctx = TestContext::new();
+        assert!(RemoteDatabase::load_cached(&ctx.config).is_err());
+    }
+
+    #[test]
+    fn test_remote_db_fetch_offline() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            ctx.config.offline = true;
+            assert!(RemoteDatabase::fetch_or_cached(&ctx.config).await.is_err());
+            RemoteDatabase::from(RawRemoteDatabase {
+                releases: vec![RemoteMod::get_test(1)],
+            })
+            .save_cached(&ctx.config)
+            .unwrap();
+            let db = RemoteDatabase::fetch_or_cached(&ctx.config).await.unwrap();
+            assert!(db.offline);
+            assert!(db.get_mod("Example.TestMod1").is_some());
+        });
+    }
+
+    #[test]
+    fn test_remote_db_fetch_falls_back_to_cached() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            ctx.config.database_url = "http://127.0.0.1:1/database.json".to_string();
+            assert!(RemoteDatabase::fetch_or_cached(&ctx.config).await.is_err());
+            RemoteDatabase::from(RawRemoteDatabase {
+                releases: vec![RemoteMod::get_test(1)],
+            })
+            .save_cached(&ctx.config)
+            .unwrap();
+            let db = RemoteDatabase::fetch_or_cached(&ctx.config).await.unwrap();
+            assert!(db.offline);
+        });
+    }
 }
@@ -0,0 +1,406 @@
+use std::cmp::Reverse;
+
+use serde::Serialize;
+use typeshare::typeshare;
+
+use crate::{
+    db::LocalDatabase,
+    exceptions::LogException,
+    mods::local::LocalMod,
+    socket::{SocketMessage, SocketMessageType},
+    validate::ModValidationError,
+};
+
+/// Why a mod is suspected of causing problems in a game session
+#[typeshare]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SuspectReason {
+    /// The mod sent the first fatal error
+    SentFatal,
+    /// The mod sent the first error
+    SentFirstError,
+    /// The mod sent more errors than any other mod
+    MostErrors,
+    /// The mod had validation issues when the game was launched
+    ValidationIssues,
+}
+
+/// How many errors a sender logged during a session
+#[typeshare]
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModErrorCount {
+    /// The name the errors were sent with
+    pub sender_name: String,
+    /// The unique name of the installed mod the sender name belongs to, if we could find one
+    pub unique_name: Option<String>,
+    /// How many errors (including fatal errors) were sent
+    pub errors: u32,
+    /// How many of the errors were fatal
+    pub fatal: u32,
+    /// How many of the errors were exceptions
+    pub exceptions: u32,
+}
+
+/// A mod that may have caused problems in a session
+#[typeshare]
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspectMod {
+    /// The unique name of the mod
+    pub unique_name: String,
+    /// The name of the mod
+    pub name: String,
+    /// Why the mod is a suspect, most important first
+    pub reasons: Vec<SuspectReason>,
+}
+
+/// The validation issues a mod had when the game was launched
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModIssues {
+    /// The unique name of the mod, or its path if it failed to load
+    pub unique_name: String,
+    /// The name of the mod
+    pub name: String,
+    /// The issues with the mod
+    pub errors: Vec<ModValidationError>,
+}
+
+/// A report on what went wrong in a game session, see [SessionAnalyzer] and [analyze_session]
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReport {
+    /// How many messages the game sent
+    pub message_count: u32,
+    /// The first fatal error the game sent
+    pub first_fatal: Option<SocketMessage>,
+    /// The first error (fatal or not) the game sent
+    pub first_error: Option<SocketMessage>,
+    /// How many errors each sender logged, most errors first
+    pub mod_errors: Vec<ModErrorCount>,
+    /// Mods that may have caused problems, most suspicious first
+    pub suspects: Vec<SuspectMod>,
+    /// Validation issues mods had when the game was launched
+    pub validation_issues: Vec<ModIssues>,
+}
+
+impl SessionReport {
+    /// Check if anything went wrong in the session
+    pub fn has_problems(&self) -> bool {
+        self.first_error.is_some() || !self.validation_issues.is_empty()
+    }
+
+    fn add_suspect(&mut self, local_mod: &LocalMod, reason: SuspectReason) {
+        let unique_name = &local_mod.manifest.unique_name;
+        match self
+            .suspects
+            .iter_mut()
+            .find(|s| &s.unique_name == unique_name)
+        {
+            Some(suspect) => {
+                if !suspect.reasons.contains(&reason) {
+                    suspect.reasons.push(reason);
+                }
+            }
+            None => self.suspects.push(SuspectMod {
+                unique_name: unique_name.clone(),
+                name: local_mod.manifest.name.clone(),
+                reasons: vec![reason],
+            }),
+        }
+    }
+}
+
+// Mods send logs with their name, so find the mod it belongs to
+fn find_sender<'a>(local_db: &'a LocalDatabase, sender_name: &str) -> Option<&'a LocalMod> {
+    local_db.get_mod(sender_name).or_else(|| {
+        local_db
+            .valid()
+            .find(|m| m.manifest.name.eq_ignore_ascii_case(sender_name))
+    })
+}
+
+/// Builds a [SessionReport] one message at a time while the game is running, so the messages don't have to be kept around.
+/// Use [analyze_session] if you already have every message.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::diagnostics::SessionAnalyzer;
+/// use owmods_core::socket::{SocketMessage, SocketMessageType};
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// let mut analyzer = SessionAnalyzer::new(&local_db);
+/// analyzer.add_message(&SocketMessage {
+///     sender_name: Some("Example.Mod".to_string()),
+///     sender_type: None,
+///     message: "Something broke".to_string(),
+///     message_type: SocketMessageType::Error,
+/// });
+/// let report = analyzer.finish();
+/// println!("{} messages", report.message_count);
+/// ```
+///
+pub struct SessionAnalyzer<'a> {
+    local_db: &'a LocalDatabase,
+    report: SessionReport,
+}
+
+impl<'a> SessionAnalyzer<'a> {
+    /// Start analyzing a session.
+    ///
+    /// Pass the [LocalDatabase] as it was when the game was launched, it's used to map sender names to mods
+    /// and to include validation issues in the report.
+    pub fn new(local_db: &'a LocalDatabase) -> Self {
+        Self {
+            local_db,
+            report: SessionReport {
+                message_count: 0,
+                first_fatal: None,
+                first_error: None,
+                mod_errors: vec![],
+                suspects: vec![],
+                validation_issues: vec![],
+            },
+        }
+    }
+
+    /// Add a message the game sent, messages from the manager itself (like clients connecting) are ignored.
+    pub fn add_message(&mut self, message: &SocketMessage) {
+        if message.is_internal() {
+            return;
+        }
+        let report = &mut self.report;
+        report.message_count = report.message_count.saturating_add(1);
+        let is_fatal = message.message_type == SocketMessageType::Fatal;
+        if !is_fatal && message.message_type != SocketMessageType::Error {
+            return;
+        }
+        if is_fatal && report.first_fatal.is_none() {
+            report.first_fatal = Some(message.clone());
+        }
+        if report.first_error.is_none() {
+            report.first_error = Some(message.clone());
+        }
+        let sender_name = message.sender_name.as_deref().unwrap_or("Unknown");
+        let count = match report
+            .mod_errors
+            .iter_mut()
+            .position(|c| c.sender_name == sender_name)
+        {
+            Some(i) => &mut report.mod_errors[i],
+            None => {
+                report.mod_errors.push(ModErrorCount {
+                    sender_name: sender_name.to_string(),
+                    unique_name: find_sender(self.local_db, sender_name)
+                        .map(|m| m.manifest.unique_name.clone()),
+                    errors: 0,
+                    fatal: 0,
+                    exceptions: 0,
+                });
+                report.mod_errors.last_mut().unwrap()
+            }
+        };
+        count.errors += 1;
+        if is_fatal {
+            count.fatal += 1;
+        }
+        if LogException::parse(message).is_some() {
+            count.exceptions += 1;
+        }
+    }
+
+    /// Finish the analysis, working out the suspects and adding validation issues.
+    ///
+    /// ## Returns
+    ///
+    /// A report with the first fatal error and error, error counts per mod, suspected culprit mods, and validation issues.
+    ///
+    pub fn finish(self) -> SessionReport {
+        let local_db = self.local_db;
+        let mut report = self.report;
+        report.mod_errors.sort_by_key(|c| Reverse(c.errors));
+
+        let first_fatal = report
+            .first_fatal
+            .as_ref()
+            .and_then(|m| m.sender_name.clone());
+        let first_error = report
+            .first_error
+            .as_ref()
+            .and_then(|m| m.sender_name.clone());
+        if let Some(local_mod) = first_fatal.and_then(|s| find_sender(local_db, &s)) {
+            report.add_suspect(local_mod, SuspectReason::SentFatal);
+        }
+        if let Some(local_mod) = first_error.and_then(|s| find_sender(local_db, &s)) {
+            report.add_suspect(local_mod, SuspectReason::SentFirstError);
+        }
+        let most_errors = report
+            .mod_errors
+            .iter()
+            .filter(|c| c.unique_name.is_some())
+            .map(|c| c.errors)
+            .max()
+            .unwrap_or(0);
+        let top_senders = report
+            .mod_errors
+            .iter()
+            .filter(|c| c.errors == most_errors)
+            .filter_map(|c| local_db.get_mod(c.unique_name.as_ref()?))
+            .collect::<Vec<_>>();
+        for local_mod in top_senders {
+            report.add_suspect(local_mod, SuspectReason::MostErrors);
+        }
+
+        let mut invalid = local_db.invalid().collect::<Vec<_>>();
+        invalid.sort_by_key(|m| m.get_unique_name());
+        for unsafe_mod in invalid {
+            let errors = unsafe_mod.get_errs();
+            if let Some(local_mod) = local_db.get_mod(unsafe_mod.get_unique_name()) {
+                // Being outdated isn't likely to break anything
+                if errors
+                    .iter()
+                    .any(|e| !matches!(e, ModValidationError::Outdated(_)))
+                {
+                    report.add_suspect(local_mod, SuspectReason::ValidationIssues);
+                }
+            }
+            report.validation_issues.push(ModIssues {
+                unique_name: unsafe_mod.get_unique_name().clone(),
+                name: unsafe_mod.get_name().clone(),
+                errors: errors.into_iter().cloned().collect(),
+            });
+        }
+
+        report
+    }
+}
+
+/// Analyze the messages the game sent during a session to figure out what went wrong.
+/// Messages from the manager itself (like clients connecting) are ignored.
+/// This is the same as feeding every message to a [SessionAnalyzer].
+///
+/// Pass the [LocalDatabase] as it was when the game was launched, it's used to map sender names to mods
+/// and to include validation issues in the report.
+///
+/// ## Returns
+///
+/// A report with the first fatal error and error, error counts per mod, suspected culprit mods, and validation issues.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::diagnostics::analyze_session;
+/// use owmods_core::sessions::get_session;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let recorded = get_session(&config, "latest").unwrap().read_messages().unwrap();
+///
+/// let report = analyze_session(recorded.iter().map(|r| &r.message), &local_db);
+/// for suspect in report.suspects {
+///     println!("{} ({:?})", suspect.name, suspect.reasons);
+/// }
+/// ```
+///
+pub fn analyze_session<'a>(
+    messages: impl IntoIterator<Item = &'a SocketMessage>,
+    local_db: &LocalDatabase,
+) -> SessionReport {
+    let mut analyzer = SessionAnalyzer::new(local_db);
+    for message in messages {
+        analyzer.add_message(message);
+    }
+    analyzer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::test_utils::TestContext;
+
+    use super::*;
+
+    #[test]
+    fn test_analyze_session() {
+        let mut ctx = TestContext::new();
+        ctx.insert_test_mod(&LocalMod::get_test(1));
+        ctx.insert_test_mod(&LocalMod::get_test(2));
+        let mut broken = LocalMod::get_test(3);
+        broken.errors.push(ModValidationError::MissingDep(
+            "Example.Missing".to_string(),
+        ));
+        ctx.insert_test_mod(&broken);
+
+        let messages = [
+            SocketMessage::make_internal("Client Connected", SocketMessageType::Info),
+            SocketMessage::get_test("Test Mod 1", "Loaded", SocketMessageType::Info),
+            SocketMessage::get_test("Example.TestMod2", "Oops", SocketMessageType::Error),
+            SocketMessage::get_test(
+                "Test Mod 1",
+                "System.Exception: Bad\n  at Foo.Bar ()",
+                SocketMessageType::Error,
+            ),
+            SocketMessage::get_test("Test Mod 1", "Broken", SocketMessageType::Error),
+            SocketMessage::get_test("Unity", "Crashed", SocketMessageType::Fatal),
+        ];
+        let report = analyze_session(&messages, &ctx.local_db);
+
+        assert!(report.has_problems());
+        assert_eq!(report.message_count, 5);
+        assert_eq!(report.first_error.unwrap().message, "Oops");
+        assert_eq!(report.first_fatal.unwrap().message, "Crashed");
+
+        assert_eq!(report.mod_errors.len(), 3);
+        let first = &report.mod_errors[0];
+        assert_eq!(first.unique_name.as_deref(), Some("Example.TestMod1"));
+        assert_eq!((first.errors, first.exceptions, first.fatal), (2, 1, 0));
+        let unity = report
+            .mod_errors
+            .iter()
+            .find(|c| c.sender_name == "Unity")
+            .unwrap();
+        assert_eq!((unity.errors, unity.fatal), (1, 1));
+        assert!(unity.unique_name.is_none());
+
+        let suspects = report
+            .suspects
+            .iter()
+            .map(|s| (s.unique_name.as_str(), s.reasons.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            suspects,
+            vec![
+                ("Example.TestMod2", vec![SuspectReason::SentFirstError]),
+                ("Example.TestMod1", vec![SuspectReason::MostErrors]),
+                ("Example.TestMod3", vec![SuspectReason::ValidationIssues]),
+            ]
+        );
+        assert_eq!(report.validation_issues.len(), 1);
+        assert_eq!(report.validation_issues[0].unique_name, "Example.TestMod3");
+    }
+
+    #[test]
+    fn test_analyze_clean_session() {
+        let mut ctx = TestContext::new();
+        ctx.insert_test_mod(&LocalMod::get_test(1));
+        let messages = [SocketMessage::get_test(
+            "Test Mod 1",
+            "Loaded",
+            SocketMessageType::Info,
+        )];
+        let report = analyze_session(&messages, &ctx.local_db);
+        assert!(!report.has_problems());
+        assert!(report.suspects.is_empty());
+        assert!(report.mod_errors.is_empty());
+    }
+}
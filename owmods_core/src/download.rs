@@ -1,54 +1,170 @@
 use log::warn;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ffi::OsStr,
     fs::File,
-    io::{BufReader, BufWriter, Read, Write},
+    io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
 use anyhow::Result;
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use futures::{stream::FuturesUnordered, StreamExt};
 use log::{debug, info};
+use reqwest::{header::RANGE, StatusCode};
+use sha2::{Digest, Sha256};
 use tempfile::TempDir;
 use tokio::sync::Mutex;
 use zip::ZipArchive;
 
 use crate::{
     analytics::{send_analytics_deferred, AnalyticsEventName},
-    config::Config,
+    autoremove::{record_dependency_installs, record_explicit_install},
+    backups::backup_mod,
+    cache::{
+        add_to_cache, cache_enabled, lease_cached_archive, lease_cached_archive_by_hash,
+        ArchiveLease,
+    },
+    config::{Config, RetryPolicy},
     constants::OWML_UNIQUE_NAME,
     db::{LocalDatabase, RemoteDatabase},
-    file::{check_file_matches_paths, create_all_parents, fix_bom},
+    file::{
+        check_file_matches_paths, create_all_parents, fix_bom, format_hash, sanitize_file_name,
+    },
     mods::{
         local::{get_paths_to_preserve, LocalMod, ModManifest},
         remote::RemoteMod,
     },
     progress::{ProgressAction, ProgressBar, ProgressType},
     remove::remove_old_mod_files,
+    resolve::{resolve_install_with_archives, InstallPlan},
     toggle::generate_config,
+    updates::check_pin,
 };
 
 fn get_end_of_url(url: &str) -> &str {
     url.split('/').next_back().unwrap_or(url)
 }
 
-async fn download_zip(url: &str, unique_name: Option<&str>, target_path: &Path) -> Result<()> {
-    debug!(
-        "Begin download of {} to {}",
-        url,
-        target_path.to_str().unwrap()
-    );
-    let client = reqwest::Client::new();
+/// The error returned when a downloaded archive doesn't match the hash it was expected to have.
+/// Use `downcast_ref` on the [anyhow::Error] returned by install functions to check for this.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::config::Config;
+/// use owmods_core::download::{install_mod_from_url, HashMismatchError};
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// let res = install_mod_from_url("https://example.com/Mod.zip", None, Some("abc123"), &config, &local_db).await;
+///
+/// if let Some(why) = res.err().and_then(|e| e.downcast::<HashMismatchError>().ok()) {
+///     println!("Download was corrupted, got {}", why.actual);
+/// }
+/// # });
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct HashMismatchError {
+    /// The URL the archive was downloaded from
+    pub url: String,
+    /// The hash the archive was expected to have
+    pub expected: String,
+    /// The hash of the archive we actually downloaded
+    pub actual: String,
+}
+
+impl std::fmt::Display for HashMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SHA-256 of {} doesn't match (expected {}, got {}), the download may be corrupted or tampered with",
+            self.url, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for HashMismatchError {}
+
+// Whether an error is worth retrying the download for (network hiccups, server errors, etc.)
+fn is_transient(err: &anyhow::Error) -> bool {
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        if let Some(status) = err.status() {
+            status.is_server_error()
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT
+        } else {
+            err.is_timeout()
+                || err.is_connect()
+                || err.is_body()
+                || err.is_decode()
+                || err.is_request()
+        }
+    } else if let Some(err) = err.downcast_ref::<std::io::Error>() {
+        matches!(
+            err.kind(),
+            ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::TimedOut
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::Interrupted
+        )
+    } else {
+        false
+    }
+}
+
+// Everything we need to resume a download after a failed attempt
+struct DownloadState {
+    file: File,
+    hasher: Sha256,
+    received: u64,
+    total_len: Option<u32>,
+    progress: Option<ProgressBar>,
+}
+
+impl DownloadState {
+    fn restart(&mut self) -> Result<()> {
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.hasher = Sha256::new();
+        self.received = 0;
+        self.total_len = None;
+        Ok(())
+    }
+}
+
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    unique_name: Option<&str>,
+    target_path: &Path,
+    state: &mut DownloadState,
+) -> Result<()> {
     let zip_name = get_end_of_url(url);
-    let request = client.get(url);
+    let mut request = client.get(url);
+
+    if state.received != 0 {
+        debug!(
+            "Resuming download of {zip_name} from {} bytes",
+            state.received
+        );
+        request = request.header(RANGE, format!("bytes={}-", state.received));
+    }
 
-    let mut stream = File::create(target_path)?;
     let mut download = request.send().await?.error_for_status()?;
 
+    if state.received != 0 && download.status() != StatusCode::PARTIAL_CONTENT {
+        debug!("Server doesn't support resuming, restarting download of {zip_name}");
+        state.restart()?;
+    }
+
     let content_length = download
         .headers()
         .get("Content-Length")
@@ -60,40 +176,178 @@ async fn download_zip(url: &str, unique_name: Option<&str>, target_path: &Path)
                 .context("Failed to parse Content-Length")
         });
 
-    let (progress_type, progress_max) = match content_length {
-        Ok(max) => (ProgressType::Definite, max),
-        Err(why) => {
-            warn!("Failed to get content length for download: {why:?}");
-            // If we can't get the length or it's to large, just use the max value for
-            // the progress bar and make it indefinite.
-            (ProgressType::Indefinite, u32::MAX)
-        }
-    };
+    if state.received == 0 {
+        state.total_len = content_length.as_ref().ok().copied();
+    }
 
-    let mut progress = ProgressBar::new(
-        target_path.to_str().unwrap(),
-        unique_name,
-        progress_max,
-        &format!("Downloading {zip_name}"),
-        &format!("Failed to download {zip_name}"),
-        progress_type,
-        ProgressAction::Download,
-    );
+    if let Some(progress) = state.progress.as_mut() {
+        progress.set_progress(state.received.try_into().unwrap_or(u32::MAX));
+        progress.set_msg(&format!("Downloading {zip_name}"));
+    } else {
+        let (progress_type, progress_max) = match content_length {
+            Ok(max) => (ProgressType::Definite, max),
+            Err(why) => {
+                warn!("Failed to get content length for download: {why:?}");
+                // If we can't get the length or it's to large, just use the max value for
+                // the progress bar and make it indefinite.
+                (ProgressType::Indefinite, u32::MAX)
+            }
+        };
+        state.progress = Some(ProgressBar::new(
+            target_path.to_str().unwrap(),
+            unique_name,
+            progress_max,
+            &format!("Downloading {zip_name}"),
+            &format!("Failed to download {zip_name}"),
+            progress_type,
+            ProgressAction::Download,
+        ));
+    }
 
     while let Some(chunk) = download.chunk().await? {
-        progress.inc(chunk.len().try_into().unwrap());
-        stream.write_all(&chunk)?;
+        if let Some(progress) = state.progress.as_mut() {
+            progress.inc(chunk.len().try_into().unwrap());
+        }
+        state.hasher.update(&chunk);
+        state.received += chunk.len() as u64;
+        state.file.write_all(&chunk)?;
     }
 
-    progress.finish(true, &format!("Downloaded {zip_name}"));
+    state.file.flush()?;
+
+    if let Some(total_len) = state.total_len {
+        if state.received < total_len as u64 {
+            return Err(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "Download of {url} ended early (expected {total_len} bytes, got {})",
+                    state.received
+                ),
+            )
+            .into());
+        }
+    }
 
     Ok(())
 }
 
+/// Download a zip to `target_path`, hashing it as it's streamed to disk.
+/// Transient errors are retried according to `retry_policy`, resuming from where the download left off if the server supports it.
+/// If `expected_hash` is given the download is checked against it, and the file is removed if it doesn't match.
+///
+/// Returns the SHA-256 hash of the downloaded file as a lowercase hex string
+pub(crate) async fn download_zip(
+    url: &str,
+    unique_name: Option<&str>,
+    target_path: &Path,
+    expected_hash: Option<&str>,
+    retry_policy: &RetryPolicy,
+) -> Result<String> {
+    debug!(
+        "Begin download of {} to {}",
+        url,
+        target_path.to_str().unwrap()
+    );
+    let client = reqwest::Client::new();
+    let zip_name = get_end_of_url(url);
+
+    let mut state = DownloadState {
+        file: File::create(target_path)?,
+        hasher: Sha256::new(),
+        received: 0,
+        total_len: None,
+        progress: None,
+    };
+
+    let mut attempt = 0;
+
+    loop {
+        match download_attempt(&client, url, unique_name, target_path, &mut state).await {
+            Ok(_) => break,
+            Err(why) if attempt < retry_policy.max_retries && is_transient(&why) => {
+                attempt += 1;
+                let backoff = retry_policy.get_backoff(attempt);
+                warn!(
+                    "Download of {zip_name} failed, retrying in {}ms ({attempt}/{}): {why}",
+                    backoff.as_millis(),
+                    retry_policy.max_retries
+                );
+                if let Some(progress) = state.progress.as_ref() {
+                    progress.set_msg(&format!(
+                        "Retrying download of {zip_name} ({attempt}/{})",
+                        retry_policy.max_retries
+                    ));
+                }
+                tokio::time::sleep(backoff).await;
+            }
+            Err(why) => {
+                drop(state);
+                std::fs::remove_file(target_path).ok();
+                return Err(why);
+            }
+        }
+    }
+
+    let hash = format_hash(&state.hasher.finalize_reset());
+    let mut progress = state.progress.take();
+    drop(state);
+
+    if let Some(expected) = expected_hash {
+        if !expected.trim().eq_ignore_ascii_case(&hash) {
+            std::fs::remove_file(target_path).ok();
+            if let Some(progress) = progress.as_mut() {
+                progress.finish(false, "");
+            }
+            return Err(HashMismatchError {
+                url: url.to_string(),
+                expected: expected.trim().to_ascii_lowercase(),
+                actual: hash,
+            }
+            .into());
+        }
+    }
+
+    if let Some(progress) = progress.as_mut() {
+        progress.finish(true, &format!("Downloaded {zip_name}"));
+    }
+
+    Ok(hash)
+}
+
+// Get an archive from the cache, or download it to `download_path` (and add it to the cache) if it isn't there.
+// Keep the lease until the archive is extracted so it isn't evicted first.
+async fn download_zip_cached(
+    url: &str,
+    unique_name: &str,
+    version: &str,
+    expected_hash: Option<&str>,
+    config: &Config,
+    download_path: &Path,
+) -> Result<(PathBuf, Option<ArchiveLease>)> {
+    if let Some(lease) = lease_cached_archive(config, unique_name, version, expected_hash) {
+        info!("Using cached archive for {unique_name} {version}");
+        return Ok((lease.path().to_path_buf(), Some(lease)));
+    }
+    let hash = download_zip(
+        url,
+        Some(unique_name),
+        download_path,
+        expected_hash,
+        &config.retry_policy,
+    )
+    .await?;
+    if cache_enabled(config) {
+        if let Err(why) = add_to_cache(config, download_path, unique_name, version, &hash) {
+            warn!("Couldn't add archive for {unique_name} to the cache: {why:?}");
+        }
+    }
+    Ok((download_path.to_path_buf(), None))
+}
+
 // Does this mean that i'll have to re-open the archive to do anything with it? Yes.
 // Do I really care? No.
 // You want a better one make it pls thx.
-fn get_manifest_path_from_zip(zip_path: &PathBuf) -> Result<(String, PathBuf)> {
+fn get_manifest_path_from_zip(zip_path: &Path) -> Result<(String, PathBuf)> {
     let file = File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
 
@@ -117,15 +371,19 @@ fn get_manifest_path_from_zip(zip_path: &PathBuf) -> Result<(String, PathBuf)> {
     Err(anyhow!("Manifest not found in zip archive"))
 }
 
-fn get_unique_name_from_zip(zip_path: &PathBuf) -> Result<String> {
+// Read a mod's manifest straight from its archive, without extracting anything
+pub(crate) fn read_manifest_from_zip(zip_path: &Path) -> Result<ModManifest> {
     let (manifest_name, _) = get_manifest_path_from_zip(zip_path)?;
     let file = File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
     let mut manifest = archive.by_name(&manifest_name)?;
     let mut buf = String::new();
     manifest.read_to_string(&mut buf)?;
-    let manifest: ModManifest = serde_json::from_str(fix_bom(&buf))?;
-    Ok(manifest.unique_name)
+    Ok(serde_json::from_str(fix_bom(&buf))?)
+}
+
+fn get_unique_name_from_zip(zip_path: &Path) -> Result<String> {
+    Ok(read_manifest_from_zip(zip_path)?.unique_name)
 }
 
 fn extract_zip(zip_path: &PathBuf, target_path: &PathBuf, display_name: &str) -> Result<()> {
@@ -268,18 +526,25 @@ pub async fn download_and_install_owml(
     owml: &RemoteMod,
     prerelease: bool,
 ) -> Result<()> {
-    let url = if prerelease {
+    let (url, version) = if prerelease {
         owml.prerelease
             .as_ref()
-            .map(|p| &p.download_url)
+            .map(|p| (&p.download_url, &p.version))
             .context("No prerelease for OWML found")
     } else {
-        Ok(&owml.download_url)
+        Ok((&owml.download_url, &owml.version))
     }?;
     let target_path = PathBuf::from(&config.owml_path);
     let temp_dir = TempDir::new()?;
-    let download_path = temp_dir.path().join("OWML.zip");
-    download_zip(url, Some(OWML_UNIQUE_NAME), &download_path).await?;
+    let (download_path, _lease) = download_zip_cached(
+        url,
+        OWML_UNIQUE_NAME,
+        version,
+        None,
+        config,
+        &temp_dir.path().join("OWML.zip"),
+    )
+    .await?;
     extract_zip(&download_path, &target_path, "OWML")?;
 
     if config.owml_path.is_empty() {
@@ -386,7 +651,10 @@ pub fn install_mod_from_zip(
     }
 }
 
-/// Download and install a mod from a URL
+/// Download and install a mod from a URL.
+/// If `expected_hash` is given the archive's SHA-256 hash is checked before it's extracted,
+/// and a cached archive with that hash is used instead of downloading it again.
+/// Downloaded archives are added to the cache.
 ///
 /// ## Returns
 ///
@@ -395,6 +663,7 @@ pub fn install_mod_from_zip(
 /// ## Errors
 ///
 /// - We can't download the ZIP file
+/// - The ZIP file doesn't match `expected_hash`, this error will be a [HashMismatchError]
 /// - We can't extract the ZIP file
 /// - There is no `manifest.json` present in the archive / it's not readable
 ///
@@ -409,7 +678,7 @@ pub fn install_mod_from_zip(
 /// let config = Config::get(None).unwrap();
 /// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
 ///
-/// let new_mod = install_mod_from_url("https://example.com/Mod.zip", None, &config, &local_db).await.unwrap();
+/// let new_mod = install_mod_from_url("https://example.com/Mod.zip", None, None, &config, &local_db).await.unwrap();
 ///
 /// println!("Installed {}", new_mod.manifest.name);
 /// # });
@@ -418,6 +687,7 @@ pub fn install_mod_from_zip(
 pub async fn install_mod_from_url(
     url: &str,
     unique_name: Option<&str>,
+    expected_hash: Option<&str>,
     config: &Config,
     local_db: &LocalDatabase,
 ) -> Result<LocalMod> {
@@ -426,14 +696,85 @@ pub async fn install_mod_from_url(
     let temp_dir = TempDir::new()?;
     let download_path = temp_dir.path().join(format!("{zip_name}.zip"));
 
-    download_zip(url, unique_name, &download_path).await?;
-    let new_mod = install_mod_from_zip(&download_path, config, local_db)?;
+    // We don't know the mod or version until it's downloaded, so only the hash can find it in the cache
+    let lease = expected_hash.and_then(|hash| lease_cached_archive_by_hash(config, hash));
+    let zip_path = if let Some(lease) = lease.as_ref() {
+        info!("Using cached archive for {url}");
+        lease.path().to_path_buf()
+    } else {
+        let hash = download_zip(
+            url,
+            unique_name,
+            &download_path,
+            expected_hash,
+            &config.retry_policy,
+        )
+        .await?;
+        if cache_enabled(config) {
+            let res = read_manifest_from_zip(&download_path).and_then(|manifest| {
+                add_to_cache(
+                    config,
+                    &download_path,
+                    &manifest.unique_name,
+                    &manifest.version,
+                    &hash,
+                )
+            });
+            if let Err(why) = res {
+                warn!("Couldn't add archive from {url} to the cache: {why:?}");
+            }
+        }
+        download_path
+    };
+    let new_mod = install_mod_from_zip(&zip_path, config, local_db)?;
+    drop(lease);
 
     temp_dir.close()?;
 
     Ok(new_mod)
 }
 
+// Install a specific version of a mod, using the cache if we can and backing up the installed version first
+pub(crate) async fn install_mod_version(
+    url: &str,
+    unique_name: &str,
+    version: &str,
+    expected_hash: Option<&str>,
+    config: &Config,
+    local_db: &LocalDatabase,
+) -> Result<LocalMod> {
+    let temp_dir = TempDir::new()?;
+    let (zip_path, _lease) = download_zip_cached(
+        url,
+        unique_name,
+        version,
+        expected_hash,
+        config,
+        &temp_dir.path().join("mod.zip"),
+    )
+    .await?;
+    let new_mod = install_mod_archive(&zip_path, unique_name, version, config, local_db)?;
+    temp_dir.close()?;
+    Ok(new_mod)
+}
+
+// Install a downloaded archive of a specific version, backing up the installed version first if it's different
+fn install_mod_archive(
+    zip_path: &PathBuf,
+    unique_name: &str,
+    version: &str,
+    config: &Config,
+    local_db: &LocalDatabase,
+) -> Result<LocalMod> {
+    if let Some(old_mod) = local_db
+        .get_mod(unique_name)
+        .filter(|m| m.manifest.version != version)
+    {
+        backup_mod(config, old_mod)?;
+    }
+    install_mod_from_zip(zip_path, config, local_db)
+}
+
 /// A utility for deduplicating mod installs, pass this to [install_mods_parallel] and
 /// [install_mod_from_db] to prevent duplicate downloads during installation.
 ///
@@ -550,9 +891,11 @@ pub async fn install_mods_parallel(
             .get_mod(name)
             .with_context(|| format!("Mod {name} not found in database."))?;
 
-        let task = install_mod_from_url(
+        let task = install_mod_version(
             &remote_mod.download_url,
-            Some(&remote_mod.unique_name),
+            &remote_mod.unique_name,
+            &remote_mod.version,
+            remote_mod.hash.as_deref(),
             config,
             local_db,
         );
@@ -566,6 +909,256 @@ pub async fn install_mods_parallel(
     Ok(installed)
 }
 
+/// An install that's been resolved and downloaded, but not extracted yet.
+/// Get one with [prepare_install] and install it with [install_prepared].
+pub struct PreparedInstall {
+    /// The plan that will be installed, every mod in it is downloaded so its dependencies are known
+    pub plan: InstallPlan,
+    unique_name: String,
+    prerelease: bool,
+    root_mod_is_symbolic: bool,
+    archives: HashMap<String, PathBuf>,
+    _leases: Vec<ArchiveLease>,
+    _temp_dir: TempDir,
+}
+
+/// Resolve a mod and all of its dependencies (recursively) and download every archive in the plan, without installing anything.
+/// The database doesn't list dependencies, so they're read from each downloaded archive's manifest,
+/// and mods the new manifests depend on are resolved and downloaded until nothing new is found.
+///
+/// ## Returns
+///
+/// A [PreparedInstall] with the complete plan, pass it to [install_prepared] to install it.
+///
+/// ## Errors
+///
+/// - If the plan has any blocking problems, see [crate::resolve::InstallPlan::ensure_ok].
+/// - If the mod is installed and pinned to a different version in the config.
+/// - If we can't download **any** mod in the plan, or its archive has no readable manifest.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::config::Config;
+/// use owmods_core::download::{prepare_install, install_prepared};
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let remote_db = RemoteDatabase::fetch(&config.database_url).await.unwrap();
+///
+/// let prepared = prepare_install("xen.NewHorizons", &config, &remote_db, &local_db, false).await.unwrap();
+/// for planned_mod in prepared.plan.mods.iter() {
+///     println!("{} {}", planned_mod.unique_name, planned_mod.version);
+/// }
+/// install_prepared(prepared, &config, &local_db).await.unwrap();
+/// # });
+/// ```
+///
+pub async fn prepare_install(
+    unique_name: &str,
+    config: &Config,
+    remote_db: &RemoteDatabase,
+    local_db: &LocalDatabase,
+    prerelease: bool,
+) -> Result<PreparedInstall> {
+    let temp_dir = TempDir::new()?;
+    let requested = [unique_name.to_string()];
+    let mut archives: HashMap<String, PathBuf> = HashMap::new();
+    let mut leases: Vec<ArchiveLease> = vec![];
+    loop {
+        let plan = resolve_install_with_archives(
+            &requested, config, remote_db, local_db, prerelease, &archives,
+        );
+        plan.ensure_ok()?;
+        if let (Some(existing_mod), Some(target)) =
+            (local_db.get_mod(unique_name), plan.get_mod(unique_name))
+        {
+            if let Some(pin) = check_pin(
+                config,
+                unique_name,
+                &existing_mod.manifest.version,
+                &target.version,
+            ) {
+                bail!(
+                    "{unique_name} is pinned to {pin}, unpin it to install version {}",
+                    target.version
+                );
+            }
+        }
+
+        let pending = plan
+            .mods
+            .iter()
+            .filter(|m| !archives.contains_key(&m.unique_name))
+            .cloned()
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            let root_mod_is_symbolic = remote_db
+                .get_mod(unique_name)
+                .and_then(|m| m.tags.as_ref())
+                .is_some_and(|t| t.iter().any(|t| t == "pack"));
+            return Ok(PreparedInstall {
+                plan,
+                unique_name: unique_name.to_string(),
+                prerelease,
+                root_mod_is_symbolic,
+                archives,
+                _leases: leases,
+                _temp_dir: temp_dir,
+            });
+        }
+
+        let mut set = FuturesUnordered::new();
+        for planned_mod in pending {
+            let download_path = temp_dir.path().join(format!(
+                "{}.zip",
+                sanitize_file_name(&planned_mod.unique_name)
+            ));
+            set.push(async move {
+                let (zip_path, lease) = download_zip_cached(
+                    &planned_mod.download_url,
+                    &planned_mod.unique_name,
+                    &planned_mod.version,
+                    planned_mod.hash.as_deref(),
+                    config,
+                    &download_path,
+                )
+                .await?;
+                read_manifest_from_zip(&zip_path).with_context(|| {
+                    format!("Couldn't read the manifest of {}", planned_mod.unique_name)
+                })?;
+                Ok::<_, anyhow::Error>((planned_mod.unique_name, zip_path, lease))
+            });
+        }
+        while let Some(res) = set.next().await {
+            let (name, zip_path, lease) = res?;
+            archives.insert(name, zip_path);
+            leases.extend(lease);
+        }
+    }
+}
+
+/// Install a [PreparedInstall], extracting every mod in the order of its plan so dependencies are installed before the mods that need them.
+/// This will also send analytics events given you set `ANALYTICS_API_KEY`.
+///
+/// ## Returns
+///
+/// The newly installed requested mod
+///
+/// ## Errors
+///
+/// If we can't extract **any** mod in the plan.
+///
+pub async fn install_prepared(
+    prepared: PreparedInstall,
+    config: &Config,
+    local_db: &LocalDatabase,
+) -> Result<LocalMod> {
+    let existing_version = local_db
+        .get_mod(&prepared.unique_name)
+        .map(|m| m.manifest.version.clone());
+
+    let (to_install, dedup_lock) = {
+        let mut dedup = local_db.dedup.lock().await;
+        dedup.start_job();
+        (
+            dedup.dedup(&prepared.plan.dependencies()),
+            ModDeduperGuard(local_db.dedup.clone()),
+        )
+    };
+
+    let mut new_mod: Option<LocalMod> = None;
+    let mut dependency_installs: Vec<LocalMod> = vec![];
+    for planned_mod in prepared.plan.mods.iter() {
+        let is_root = planned_mod.unique_name == prepared.unique_name;
+        if !is_root && !to_install.contains(&planned_mod.unique_name) {
+            // Already being installed by another job
+            continue;
+        }
+        let zip_path = prepared
+            .archives
+            .get(&planned_mod.unique_name)
+            .with_context(|| format!("{} wasn't downloaded", planned_mod.unique_name))?;
+        let installed = install_mod_archive(
+            zip_path,
+            &planned_mod.unique_name,
+            &planned_mod.version,
+            config,
+            local_db,
+        )?;
+        if is_root {
+            new_mod = Some(installed);
+        } else {
+            dependency_installs.push(installed);
+        }
+    }
+
+    drop(dedup_lock);
+
+    let new_mod =
+        new_mod.with_context(|| format!("{} isn't in the install plan", prepared.unique_name))?;
+
+    if let Err(why) = record_explicit_install(config, &prepared.unique_name) {
+        warn!(
+            "Couldn't record {} as installed directly: {why:?}",
+            prepared.unique_name
+        );
+    }
+    let dependency_names = dependency_installs
+        .iter()
+        .map(|m| m.manifest.unique_name.clone())
+        .collect::<Vec<_>>();
+    if let Err(why) = record_dependency_installs(config, &dependency_names) {
+        warn!("Couldn't record dependency installs: {why:?}");
+    }
+
+    let direct_deps = new_mod.manifest.dependencies.clone().unwrap_or_default();
+    for installed_mod in dependency_installs.iter() {
+        let event = if prepared.root_mod_is_symbolic
+            && direct_deps.contains(&installed_mod.manifest.unique_name)
+        {
+            // Direct dependencies of symbolic mods should be counted as normal
+            // installs
+            AnalyticsEventName::ModInstall
+        } else {
+            AnalyticsEventName::ModRequiredInstall
+        };
+        send_analytics_deferred(event, &installed_mod.manifest.unique_name, config).await;
+    }
+
+    send_install_event(
+        &new_mod,
+        existing_version.as_deref(),
+        prepared.prerelease,
+        config,
+    )
+    .await;
+    Ok(new_mod)
+}
+
+async fn send_install_event(
+    new_mod: &LocalMod,
+    existing_version: Option<&str>,
+    prerelease: bool,
+    config: &Config,
+) {
+    let mod_event = if prerelease {
+        AnalyticsEventName::ModPrereleaseInstall
+    } else if let Some(existing_version) = existing_version {
+        if existing_version == new_mod.manifest.version {
+            AnalyticsEventName::ModReinstall
+        } else {
+            AnalyticsEventName::ModUpdate
+        }
+    } else {
+        AnalyticsEventName::ModInstall
+    };
+
+    send_analytics_deferred(mod_event, &new_mod.manifest.unique_name, config).await;
+}
+
 /// Install mod from the database with the given unique name.
 /// This should be the preferred method when installing a specific mod.
 /// It can also install prereleases and auto-install dependencies (recursively) as well.
@@ -574,6 +1167,8 @@ pub async fn install_mods_parallel(
 /// ## Errors
 ///
 /// - If you requested a prerelease and the mod doesn't have one.
+/// - If the mod is installed and pinned to a different version in the config.
+/// - If `recursive` is set and the dependencies can't be resolved, see [prepare_install].
 /// - If we can't install the target mod for any reason.
 /// - If we can't install **any** dependencies for any reason.
 ///
@@ -636,122 +1231,60 @@ pub async fn install_mod_from_db(
     prerelease: bool,
 ) -> Result<LocalMod> {
     let existing_mod = local_db.get_mod(unique_name);
-
-    let already_installed = existing_mod.is_some();
-    let existing_version = existing_mod
-        .as_ref()
-        .map(|m| m.manifest.version.clone())
-        .unwrap_or_default();
+    let existing_version = existing_mod.map(|m| m.manifest.version.clone());
 
     let remote_mod = remote_db
         .get_mod(unique_name)
         .with_context(|| format!("Mod {unique_name} not found"))?;
-    let target_url = if prerelease {
+    let (target_url, target_version, expected_hash) = if prerelease {
         let prerelease = remote_mod
             .prerelease
             .as_ref()
             .with_context(|| format!("No prerelease for {unique_name} found"))?;
-        let url = &prerelease.download_url;
         info!(
             "Using Prerelease {} for {}",
             prerelease.version, remote_mod.name
         );
-        url.clone()
+        (&prerelease.download_url, &prerelease.version, None)
     } else {
-        remote_mod.download_url.clone()
-    };
-
-    // Should we send `ModInstall` to analytics for direct dependencies?
-    let root_mod_is_symbolic = remote_mod
-        .tags
-        .as_ref()
-        .is_some_and(|t| t.iter().any(|t| t == "pack"));
-
-    let dedup_lock = {
-        let mut dedup = local_db.dedup.lock().await;
-        dedup.start_job();
-        ModDeduperGuard(local_db.dedup.clone())
+        (
+            &remote_mod.download_url,
+            &remote_mod.version,
+            remote_mod.hash.as_deref(),
+        )
     };
 
-    let new_mod =
-        install_mod_from_url(&target_url, Some(&remote_mod.unique_name), config, local_db).await?;
-
-    if recursive && new_mod.manifest.dependencies.is_some() {
-        let mut to_install = new_mod.manifest.dependencies.as_ref().unwrap().clone();
-        let mut installed: Vec<String> = local_db
-            .valid()
-            .filter_map(|m| {
-                if m.manifest.unique_name == *unique_name {
-                    None
-                } else {
-                    Some(m.manifest.unique_name.clone())
-                }
-            })
-            .collect();
-
-        installed.push(new_mod.manifest.unique_name.clone());
-
-        let mut count = 1;
+    if let Some(pin) = existing_mod
+        .and_then(|m| check_pin(config, unique_name, &m.manifest.version, target_version))
+    {
+        bail!("{unique_name} is pinned to {pin}, unpin it to install version {target_version}");
+    }
 
-        while !to_install.is_empty() {
-            debug!(
-                "Begin round {} of install with {} dependencies",
-                count,
-                installed.len()
-            );
-            let newly_installed = install_mods_parallel(
-                to_install
-                    .drain(..)
-                    .filter(|m| !installed.contains(m))
-                    .collect(),
-                config,
-                remote_db,
-                local_db,
-            )
-            .await?;
-            for installed_mod in newly_installed
-                .iter()
-                .filter(|m| &m.manifest.unique_name != unique_name)
-            {
-                let event = if count == 1 && root_mod_is_symbolic {
-                    // Direct dependencies of symbolic mods should be counted as normal
-                    // installs
-                    AnalyticsEventName::ModInstall
-                } else {
-                    AnalyticsEventName::ModRequiredInstall
-                };
-                send_analytics_deferred(event, &installed_mod.manifest.unique_name, config).await;
-            }
-            installed.append(
-                &mut newly_installed
-                    .iter()
-                    .map(|m| m.manifest.unique_name.to_owned())
-                    .collect(),
-            );
-            for new_mod in newly_installed.into_iter() {
-                if let Some(mut deps) = new_mod.manifest.dependencies {
-                    to_install.append(&mut deps);
-                }
-            }
-            count += 1;
+    if recursive {
+        // Download the whole plan up front so a missing dependency fails before anything is extracted
+        let prepared =
+            prepare_install(unique_name, config, remote_db, local_db, prerelease).await?;
+        for problem in prepared.plan.problems.iter() {
+            warn!("{problem}");
         }
+        return install_prepared(prepared, config, local_db).await;
     }
 
-    drop(dedup_lock);
+    let new_mod = install_mod_version(
+        target_url,
+        &remote_mod.unique_name,
+        target_version,
+        expected_hash,
+        config,
+        local_db,
+    )
+    .await?;
 
-    let mod_event = if prerelease {
-        AnalyticsEventName::ModPrereleaseInstall
-    } else if already_installed {
-        if existing_version == new_mod.manifest.version {
-            AnalyticsEventName::ModReinstall
-        } else {
-            AnalyticsEventName::ModUpdate
-        }
-    } else {
-        AnalyticsEventName::ModInstall
-    };
+    if let Err(why) = record_explicit_install(config, unique_name) {
+        warn!("Couldn't record {unique_name} as installed directly: {why:?}");
+    }
 
-    send_analytics_deferred(mod_event, unique_name, config).await;
+    send_install_event(&new_mod, existing_version.as_deref(), prerelease, config).await;
     Ok(new_mod)
 }
 
@@ -772,12 +1305,172 @@ mod tests {
         tokio_test::block_on(async {
             let dir = make_test_dir();
             let path = dir.path().join("test.zip");
-            download_zip(TEST_URL, None, &path).await.unwrap();
+            download_zip(TEST_URL, None, &path, None, &RetryPolicy::default())
+                .await
+                .unwrap();
             assert!(path.is_file());
             dir.close().unwrap();
         });
     }
 
+    #[test]
+    fn test_download_zip_hash() {
+        tokio_test::block_on(async {
+            let dir = make_test_dir();
+            let path = dir.path().join("test.zip");
+            let hash = download_zip(TEST_URL, None, &path, None, &RetryPolicy::default())
+                .await
+                .unwrap();
+            assert_eq!(hash, crate::file::get_file_hash(&path).unwrap());
+            download_zip(
+                TEST_URL,
+                None,
+                &path,
+                Some(&hash.to_ascii_uppercase()),
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+            dir.close().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_download_zip_hash_mismatch() {
+        tokio_test::block_on(async {
+            let dir = make_test_dir();
+            let path = dir.path().join("test.zip");
+            let err = download_zip(
+                TEST_URL,
+                None,
+                &path,
+                Some("abc123"),
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap_err();
+            let err = err.downcast::<HashMismatchError>().unwrap();
+            assert_eq!(err.expected, "abc123");
+            assert!(!path.is_file());
+            dir.close().unwrap();
+        });
+    }
+
+    // Serves `body` twice, the first response is cut off halfway through.
+    // If `support_range` is set the second response honors the Range header.
+    async fn serve_flaky(
+        body: &'static [u8],
+        support_range: bool,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/Flaky.zip", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = vec![];
+            for i in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let len = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok())
+                    .filter(|_| support_range);
+                let response = if i == 0 {
+                    let mut res = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes();
+                    res.extend_from_slice(&body[..body.len() / 2]);
+                    res
+                } else if let Some(start) = start {
+                    let mut res = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {start}-{}/{}\r\nConnection: close\r\n\r\n",
+                        body.len() - start,
+                        body.len() - 1,
+                        body.len()
+                    )
+                    .into_bytes();
+                    res.extend_from_slice(&body[start..]);
+                    res
+                } else {
+                    let mut res = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes();
+                    res.extend_from_slice(body);
+                    res
+                };
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.ok();
+                requests.push(request);
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    const FAST_RETRY: RetryPolicy = RetryPolicy {
+        max_retries: 2,
+        initial_backoff_ms: 10,
+        max_backoff_ms: 10,
+    };
+
+    #[test]
+    fn test_download_zip_resume() {
+        tokio_test::block_on(async {
+            const BODY: &[u8] = b"0123456789abcdefghij";
+            let (url, server) = serve_flaky(BODY, true).await;
+            let dir = make_test_dir();
+            let path = dir.path().join("test.zip");
+            let hash = download_zip(&url, None, &path, None, &FAST_RETRY)
+                .await
+                .unwrap();
+            let requests = server.await.unwrap();
+            assert!(requests[1].contains("range: bytes=10-"));
+            assert_eq!(std::fs::read(&path).unwrap(), BODY);
+            assert_eq!(hash, crate::file::get_file_hash(&path).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_download_zip_restart_without_range() {
+        tokio_test::block_on(async {
+            const BODY: &[u8] = b"0123456789abcdefghij";
+            let (url, server) = serve_flaky(BODY, false).await;
+            let dir = make_test_dir();
+            let path = dir.path().join("test.zip");
+            let hash = download_zip(&url, None, &path, None, &FAST_RETRY)
+                .await
+                .unwrap();
+            server.await.unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), BODY);
+            assert_eq!(hash, crate::file::get_file_hash(&path).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_download_zip_no_retries() {
+        tokio_test::block_on(async {
+            const BODY: &[u8] = b"0123456789abcdefghij";
+            let (url, _server) = serve_flaky(BODY, true).await;
+            let dir = make_test_dir();
+            let path = dir.path().join("test.zip");
+            let policy = RetryPolicy {
+                max_retries: 0,
+                ..FAST_RETRY
+            };
+            assert!(download_zip(&url, None, &path, None, &policy)
+                .await
+                .is_err());
+            assert!(!path.exists());
+        });
+    }
+
     #[test]
     fn test_get_manifest_path() {
         let path = get_test_file("Bwc9876.NestedManifest.zip");
@@ -874,7 +1567,7 @@ mod tests {
     fn test_install_mod_from_url() {
         tokio_test::block_on(async {
             let ctx = TestContext::new();
-            let new_mod = install_mod_from_url(TEST_URL, None, &ctx.config, &ctx.local_db)
+            let new_mod = install_mod_from_url(TEST_URL, None, None, &ctx.config, &ctx.local_db)
                 .await
                 .unwrap();
             let target_path = ctx.get_test_path("Bwc9876.TimeSaver");
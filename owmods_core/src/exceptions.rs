@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::socket::SocketMessage;
+
+lazy_static! {
+    static ref HEADER_REGEX: Regex =
+        Regex::new(r"^\s*(?:---> )?([A-Za-z_][\w.`+]*Exception)(?:: (.*))?$").unwrap();
+    static ref FRAME_REGEX: Regex =
+        Regex::new(r"^\s*at (.+?)(?: \[0x[0-9a-fA-F]+\])?(?: in (.+?))?\s*$").unwrap();
+}
+
+const FRAME_MARKER_PREFIX: &str = "--- ";
+
+/// A single frame of a stack trace
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    /// The method that was running, including its arguments, like `Foo.Bar (System.String name)`
+    pub method: String,
+    /// The file and line of the method if the game knew it, like `Foo.cs:12`
+    pub location: Option<String>,
+}
+
+impl StackFrame {
+    /// Parse a stack frame from a line like `at Foo.Bar () [0x00000] in Foo.cs:12`.
+    ///
+    /// ## Returns
+    ///
+    /// The frame, or `None` if the line isn't a stack frame.
+    ///
+    pub fn parse(line: &str) -> Option<Self> {
+        let captures = FRAME_REGEX.captures(line)?;
+        Some(Self {
+            method: captures[1].to_string(),
+            location: captures
+                .get(2)
+                .map(|m| m.as_str().to_string())
+                .filter(|l| l != "<filename unknown>:0"),
+        })
+    }
+}
+
+/// An exception the game logged, along with its stack trace
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogException {
+    /// The type of the exception, like `System.NullReferenceException`
+    pub exception_type: String,
+    /// The exception's message, this may be empty
+    pub message: String,
+    /// The stack trace of the exception, innermost frame first
+    pub frames: Vec<StackFrame>,
+    /// The name of the mod that logged the exception, if the game told us
+    pub source: Option<String>,
+}
+
+fn is_continuation(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with(FRAME_MARKER_PREFIX) || FRAME_REGEX.is_match(line)
+}
+
+impl LogException {
+    /// Parse an exception from a message, the message's first line has to look like a .NET exception header
+    /// (`System.Exception: Message`). Any `at ...` lines after it are parsed as the exception's stack trace.
+    ///
+    /// ## Returns
+    ///
+    /// The exception, or `None` if the message doesn't start with an exception header.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use owmods_core::exceptions::LogException;
+    /// use owmods_core::socket::{SocketMessage, SocketMessageType};
+    ///
+    /// let message = SocketMessage::make_internal(
+    ///     "System.NullReferenceException: Object reference not set to an instance of an object\n  at Foo.Bar () [0x00000] in Foo.cs:12",
+    ///     SocketMessageType::Error,
+    /// );
+    /// let exception = LogException::parse(&message).unwrap();
+    /// assert_eq!(exception.exception_type, "System.NullReferenceException");
+    /// assert_eq!(exception.frames[0].method, "Foo.Bar ()");
+    /// ```
+    ///
+    pub fn parse(message: &SocketMessage) -> Option<Self> {
+        let mut lines = message.message.lines();
+        let captures = HEADER_REGEX.captures(lines.next()?)?;
+        let mut exception = Self {
+            exception_type: captures[1].to_string(),
+            message: captures
+                .get(2)
+                .map(|m| m.as_str().trim().to_string())
+                .unwrap_or_default(),
+            frames: vec![],
+            source: message.sender_name.clone(),
+        };
+        for line in lines {
+            if let Some(frame) = StackFrame::parse(line) {
+                exception.frames.push(frame);
+            } else if !is_continuation(line) && exception.frames.is_empty() {
+                // Messages can span multiple lines before the stack trace starts
+                exception.message.push('\n');
+                exception.message.push_str(line);
+            }
+        }
+        Some(exception)
+    }
+
+    /// Add the stack frames in a message to this exception, if the message is only stack frames
+    /// and was sent by the same mod that sent the exception.
+    /// Use this when the game sends an exception's stack trace as separate messages.
+    ///
+    /// ## Returns
+    ///
+    /// If the message was part of this exception and its frames were added.
+    ///
+    pub fn extend(&mut self, message: &SocketMessage) -> bool {
+        if message.sender_name != self.source
+            || message.message.trim().is_empty()
+            || !message.message.lines().all(is_continuation)
+        {
+            return false;
+        }
+        self.frames
+            .extend(message.message.lines().filter_map(StackFrame::parse));
+        true
+    }
+
+    /// Get the name of the mod this exception came from, or `"Unknown"`
+    pub fn source_name(&self) -> &str {
+        self.source.as_deref().unwrap_or("Unknown")
+    }
+}
+
+/// A message from the game, with the exception it contains if it has one
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupedMessage {
+    /// The message, when an exception's stack trace was sent as multiple messages this has all of their text
+    pub message: SocketMessage,
+    /// The exception in the message
+    pub exception: Option<LogException>,
+}
+
+/// A log processing stage that merges exceptions with the stack frames sent after them.
+///
+/// Messages that aren't exceptions are passed through right away. An exception is held onto until
+/// a message that isn't part of its stack trace comes in (or [ExceptionGrouper::flush] is called),
+/// so callers reading a live stream should flush every so often when no messages are coming in.
+///
+/// ## Examples
+///
+/// ```
+/// use owmods_core::exceptions::ExceptionGrouper;
+/// use owmods_core::socket::{SocketMessage, SocketMessageType};
+///
+/// let mut grouper = ExceptionGrouper::default();
+/// let messages = [
+///     "System.Exception: Oh no",
+///     "  at Foo.Bar () [0x00000] in <filename unknown>:0",
+///     "Something else",
+/// ];
+/// let mut grouped = vec![];
+/// for message in messages {
+///     let message = SocketMessage::make_internal(message, SocketMessageType::Error);
+///     grouped.extend(grouper.push(message));
+/// }
+/// grouped.extend(grouper.flush());
+///
+/// assert_eq!(grouped.len(), 2);
+/// assert_eq!(grouped[0].exception.as_ref().unwrap().frames.len(), 1);
+/// assert_eq!(grouper.counts().get("Manager"), Some(&1));
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct ExceptionGrouper {
+    pending: Option<GroupedMessage>,
+    counts: BTreeMap<String, u32>,
+}
+
+impl ExceptionGrouper {
+    /// Process a message from the game.
+    ///
+    /// ## Returns
+    ///
+    /// The messages that are done being grouped, in the order they were received.
+    ///
+    pub fn push(&mut self, message: SocketMessage) -> Vec<GroupedMessage> {
+        if let Some(pending) = self.pending.as_mut() {
+            if pending.exception.as_mut().unwrap().extend(&message) {
+                pending.message.message.push('\n');
+                pending.message.message.push_str(&message.message);
+                return vec![];
+            }
+        }
+        let mut done = self.flush().into_iter().collect::<Vec<_>>();
+        if let Some(exception) = LogException::parse(&message) {
+            *self
+                .counts
+                .entry(exception.source_name().to_string())
+                .or_default() += 1;
+            self.pending = Some(GroupedMessage {
+                message,
+                exception: Some(exception),
+            });
+        } else {
+            done.push(GroupedMessage {
+                message,
+                exception: None,
+            });
+        }
+        done
+    }
+
+    /// Stop waiting for more of the current exception's stack trace.
+    ///
+    /// ## Returns
+    ///
+    /// The exception that was being grouped, if there was one.
+    ///
+    pub fn flush(&mut self) -> Option<GroupedMessage> {
+        self.pending.take()
+    }
+
+    /// Check if an exception is being held onto while waiting for more of its stack trace
+    pub fn is_grouping(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Get how many exceptions each mod has logged so far
+    pub fn counts(&self) -> &BTreeMap<String, u32> {
+        &self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::socket::SocketMessageType;
+
+    use super::*;
+
+    fn make_error(sender: &str, message: &str) -> SocketMessage {
+        SocketMessage::get_test(sender, message, SocketMessageType::Error)
+    }
+
+    #[test]
+    fn test_parse_exception() {
+        let message = make_error(
+            "NewHorizons",
+            "System.IO.FileNotFoundException: Couldn't find planet.json\nIn the planets folder\n  at NewHorizons.Main.LoadPlanet (System.String path) [0x0001a] in Main.cs:42\n  at NewHorizons.Main.Start () [0x00000] in <filename unknown>:0\n--- End of stack trace from previous location ---",
+        );
+        let exception = LogException::parse(&message).unwrap();
+        assert_eq!(exception.exception_type, "System.IO.FileNotFoundException");
+        assert_eq!(
+            exception.message,
+            "Couldn't find planet.json\nIn the planets folder"
+        );
+        assert_eq!(exception.frames.len(), 2);
+        assert_eq!(
+            exception.frames[0].method,
+            "NewHorizons.Main.LoadPlanet (System.String path)"
+        );
+        assert_eq!(exception.frames[0].location.as_deref(), Some("Main.cs:42"));
+        assert_eq!(exception.frames[1].location, None);
+        assert_eq!(exception.source_name(), "NewHorizons");
+    }
+
+    #[test]
+    fn test_parse_not_exception() {
+        assert!(LogException::parse(&make_error("A", "Loaded 3 planets")).is_none());
+        assert!(LogException::parse(&make_error("A", "Error: not an exception")).is_none());
+        assert!(LogException::parse(&make_error("A", "")).is_none());
+    }
+
+    #[test]
+    fn test_grouper() {
+        let mut grouper = ExceptionGrouper::default();
+        let mut grouped = vec![];
+        for (sender, message) in [
+            ("A", "NullReferenceException"),
+            ("A", "  at Foo.Bar () [0x00000] in <filename unknown>:0"),
+            ("A", "  at Foo.Baz () [0x00000] in <filename unknown>:0"),
+            // A different mod's stack frame isn't part of A's exception
+            ("B", "  at Other.Thing ()"),
+            ("B", "System.Exception: First"),
+            ("B", "System.Exception: Second"),
+        ] {
+            grouped.extend(grouper.push(make_error(sender, message)));
+        }
+        grouped.extend(grouper.flush());
+
+        assert_eq!(grouped.len(), 4);
+        let first = grouped[0].exception.as_ref().unwrap();
+        assert_eq!(first.exception_type, "NullReferenceException");
+        assert_eq!(first.frames.len(), 2);
+        assert_eq!(grouped[0].message.message.lines().count(), 3);
+        assert!(grouped[1].exception.is_none());
+        assert_eq!(grouped[2].exception.as_ref().unwrap().message, "First");
+        assert_eq!(grouped[3].exception.as_ref().unwrap().message, "Second");
+        assert_eq!(grouper.counts().get("A"), Some(&1));
+        assert_eq!(grouper.counts().get("B"), Some(&2));
+    }
+}
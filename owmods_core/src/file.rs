@@ -1,11 +1,14 @@
 use std::{
-    fs::{create_dir_all, read_to_string},
+    fmt::Write,
+    fs::{copy, create_dir_all, read_dir, read_to_string, File},
+    io::{BufReader, Read},
     path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Context, Result};
 use directories::{BaseDirs, ProjectDirs};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::constants::OLD_MANAGER_FOLDER_NAME;
 
@@ -172,6 +175,114 @@ pub fn fix_bom(str: &str) -> &str {
     str.strip_prefix('\u{FEFF}').unwrap_or(str)
 }
 
+/// Make a string safe to use as a single file or folder name
+pub(crate) fn sanitize_file_name(part: &str) -> String {
+    part.replace(['/', '\\', ':', '_'], "-")
+}
+
+// Characters that can't be in a file name on at least one OS
+const INVALID_FILE_NAME_CHARS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+// Names Windows reserves for devices, with or without an extension
+const RESERVED_FILE_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check a user-given name can be used as a file name on every OS, `kind` is used in the error (e.g. "profile").
+///
+/// ## Returns
+///
+/// The name with surrounding whitespace trimmed
+///
+/// ## Errors
+///
+/// If the name is empty, only dots, ends with a dot, is reserved on Windows,
+/// or contains a path separator, control character, or one of `: * ? " < > |`.
+///
+pub(crate) fn validate_file_name<'a>(name: &'a str, kind: &str) -> Result<&'a str> {
+    let name = name.trim();
+    let stem = name.split('.').next().unwrap_or(name);
+    if name.is_empty()
+        || name.ends_with('.')
+        || name.contains(|c: char| c.is_control() || INVALID_FILE_NAME_CHARS.contains(&c))
+        || RESERVED_FILE_NAMES
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        Err(anyhow!(
+            "Invalid {kind} name \"{name}\", names can't be empty, end with a dot, or contain / \\ : * ? \" < > |"
+        ))
+    } else {
+        Ok(name)
+    }
+}
+
+/// Recursively copy the contents of a folder into another, creating it if needed
+pub(crate) fn copy_dir_all(from: &Path, to: &Path) -> Result<()> {
+    create_dir_all(to)?;
+    for entry in read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            copy(entry.path(), &target).with_context(|| {
+                format!(
+                    "Failed to copy {} to {}",
+                    entry.path().display(),
+                    target.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Format a SHA-256 digest as a lowercase hex string
+pub(crate) fn format_hash(digest: &[u8]) -> String {
+    digest.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+/// Get the SHA-256 hash of a file
+///
+/// ## Returns
+///
+/// The hash as a lowercase hex string
+///
+/// ## Errors
+///
+/// If we can't read the file
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::file::get_file_hash;
+/// use std::path::PathBuf;
+///
+/// let hash = get_file_hash(&PathBuf::from("Mod.zip")).unwrap();
+/// println!("SHA-256: {hash}");
+/// ```
+///
+pub fn get_file_hash(path: &Path) -> Result<String> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(format_hash(&hasher.finalize()))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -185,4 +296,47 @@ mod tests {
         assert!(check_file_matches_paths(test_path, &[test_parent]));
         assert!(!check_file_matches_paths(test_path, &[unrelated_parent]),);
     }
+
+    #[test]
+    fn test_validate_file_name() {
+        assert_eq!(validate_file_name(" v1.2 ", "profile").unwrap(), "v1.2");
+        assert!(validate_file_name("My Mods (old)", "profile").is_ok());
+        assert!(validate_file_name("Console", "profile").is_ok());
+        for invalid in [
+            "",
+            "  ",
+            ".",
+            "..",
+            "name.",
+            "a/b",
+            "a\\b",
+            "../up",
+            "a:b",
+            "a*",
+            "a?",
+            "a\"b",
+            "<a>",
+            "a|b",
+            "tab\tname",
+            "CON",
+            "nul.json",
+            "com1",
+        ] {
+            assert!(
+                validate_file_name(invalid, "profile").is_err(),
+                "{invalid:?} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_file_hash() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("test.txt");
+        std::fs::write(&path, "hello").unwrap();
+        assert_eq!(
+            get_file_hash(&path).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
 }
@@ -17,6 +17,38 @@ Then you can play all of our cool mods!
 To stop this dialog from displaying please edit your game path in settings to not point to an Outer Worlds install. 
 You can leave it blank to have OWML auto-detect it";
 
+/// Check if a game path points to an install of The Outer Worlds instead of Outer Wilds
+pub fn is_outer_worlds_path(game_path: &str) -> bool {
+    let lower_path = game_path.to_lowercase();
+    OUTER_WORLDS_FOLDER_NAMES
+        .iter()
+        .any(|name| lower_path.contains(name))
+}
+
+/// Check if Mono can be found, OWML needs it to launch the game on Linux and macOS.
+/// Uses the `MONO_BINARY` environment variable if it's set, otherwise looks for the default Mono install.
+///
+/// ## Returns
+///
+/// If Mono was found, this is always `true` on Windows.
+///
+pub fn has_mono() -> bool {
+    #[cfg(unix)]
+    {
+        let mono = PathBuf::from(get_mono_binary());
+        if mono.components().count() > 1 {
+            mono.is_file()
+        } else {
+            std::env::var_os("PATH")
+                .is_some_and(|paths| std::env::split_paths(&paths).any(|p| p.join(&mono).is_file()))
+        }
+    }
+    #[cfg(windows)]
+    {
+        true
+    }
+}
+
 /// Launch the game using the given port for logs.  
 /// If no port is given, the output of OWML.Launcher.exe will be written to stdout.  
 /// You can set `open_in_new_window` to `true` to make the command open in a new cmd window (**Windows Only**).  
@@ -68,11 +100,7 @@ pub async fn launch_game(
             .stderr(Stdio::piped());
         // Sometimes OWML.Launcher.exe doesn't like setting the socket port, just do it ourselves.
         let mut owml_config = OWMLConfig::get(config)?;
-        let lower_path = owml_config.game_path.to_lowercase();
-        if OUTER_WORLDS_FOLDER_NAMES
-            .iter()
-            .any(|name| lower_path.contains(name))
-        {
+        if is_outer_worlds_path(&owml_config.game_path) {
             return Err(anyhow!(OUTER_WORLDS_TEXT));
         }
         owml_config.socket_port = *port;
@@ -139,10 +167,15 @@ fn get_default_mono_binary() -> String {
 }
 
 #[cfg(unix)]
-fn get_cmd(config: &Config, _: bool) -> Result<Command> {
-    let mono = std::env::var("MONO_BINARY")
+fn get_mono_binary() -> String {
+    std::env::var("MONO_BINARY")
         .ok()
-        .unwrap_or_else(get_default_mono_binary);
+        .unwrap_or_else(get_default_mono_binary)
+}
+
+#[cfg(unix)]
+fn get_cmd(config: &Config, _: bool) -> Result<Command> {
+    let mono = get_mono_binary();
 
     let owml_path = PathBuf::from(&config.owml_path).join(OWML_EXE_NAME);
     let exe_path = owml_path.to_str().unwrap();
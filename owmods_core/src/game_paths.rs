@@ -0,0 +1,677 @@
+use std::{
+    fs::{read_dir, read_to_string, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use directories::BaseDirs;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use typeshare::typeshare;
+
+use crate::game::is_outer_worlds_path;
+
+const STEAM_APP_ID: &str = "753640";
+const STEAM_INSTALL_DIR: &str = "Outer Wilds";
+const GAME_EXE_NAME: &str = "OuterWilds.exe";
+const GAME_DATA_FOLDER_NAME: &str = "OuterWilds_Data";
+const GAME_TITLE: &str = "outer wilds";
+const UNITY_GAME_MANAGERS_NAME: &str = "globalgamemanagers";
+const DLC_MARKER: &str = "dlc";
+
+/// Where a possible game install was found, in order of how much we trust it
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GamePathSource {
+    /// A Steam library that has the game installed
+    Steam,
+    /// A Steam library of the Flatpak version of Steam
+    SteamFlatpak,
+    /// The Epic Games Launcher's install manifests
+    Epic,
+    /// The Heroic Games Launcher's list of installed Epic games
+    Heroic,
+    /// A common install location that wasn't found through a launcher
+    Common,
+}
+
+/// A folder Outer Wilds is installed in
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GamePathCandidate {
+    /// The path to the folder containing `OuterWilds.exe`
+    pub path: String,
+    /// Where we found it
+    pub source: GamePathSource,
+}
+
+/// Something wrong with a game install, see [validate_game_path]
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum GamePathProblem {
+    /// The folder doesn't exist
+    NotFound,
+    /// The folder is an install of The Outer Worlds, not Outer Wilds
+    OuterWorlds,
+    /// `OuterWilds.exe` is missing
+    MissingExe,
+    /// The `OuterWilds_Data` folder is missing
+    MissingData,
+}
+
+/// What we know about the game installed in a folder, see [validate_game_path]
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GameInstall {
+    /// The folder that was checked
+    pub path: String,
+    /// Everything wrong with the install, empty if it's valid
+    pub problems: Vec<GamePathProblem>,
+    /// The version of Unity the game was built with, if we could read it.
+    /// This is *not* the game's own version, which we don't read.
+    pub unity_version: Option<String>,
+    /// Whether anything that looks like the Echoes of the Eye DLC's content was found.
+    /// This is a best guess from asset bundle names, not a check of the DLC's files or whether it's owned.
+    pub has_dlc: bool,
+}
+
+impl GameInstall {
+    /// Check if the folder contains a usable Outer Wilds install
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check if a folder contains an Outer Wilds install (`OuterWilds.exe` and the `OuterWilds_Data` folder)
+pub fn is_game_dir(path: &Path) -> bool {
+    path.join(GAME_EXE_NAME).is_file() && path.join(GAME_DATA_FOLDER_NAME).is_dir()
+}
+
+// The version is stored as a string right after the header of globalgamemanagers,
+// where the header ends depends on the version of the file format
+fn read_unity_version(data_dir: &Path) -> Option<String> {
+    let mut header = vec![];
+    File::open(data_dir.join(UNITY_GAME_MANAGERS_NAME))
+        .ok()?
+        .take(128)
+        .read_to_end(&mut header)
+        .ok()?;
+    let format = u32::from_be_bytes(header.get(8..12)?.try_into().ok()?);
+    let start = if format >= 22 { 48 } else { 20 };
+    let rest = header.get(start..)?;
+    let end = rest.iter().position(|b| *b == 0)?;
+    let version = std::str::from_utf8(&rest[..end]).ok()?;
+    version
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then(|| version.to_string())
+}
+
+// Best guess only: we assume the DLC's content is shipped as extra asset bundles with "dlc" in their names.
+// The exact bundle names aren't checked, so a renamed bundle would be missed and any unrelated bundle
+// with "dlc" in its name would be counted.
+fn has_dlc_content(data_dir: &Path) -> bool {
+    let streaming_assets = data_dir.join("StreamingAssets");
+    [streaming_assets.join("AssetBundles"), streaming_assets]
+        .iter()
+        .filter_map(|dir| read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok()))
+        .any(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(DLC_MARKER)
+        })
+}
+
+/// Check that a folder contains a usable Outer Wilds install and find out what we can about it.
+///
+/// ## Returns
+///
+/// A [GameInstall] with any [GamePathProblem]s, the Unity version, and whether the Echoes of the Eye DLC seems to be installed.
+/// If the folder doesn't exist or is The Outer Worlds, that's the only problem reported.
+///
+/// Only the Unity version is read, not the game's version.
+/// DLC detection looks for asset bundles with "dlc" in their name, so treat [GameInstall::has_dlc] as a hint rather than a guarantee.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::game_paths::validate_game_path;
+/// use owmods_core::owml::OWMLConfig;
+/// use std::path::Path;
+///
+/// let config = Config::get(None).unwrap();
+/// let owml_config = OWMLConfig::get(&config).unwrap();
+///
+/// let install = validate_game_path(Path::new(&owml_config.game_path));
+/// if install.is_valid() {
+///     println!("Unity {:?}, DLC installed: {}", install.unity_version, install.has_dlc);
+/// } else {
+///     println!("Problems: {:?}", install.problems);
+/// }
+/// ```
+///
+pub fn validate_game_path(path: &Path) -> GameInstall {
+    let mut install = GameInstall {
+        path: path.to_string_lossy().to_string(),
+        problems: vec![],
+        unity_version: None,
+        has_dlc: false,
+    };
+    if is_outer_worlds_path(&install.path) {
+        install.problems.push(GamePathProblem::OuterWorlds);
+        return install;
+    }
+    if !path.is_dir() {
+        install.problems.push(GamePathProblem::NotFound);
+        return install;
+    }
+    if !path.join(GAME_EXE_NAME).is_file() {
+        install.problems.push(GamePathProblem::MissingExe);
+    }
+    let data_dir = path.join(GAME_DATA_FOLDER_NAME);
+    if data_dir.is_dir() {
+        install.unity_version = read_unity_version(&data_dir);
+        install.has_dlc = has_dlc_content(&data_dir);
+    } else {
+        install.problems.push(GamePathProblem::MissingData);
+    }
+    install
+}
+
+#[derive(Debug, PartialEq)]
+enum Vdf {
+    Str(String),
+    Obj(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Obj(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            Vdf::Str(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Vdf::Str(s) => Some(s),
+            Vdf::Obj(_) => None,
+        }
+    }
+}
+
+fn tokenize_vdf(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => tokens.push(c.to_string()),
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                token.push(match escaped {
+                                    'n' => '\n',
+                                    't' => '\t',
+                                    other => other,
+                                });
+                            }
+                        }
+                        '"' => break,
+                        _ => token.push(c),
+                    }
+                }
+                tokens.push(token);
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+            c if c.is_whitespace() => {}
+            _ => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}\"".contains(*c)) {
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
+fn parse_vdf_entries(tokens: &mut std::vec::IntoIter<String>) -> Vec<(String, Vdf)> {
+    let mut entries = vec![];
+    while let Some(key) = tokens.next() {
+        if key == "}" {
+            break;
+        }
+        match tokens.next() {
+            Some(open) if open == "{" => entries.push((key, Vdf::Obj(parse_vdf_entries(tokens)))),
+            Some(value) => entries.push((key, Vdf::Str(value))),
+            None => break,
+        }
+    }
+    entries
+}
+
+// Steam's files are always a single root key, this returns a "virtual" object containing it
+fn parse_vdf(text: &str) -> Vdf {
+    Vdf::Obj(parse_vdf_entries(&mut tokenize_vdf(text).into_iter()))
+}
+
+fn read_vdf(path: &Path) -> Option<Vdf> {
+    read_to_string(path).ok().map(|t| parse_vdf(&t))
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&read_to_string(path).ok()?).ok()
+}
+
+fn is_game_title(title: Option<&Value>) -> bool {
+    title
+        .and_then(|t| t.as_str())
+        .is_some_and(|t| t.to_lowercase().contains(GAME_TITLE))
+}
+
+#[derive(Debug, Default)]
+struct SearchLocations {
+    steam_roots: Vec<(PathBuf, GamePathSource)>,
+    heroic_configs: Vec<PathBuf>,
+    epic_manifests: Option<PathBuf>,
+    common: Vec<PathBuf>,
+}
+
+impl SearchLocations {
+    #[cfg(target_os = "linux")]
+    fn for_home(home: &Path) -> Self {
+        let flatpak_steam = home.join(".var/app/com.valvesoftware.Steam");
+        Self {
+            steam_roots: vec![
+                (home.join(".steam/steam"), GamePathSource::Steam),
+                (home.join(".local/share/Steam"), GamePathSource::Steam),
+                (
+                    home.join("snap/steam/common/.local/share/Steam"),
+                    GamePathSource::Steam,
+                ),
+                (
+                    flatpak_steam.join(".local/share/Steam"),
+                    GamePathSource::SteamFlatpak,
+                ),
+                (
+                    flatpak_steam.join("data/Steam"),
+                    GamePathSource::SteamFlatpak,
+                ),
+            ],
+            heroic_configs: vec![
+                home.join(".config/heroic"),
+                home.join(".var/app/com.heroicgameslauncher.hgl/config/heroic"),
+            ],
+            epic_manifests: None,
+            common: vec![],
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn for_home(home: &Path) -> Self {
+        Self {
+            steam_roots: vec![(
+                home.join("Library/Application Support/Steam"),
+                GamePathSource::Steam,
+            )],
+            heroic_configs: vec![home.join("Library/Application Support/heroic")],
+            epic_manifests: None,
+            common: vec![],
+        }
+    }
+
+    #[cfg(windows)]
+    fn for_home(_: &Path) -> Self {
+        let env_path = |name: &str, default: &str| {
+            PathBuf::from(std::env::var(name).unwrap_or_else(|_| default.to_string()))
+        };
+        let program_files = env_path("ProgramFiles", r"C:\Program Files");
+        let program_files_x86 = env_path("ProgramFiles(x86)", r"C:\Program Files (x86)");
+        let program_data = env_path("ProgramData", r"C:\ProgramData");
+        Self {
+            steam_roots: vec![(program_files_x86.join("Steam"), GamePathSource::Steam)],
+            heroic_configs: vec![],
+            epic_manifests: Some(program_data.join(r"Epic\EpicGamesLauncher\Data\Manifests")),
+            common: vec![
+                program_files.join(r"Epic Games\OuterWilds"),
+                PathBuf::from(r"C:\XboxGames\Outer Wilds\Content"),
+            ],
+        }
+    }
+}
+
+fn find_steam_paths(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+    let library_folders = read_vdf(&root.join("steamapps/libraryfolders.vdf"))
+        .or_else(|| read_vdf(&root.join("config/libraryfolders.vdf")));
+    if let Some(Vdf::Obj(folders)) = library_folders
+        .as_ref()
+        .and_then(|v| v.get("libraryfolders"))
+    {
+        for (_, folder) in folders {
+            // Older versions of the file map indices directly to paths
+            let path = folder
+                .get("path")
+                .and_then(Vdf::as_str)
+                .or_else(|| folder.as_str());
+            if let Some(path) = path {
+                libraries.push(PathBuf::from(path));
+            }
+        }
+    }
+    libraries
+        .into_iter()
+        .map(|library| {
+            let steamapps = library.join("steamapps");
+            let install_dir = read_vdf(&steamapps.join(format!("appmanifest_{STEAM_APP_ID}.acf")))
+                .and_then(|m| {
+                    m.get("AppState")?
+                        .get("installdir")?
+                        .as_str()
+                        .map(|s| s.to_string())
+                })
+                .unwrap_or_else(|| STEAM_INSTALL_DIR.to_string());
+            steamapps.join("common").join(install_dir)
+        })
+        .collect()
+}
+
+fn find_heroic_paths(config_dir: &Path) -> Vec<PathBuf> {
+    let installed = read_json(&config_dir.join("legendaryConfig/legendary/installed.json"));
+    let Some(Value::Object(games)) = installed else {
+        return vec![];
+    };
+    games
+        .values()
+        .filter(|g| is_game_title(g.get("title")))
+        .filter_map(|g| g.get("install_path")?.as_str().map(PathBuf::from))
+        .collect()
+}
+
+fn find_epic_paths(manifests_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = read_dir(manifests_dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "item"))
+        .filter_map(|e| read_json(&e.path()))
+        .filter(|m| is_game_title(m.get("DisplayName")))
+        .filter_map(|m| m.get("InstallLocation")?.as_str().map(PathBuf::from))
+        .collect()
+}
+
+fn find_game_paths_in(locations: &SearchLocations) -> Vec<GamePathCandidate> {
+    let mut found = vec![];
+    for (root, source) in locations.steam_roots.iter() {
+        found.extend(find_steam_paths(root).into_iter().map(|p| (p, *source)));
+    }
+    if let Some(manifests) = &locations.epic_manifests {
+        found.extend(
+            find_epic_paths(manifests)
+                .into_iter()
+                .map(|p| (p, GamePathSource::Epic)),
+        );
+    }
+    for config_dir in locations.heroic_configs.iter() {
+        found.extend(
+            find_heroic_paths(config_dir)
+                .into_iter()
+                .map(|p| (p, GamePathSource::Heroic)),
+        );
+    }
+    found.extend(
+        locations
+            .common
+            .iter()
+            .map(|p| (p.clone(), GamePathSource::Common)),
+    );
+
+    // The path we show, where it actually points (for comparing), and where it was found
+    let mut candidates: Vec<(PathBuf, PathBuf, GamePathSource)> = vec![];
+    for (path, source) in found {
+        if !is_game_dir(&path) {
+            continue;
+        }
+        // ~/.steam/steam is usually a link to another root, so compare where paths actually point.
+        // The canonical path is only used for comparing, on Windows it has a `\\?\` prefix we don't want to show.
+        let real_path = path.canonicalize().unwrap_or_else(|_| path.clone());
+        match candidates.iter_mut().find(|(_, p, _)| p == &real_path) {
+            Some((existing_path, _, existing)) => {
+                if source < *existing {
+                    *existing_path = path;
+                    *existing = source;
+                }
+            }
+            None => candidates.push((path, real_path, source)),
+        }
+    }
+    candidates.sort_by_key(|(_, _, source)| *source);
+    candidates
+        .into_iter()
+        .filter_map(|(path, _, source)| {
+            Some(GamePathCandidate {
+                path: path.to_str()?.to_string(),
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Look for Outer Wilds installs in Steam libraries (including Flatpak Steam and secondary library folders),
+/// the Epic Games Launcher, the Heroic Games Launcher, and common install locations.
+/// Only folders that actually contain the game (see [is_game_dir]) are returned.
+///
+/// ## Returns
+///
+/// The installs that were found, the one most likely to be right first.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::game_paths::find_game_paths;
+///
+/// for candidate in find_game_paths() {
+///     println!("{} ({:?})", candidate.path, candidate.source);
+/// }
+/// ```
+///
+pub fn find_game_paths() -> Vec<GamePathCandidate> {
+    let Some(dirs) = BaseDirs::new() else {
+        debug!("Can't get the home directory, not looking for the game");
+        return vec![];
+    };
+    find_game_paths_in(&SearchLocations::for_home(dirs.home_dir()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs::{create_dir_all, write};
+
+    use crate::test_utils::TestContext;
+
+    use super::*;
+
+    fn make_game(path: &Path) {
+        create_dir_all(path.join(GAME_DATA_FOLDER_NAME)).unwrap();
+        write(path.join(GAME_EXE_NAME), "").unwrap();
+    }
+
+    #[test]
+    fn test_validate_game_path() {
+        let ctx = TestContext::new();
+        let game = ctx.temp_dir.path().join("Outer Wilds");
+        make_game(&game);
+
+        let install = validate_game_path(&game);
+        assert!(install.is_valid());
+        assert_eq!(install.unity_version, None);
+        assert!(!install.has_dlc);
+
+        let data_dir = game.join(GAME_DATA_FOLDER_NAME);
+        let mut header = vec![0; 20];
+        header[8..12].copy_from_slice(&21_u32.to_be_bytes());
+        header.extend_from_slice(b"2019.4.39f1\0more data");
+        write(data_dir.join(UNITY_GAME_MANAGERS_NAME), header).unwrap();
+        create_dir_all(data_dir.join("StreamingAssets/AssetBundles")).unwrap();
+        write(
+            data_dir.join("StreamingAssets/AssetBundles/dlc_dreamworld"),
+            "",
+        )
+        .unwrap();
+        let install = validate_game_path(&game);
+        assert_eq!(install.unity_version.as_deref(), Some("2019.4.39f1"));
+        assert!(install.has_dlc);
+
+        let broken = ctx.temp_dir.path().join("broken");
+        create_dir_all(&broken).unwrap();
+        assert_eq!(
+            validate_game_path(&broken).problems,
+            vec![GamePathProblem::MissingExe, GamePathProblem::MissingData]
+        );
+        assert_eq!(
+            validate_game_path(&ctx.temp_dir.path().join("nope")).problems,
+            vec![GamePathProblem::NotFound]
+        );
+        assert_eq!(
+            validate_game_path(Path::new("C:/Games/TheOuterWorlds")).problems,
+            vec![GamePathProblem::OuterWorlds]
+        );
+    }
+
+    #[test]
+    fn test_parse_vdf() {
+        let vdf = parse_vdf(
+            r#"
+            // A comment
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"		"C:\\Program Files (x86)\\Steam"
+                    "apps" { "753640" "123" }
+                }
+                "1" "/old/style"
+            }
+            "#,
+        );
+        let folders = vdf.get("libraryfolders").unwrap();
+        assert_eq!(
+            folders.get("0").unwrap().get("path").unwrap().as_str(),
+            Some(r"C:\Program Files (x86)\Steam")
+        );
+        assert_eq!(
+            folders
+                .get("0")
+                .unwrap()
+                .get("apps")
+                .unwrap()
+                .get(STEAM_APP_ID),
+            Some(&Vdf::Str("123".to_string()))
+        );
+        assert_eq!(folders.get("1").unwrap().as_str(), Some("/old/style"));
+    }
+
+    #[test]
+    fn test_find_game_paths() {
+        let ctx = TestContext::new();
+        let dir = ctx.temp_dir.path();
+
+        // The main Steam library doesn't have the game, a secondary one does in a custom folder
+        let steam = dir.join("steam");
+        let library = dir.join("library");
+        create_dir_all(steam.join("steamapps")).unwrap();
+        write(
+            steam.join("steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\" {{ \"0\" {{ \"path\" \"{}\" }} \"1\" {{ \"path\" \"{}\" }} }}",
+                steam.display(),
+                library.display()
+            )
+            .replace('\\', "\\\\"),
+        )
+        .unwrap();
+        create_dir_all(library.join("steamapps")).unwrap();
+        write(
+            library.join(format!("steamapps/appmanifest_{STEAM_APP_ID}.acf")),
+            "\"AppState\" { \"appid\" \"753640\" \"installdir\" \"OW\" }",
+        )
+        .unwrap();
+        make_game(&library.join("steamapps/common/OW"));
+
+        // Flatpak Steam with the game in the default folder, found through a link
+        let flatpak = dir.join("flatpak");
+        make_game(&flatpak.join("steamapps/common/Outer Wilds"));
+        #[cfg(unix)]
+        let flatpak_link = {
+            let link = dir.join("flatpak-link");
+            std::os::unix::fs::symlink(&flatpak, &link).unwrap();
+            link
+        };
+        #[cfg(not(unix))]
+        let flatpak_link = flatpak.clone();
+
+        let heroic = dir.join("heroic");
+        let heroic_game = dir.join("Games/OuterWilds");
+        make_game(&heroic_game);
+        create_dir_all(heroic.join("legendaryConfig/legendary")).unwrap();
+        write(
+            heroic.join("legendaryConfig/legendary/installed.json"),
+            serde_json::json!({
+                "Hawk": { "title": "Outer Wilds", "install_path": heroic_game },
+                "Other": { "title": "Some Other Game", "install_path": dir },
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        // Doesn't actually contain the game
+        let fake = dir.join("fake");
+        create_dir_all(&fake).unwrap();
+
+        let locations = SearchLocations {
+            steam_roots: vec![
+                (steam.clone(), GamePathSource::Steam),
+                (flatpak_link.clone(), GamePathSource::SteamFlatpak),
+            ],
+            heroic_configs: vec![heroic],
+            epic_manifests: None,
+            // The same install found twice keeps the more trusted source
+            common: vec![
+                fake,
+                heroic_game.clone(),
+                flatpak.join("steamapps/common/Outer Wilds"),
+            ],
+        };
+        let candidates = find_game_paths_in(&locations);
+        let found = candidates
+            .iter()
+            .map(|c| (PathBuf::from(&c.path), c.source))
+            .collect::<Vec<_>>();
+        // Links are only resolved to compare paths, the path that was found is kept
+        assert_eq!(
+            found,
+            vec![
+                (library.join("steamapps/common/OW"), GamePathSource::Steam),
+                (
+                    flatpak_link.join("steamapps/common/Outer Wilds"),
+                    GamePathSource::SteamFlatpak
+                ),
+                (heroic_game, GamePathSource::Heroic),
+            ]
+        );
+    }
+}
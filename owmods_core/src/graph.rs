@@ -0,0 +1,573 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
+    fmt::Display,
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{db::LocalDatabase, mods::local::LocalMod};
+
+/// A mod in a [DependencyGraph]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    /// The unique name of the mod
+    pub unique_name: String,
+    /// The name of the mod, this is the unique name if the mod isn't installed
+    pub name: String,
+    /// The installed version of the mod, if it's installed
+    pub version: Option<String>,
+    /// Whether the mod is installed
+    pub installed: bool,
+    /// Whether the mod is enabled
+    pub enabled: bool,
+}
+
+/// An edge in a [DependencyGraph], `from` depends on `to`
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+    /// The unique name of the mod that has the dependency
+    pub from: String,
+    /// The unique name of the dependency
+    pub to: String,
+}
+
+/// The dependency graph of every valid mod in a [LocalDatabase].
+/// Dependencies that aren't installed are still included as nodes so they show up in exports.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    /// Every mod in the graph, sorted by unique name
+    pub nodes: Vec<GraphNode>,
+    /// Every dependency in the graph, sorted by `from` then `to`
+    pub edges: Vec<GraphEdge>,
+    #[serde(skip)]
+    deps: BTreeMap<String, BTreeSet<String>>,
+    #[serde(skip)]
+    dependents: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph from the valid mods in a local database.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    /// use owmods_core::db::LocalDatabase;
+    /// use owmods_core::graph::DependencyGraph;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+    /// let graph = DependencyGraph::from_local_db(&local_db);
+    ///
+    /// for dep in graph.dependencies("xen.NewHorizons") {
+    ///     println!("New Horizons needs {dep}");
+    /// }
+    /// ```
+    ///
+    pub fn from_local_db(local_db: &LocalDatabase) -> Self {
+        let mut nodes: BTreeMap<String, GraphNode> = BTreeMap::new();
+        let mut deps: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut dependents: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+        for local_mod in local_db.valid() {
+            let unique_name = &local_mod.manifest.unique_name;
+            nodes.insert(
+                unique_name.clone(),
+                GraphNode {
+                    unique_name: unique_name.clone(),
+                    name: local_mod.manifest.name.clone(),
+                    version: Some(local_mod.manifest.version.clone()),
+                    installed: true,
+                    enabled: local_mod.enabled,
+                },
+            );
+            let mod_deps = deps.entry(unique_name.clone()).or_default();
+            for dep in local_mod.manifest.dependencies.iter().flatten() {
+                mod_deps.insert(dep.clone());
+                dependents
+                    .entry(dep.clone())
+                    .or_default()
+                    .insert(unique_name.clone());
+            }
+        }
+
+        for dep in dependents.keys() {
+            if !nodes.contains_key(dep) {
+                nodes.insert(
+                    dep.clone(),
+                    GraphNode {
+                        unique_name: dep.clone(),
+                        name: dep.clone(),
+                        version: None,
+                        installed: false,
+                        enabled: false,
+                    },
+                );
+            }
+        }
+
+        let edges = deps
+            .iter()
+            .flat_map(|(from, to)| {
+                to.iter().map(|to| GraphEdge {
+                    from: from.clone(),
+                    to: to.clone(),
+                })
+            })
+            .collect();
+
+        Self {
+            nodes: nodes.into_values().collect(),
+            edges,
+            deps,
+            dependents,
+        }
+    }
+
+    /// Get a node in the graph by its unique name
+    pub fn get_node(&self, unique_name: &str) -> Option<&GraphNode> {
+        self.nodes.iter().find(|n| n.unique_name == unique_name)
+    }
+
+    /// Get the mods the given mod depends on directly
+    pub fn direct_dependencies(&self, unique_name: &str) -> Vec<&str> {
+        self.deps
+            .get(unique_name)
+            .map(|d| d.iter().map(|d| d.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the mods that depend on the given mod directly
+    pub fn direct_dependents(&self, unique_name: &str) -> Vec<&str> {
+        self.dependents
+            .get(unique_name)
+            .map(|d| d.iter().map(|d| d.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    fn walk<'a>(map: &'a BTreeMap<String, BTreeSet<String>>, unique_name: &str) -> Vec<&'a str> {
+        let mut found: Vec<&str> = vec![];
+        let mut seen: HashSet<&str> = HashSet::from([unique_name]);
+        let mut queue: VecDeque<&str> = map
+            .get(unique_name)
+            .map(|n| n.iter().map(|n| n.as_str()).collect())
+            .unwrap_or_default();
+        while let Some(next) = queue.pop_front() {
+            if !seen.insert(next) {
+                continue;
+            }
+            found.push(next);
+            queue.extend(
+                map.get(next)
+                    .iter()
+                    .flat_map(|n| n.iter().map(|n| n.as_str())),
+            );
+        }
+        found
+    }
+
+    /// Get every mod the given mod depends on, directly or through other dependencies.
+    /// Closer dependencies come first.
+    pub fn dependencies(&self, unique_name: &str) -> Vec<&str> {
+        Self::walk(&self.deps, unique_name)
+    }
+
+    /// Get every mod that depends on the given mod, directly or through other dependencies.
+    /// Closer dependents come first.
+    pub fn dependents(&self, unique_name: &str) -> Vec<&str> {
+        Self::walk(&self.dependents, unique_name)
+    }
+
+    /// Get every enabled mod that depends on the given mod, either directly or through other enabled mods.
+    /// Closer dependents come first.
+    pub fn enabled_dependents(&self, unique_name: &str) -> Vec<&str> {
+        let is_enabled = |m: &str| self.get_node(m).is_some_and(|n| n.enabled);
+        let mut found: Vec<&str> = vec![];
+        let mut queue: VecDeque<&str> = self
+            .direct_dependents(unique_name)
+            .into_iter()
+            .filter(|m| is_enabled(m))
+            .collect();
+        while let Some(next) = queue.pop_front() {
+            if next == unique_name || found.contains(&next) {
+                continue;
+            }
+            found.push(next);
+            queue.extend(
+                self.direct_dependents(next)
+                    .into_iter()
+                    .filter(|m| is_enabled(m)),
+            );
+        }
+        found
+    }
+
+    /// Explain why a mod is installed.
+    ///
+    /// ## Returns
+    ///
+    /// Every chain of mods that requires the given mod, each chain starts at a mod nothing else depends on
+    /// and ends with the given mod. If no mods depend on it the list is empty.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    /// use owmods_core::db::LocalDatabase;
+    /// use owmods_core::graph::DependencyGraph;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+    /// let graph = DependencyGraph::from_local_db(&local_db);
+    ///
+    /// for chain in graph.why("xen.CommonResources") {
+    ///     println!("{}", chain.join(" -> "));
+    /// }
+    /// ```
+    ///
+    pub fn why(&self, unique_name: &str) -> Vec<Vec<String>> {
+        let mut chains: Vec<Vec<String>> = vec![];
+        let mut path = vec![unique_name.to_string()];
+        self.collect_chains(&mut path, &mut chains);
+        chains
+    }
+
+    fn collect_chains(&self, path: &mut Vec<String>, chains: &mut Vec<Vec<String>>) {
+        let current = path.last().cloned().unwrap_or_default();
+        let parents = self
+            .direct_dependents(&current)
+            .into_iter()
+            .filter(|p| !path.iter().any(|m| m == p))
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        if parents.is_empty() {
+            if path.len() > 1 {
+                chains.push(path.iter().rev().cloned().collect());
+            }
+            return;
+        }
+        for parent in parents {
+            path.push(parent);
+            self.collect_chains(path, chains);
+            path.pop();
+        }
+    }
+
+    /// Get the mods nothing else depends on, these are usually the mods the user installed themselves
+    pub fn roots(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .filter(|n| !self.dependents.contains_key(&n.unique_name))
+            .map(|n| n.unique_name.as_str())
+            .collect()
+    }
+
+    /// Export the graph in Graphviz DOT format.
+    /// Disabled mods are drawn greyed out and missing dependencies are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph mods {\n    rankdir=LR;\n");
+        for node in self.nodes.iter() {
+            let mut attrs = vec![format!("label=\"{}\"", escape_dot(&node.name))];
+            if !node.installed {
+                attrs.push("style=dashed".to_string());
+            } else if !node.enabled {
+                attrs.push("color=grey fontcolor=grey".to_string());
+            }
+            out.push_str(&format!(
+                "    \"{}\" [{}];\n",
+                escape_dot(&node.unique_name),
+                attrs.join(" ")
+            ));
+        }
+        for edge in self.edges.iter() {
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\";\n",
+                escape_dot(&edge.from),
+                escape_dot(&edge.to)
+            ));
+        }
+        out.push('}');
+        out
+    }
+
+    /// Export the graph as JSON, containing `nodes` and `edges`.
+    ///
+    /// ## Errors
+    ///
+    /// If we can't serialize the graph.
+    ///
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// What to do with enabled mods that depend on a mod that's being disabled or removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependentsMode {
+    /// Fail with an [EnabledDependentsError] if any enabled mods depend on the mod
+    Refuse,
+    /// Disable or remove the enabled dependents as well
+    Cascade,
+    /// Don't check dependents at all, useful when changing many mods at once
+    Ignore,
+}
+
+/// Returned (wrapped in [anyhow::Error]) when disabling or removing a mod other enabled mods depend on,
+/// use [anyhow::Error::downcast_ref] to get it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnabledDependentsError {
+    /// The unique name of the mod that was going to be disabled or removed
+    pub unique_name: String,
+    /// The unique names of the enabled mods that depend on it, closest first
+    pub dependents: Vec<String>,
+}
+
+impl Display for EnabledDependentsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is needed by {}, disable them first or cascade the change to them",
+            self.unique_name,
+            self.dependents.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for EnabledDependentsError {}
+
+/// Check for enabled mods that depend on a mod that's about to be disabled or removed.
+///
+/// ## Returns
+///
+/// The enabled dependents that should be disabled or removed alongside the mod,
+/// this is always empty unless `mode` is [DependentsMode::Cascade].
+/// Mods in `changing` are being disabled or removed alongside this one (like dependencies in a cycle),
+/// so they never count as dependents.
+///
+/// ## Errors
+///
+/// An [EnabledDependentsError] if `mode` is [DependentsMode::Refuse] and there are enabled dependents.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::graph::{check_enabled_dependents, DependentsMode, EnabledDependentsError};
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// if let Err(why) = check_enabled_dependents(&local_db, "xen.CommonResources", &[], DependentsMode::Refuse) {
+///     let err = why.downcast_ref::<EnabledDependentsError>().unwrap();
+///     println!("Still needed by {}", err.dependents.join(", "));
+/// }
+/// ```
+///
+pub fn check_enabled_dependents<'a>(
+    local_db: &'a LocalDatabase,
+    unique_name: &str,
+    changing: &[&str],
+    mode: DependentsMode,
+) -> Result<Vec<&'a LocalMod>> {
+    if mode == DependentsMode::Ignore {
+        return Ok(vec![]);
+    }
+    let graph = DependencyGraph::from_local_db(local_db);
+    let mut dependents = graph.enabled_dependents(unique_name);
+    dependents.retain(|d| !changing.contains(d));
+    match mode {
+        DependentsMode::Refuse if !dependents.is_empty() => Err(EnabledDependentsError {
+            unique_name: unique_name.to_string(),
+            dependents: dependents.into_iter().map(|d| d.to_string()).collect(),
+        }
+        .into()),
+        DependentsMode::Cascade => Ok(dependents
+            .into_iter()
+            .filter_map(|d| local_db.get_mod(d))
+            .collect()),
+        _ => Ok(vec![]),
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::test_utils::TestContext;
+
+    use super::*;
+
+    fn setup() -> TestContext {
+        // 1 -> 2 -> 3, 4 -> 3, 4 -> 5 (not installed)
+        let mut ctx = TestContext::new();
+        let deps: [(u8, &[u8]); 4] = [(1, &[2]), (2, &[3]), (3, &[]), (4, &[3, 5])];
+        for (num, mod_deps) in deps {
+            let mut local_mod = LocalMod::get_test(num);
+            local_mod.manifest.dependencies = Some(
+                mod_deps
+                    .iter()
+                    .map(|d| format!("Example.TestMod{d}"))
+                    .collect(),
+            );
+            ctx.insert_test_mod(&local_mod);
+        }
+        ctx
+    }
+
+    #[test]
+    fn test_graph_dependencies() {
+        let ctx = setup();
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges.len(), 4);
+        assert_eq!(
+            graph.dependencies("Example.TestMod1"),
+            vec!["Example.TestMod2", "Example.TestMod3"]
+        );
+        assert_eq!(
+            graph.direct_dependencies("Example.TestMod4"),
+            vec!["Example.TestMod3", "Example.TestMod5"]
+        );
+        assert!(!graph.get_node("Example.TestMod5").unwrap().installed);
+    }
+
+    #[test]
+    fn test_graph_dependents() {
+        let ctx = setup();
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        assert_eq!(
+            graph.dependents("Example.TestMod3"),
+            vec!["Example.TestMod2", "Example.TestMod4", "Example.TestMod1"]
+        );
+        assert_eq!(
+            graph.direct_dependents("Example.TestMod3"),
+            vec!["Example.TestMod2", "Example.TestMod4"]
+        );
+        assert_eq!(graph.roots(), vec!["Example.TestMod1", "Example.TestMod4"]);
+    }
+
+    #[test]
+    fn test_graph_why() {
+        let ctx = setup();
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        assert_eq!(
+            graph.why("Example.TestMod3"),
+            vec![
+                vec![
+                    "Example.TestMod1".to_string(),
+                    "Example.TestMod2".to_string(),
+                    "Example.TestMod3".to_string()
+                ],
+                vec![
+                    "Example.TestMod4".to_string(),
+                    "Example.TestMod3".to_string()
+                ]
+            ]
+        );
+        assert!(graph.why("Example.TestMod1").is_empty());
+    }
+
+    #[test]
+    fn test_graph_enabled_dependents() {
+        let mut ctx = setup();
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        assert_eq!(
+            graph.enabled_dependents("Example.TestMod3"),
+            vec!["Example.TestMod2", "Example.TestMod4", "Example.TestMod1"]
+        );
+        let mut disabled = LocalMod::get_test(2);
+        disabled.enabled = false;
+        disabled.manifest.dependencies = Some(vec!["Example.TestMod3".to_string()]);
+        ctx.insert_test_mod(&disabled);
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        assert_eq!(
+            graph.enabled_dependents("Example.TestMod3"),
+            vec!["Example.TestMod4"]
+        );
+    }
+
+    #[test]
+    fn test_check_enabled_dependents() {
+        let ctx = setup();
+        let err = check_enabled_dependents(
+            &ctx.local_db,
+            "Example.TestMod2",
+            &[],
+            DependentsMode::Refuse,
+        )
+        .unwrap_err();
+        let err = err.downcast_ref::<EnabledDependentsError>().unwrap();
+        assert_eq!(err.unique_name, "Example.TestMod2");
+        assert_eq!(err.dependents, vec!["Example.TestMod1".to_string()]);
+        let cascade = check_enabled_dependents(
+            &ctx.local_db,
+            "Example.TestMod2",
+            &[],
+            DependentsMode::Cascade,
+        )
+        .unwrap();
+        assert_eq!(cascade.len(), 1);
+        assert_eq!(cascade[0].manifest.unique_name, "Example.TestMod1");
+        assert!(check_enabled_dependents(
+            &ctx.local_db,
+            "Example.TestMod1",
+            &[],
+            DependentsMode::Refuse
+        )
+        .unwrap()
+        .is_empty());
+        assert!(check_enabled_dependents(
+            &ctx.local_db,
+            "Example.TestMod2",
+            &[],
+            DependentsMode::Ignore
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn test_graph_cycle() {
+        let mut ctx = TestContext::new();
+        let mut mod1 = LocalMod::get_test(1);
+        mod1.manifest.dependencies = Some(vec!["Example.TestMod2".to_string()]);
+        let mut mod2 = LocalMod::get_test(2);
+        mod2.manifest.dependencies = Some(vec!["Example.TestMod1".to_string()]);
+        ctx.insert_test_mod(&mod1);
+        ctx.insert_test_mod(&mod2);
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        assert_eq!(
+            graph.dependencies("Example.TestMod1"),
+            vec!["Example.TestMod2"]
+        );
+        assert_eq!(
+            graph.why("Example.TestMod1"),
+            vec![vec![
+                "Example.TestMod2".to_string(),
+                "Example.TestMod1".to_string()
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_graph_export() {
+        let ctx = setup();
+        let graph = DependencyGraph::from_local_db(&ctx.local_db);
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph mods {"));
+        assert!(dot.contains("\"Example.TestMod1\" -> \"Example.TestMod2\";"));
+        assert!(dot.contains("\"Example.TestMod5\" [label=\"Example.TestMod5\" style=dashed];"));
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 5);
+        assert_eq!(json["edges"][0]["from"], "Example.TestMod1");
+        assert_eq!(json["edges"][0]["to"], "Example.TestMod2");
+    }
+}
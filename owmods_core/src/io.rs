@@ -8,6 +8,7 @@ use crate::{
     db::{LocalDatabase, RemoteDatabase},
     download::install_mods_parallel,
     file::deserialize_from_json,
+    graph::DependentsMode,
     toggle::toggle_mod,
 };
 
@@ -54,6 +55,7 @@ pub fn export_mods(db: &LocalDatabase) -> Result<String> {
 /// ```no_run
 /// use owmods_core::io::{import_mods, export_mods};
 /// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::graph::DependentsMode;
 /// use owmods_core::toggle::toggle_mod;
 /// use owmods_core::config::Config;
 /// use std::path::PathBuf;
@@ -69,7 +71,7 @@ pub fn export_mods(db: &LocalDatabase) -> Result<String> {
 ///
 /// for local_mod in local_db.valid() {
 ///     if local_mod.enabled {
-///         toggle_mod(&local_mod.manifest.unique_name, &local_db, false, false).unwrap();
+///         toggle_mod(&local_mod.manifest.unique_name, &local_db, false, false, DependentsMode::Ignore).unwrap();
 ///     }
 /// }
 ///
@@ -80,6 +82,7 @@ pub fn export_mods(db: &LocalDatabase) -> Result<String> {
 /// ```no_run
 /// use owmods_core::io::{import_mods, export_mods};
 /// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::graph::DependentsMode;
 /// use owmods_core::toggle::toggle_mod;
 /// use owmods_core::config::Config;
 /// use std::path::PathBuf;
@@ -89,18 +92,18 @@ pub fn export_mods(db: &LocalDatabase) -> Result<String> {
 /// let remote_db = RemoteDatabase::fetch(&config.database_url).await.unwrap();
 ///
 /// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
-/// toggle_mod("Bwc9876.TimeSaver", &local_db, false, false).unwrap();
+/// toggle_mod("Bwc9876.TimeSaver", &local_db, false, false, DependentsMode::Ignore).unwrap();
 /// let exported_list = export_mods(&local_db).unwrap();
 ///
 /// std::fs::write("exported_list.json", exported_list).unwrap();
 ///
 /// for local_mod in local_db.valid() {
 ///    if local_mod.enabled {
-///       toggle_mod(&local_mod.manifest.unique_name, &local_db, false, false).unwrap();
+///       toggle_mod(&local_mod.manifest.unique_name, &local_db, false, false, DependentsMode::Ignore).unwrap();
 ///    }
 /// }
 ///
-/// toggle_mod("Bwc9876.TimeSaver", &local_db, true, false).unwrap();
+/// toggle_mod("Bwc9876.TimeSaver", &local_db, true, false, DependentsMode::Ignore).unwrap();
 ///
 /// import_mods(&config, &local_db, &remote_db, &PathBuf::from("exported_list.json"), true).await.unwrap();
 ///
@@ -122,14 +125,26 @@ pub async fn import_mods(
     if disable_missing {
         for local_mod in local_db.valid() {
             if local_mod.enabled {
-                toggle_mod(&local_mod.manifest.unique_name, local_db, false, false)?;
+                toggle_mod(
+                    &local_mod.manifest.unique_name,
+                    local_db,
+                    false,
+                    false,
+                    DependentsMode::Ignore,
+                )?;
             }
         }
     }
     for name in unique_names.iter() {
         let local_mod = local_db.get_mod(name);
         if let Some(local_mod) = local_mod {
-            toggle_mod(&local_mod.manifest.unique_name, local_db, true, false)?;
+            toggle_mod(
+                &local_mod.manifest.unique_name,
+                local_db,
+                true,
+                false,
+                DependentsMode::Ignore,
+            )?;
         } else {
             needed_install.push(name.to_string());
         }
@@ -193,7 +208,14 @@ mod tests {
             let mut ctx = TestContext::new();
             ctx.fetch_remote_db().await;
             ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
-            toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, false).unwrap();
+            toggle_mod(
+                "Bwc9876.TimeSaver",
+                &ctx.local_db,
+                false,
+                false,
+                DependentsMode::Ignore,
+            )
+            .unwrap();
             let list_path = make_list_json(&ctx, "[\"Bwc9876.TimeSaver\"]");
             import_mods(
                 &ctx.config,
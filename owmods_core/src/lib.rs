@@ -10,6 +10,18 @@ pub mod alerts;
 /// Send analytics events.
 pub mod analytics;
 
+/// Find and remove mods that were installed as dependencies but aren't needed anymore.
+pub mod autoremove;
+
+/// Back up mods before they're updated and roll them back.
+pub mod backups;
+
+/// Collect configs and logs into a zip to help debug someone's setup.
+pub mod bundle;
+
+/// Cache downloaded mod archives for offline reinstalls.
+pub mod cache;
+
 /// Work with the configuration of the app.
 pub mod config;
 
@@ -19,18 +31,36 @@ pub mod constants;
 /// Work with both remote and local databases.
 pub mod db;
 
+/// Analyze game log sessions to find what went wrong.
+pub mod diagnostics;
+
 /// Download and install mods and OWML.
 pub mod download;
 
+/// Group exceptions and their stack traces from game logs.
+pub mod exceptions;
+
 /// Utilities when working with files.
 pub mod file;
 
 /// Run the game and setup prerequisites on Linux.
 pub mod game;
 
+/// Find where Outer Wilds is installed.
+pub mod game_paths;
+
+/// Inspect the dependency graph of installed mods.
+pub mod graph;
+
 /// Import and export mods from JSON arrays.
 pub mod io;
 
+/// Lock mods to exact versions and reproduce installs from lockfiles.
+pub mod lock;
+
+/// Filter game logs with a small query language.
+pub mod log_query;
+
 /// Work with local and remote mods.
 pub mod mods;
 
@@ -40,12 +70,30 @@ pub mod owml;
 /// Open shortcuts and mod readmes.
 pub mod open;
 
+/// Check for problems that should be dealt with before launching the game.
+pub mod prelaunch;
+
 /// Utilities for managing and parsing progress bars.
 pub mod progress;
 
+/// Save and apply named sets of enabled mods.
+pub mod profiles;
+
 /// Uninstall mods
 pub mod remove;
 
+/// Resolve mods and their dependencies into install plans.
+pub mod resolve;
+
+/// Record game log sessions to disk and read them back.
+pub mod sessions;
+
+/// Read and change the settings of mods.
+pub mod settings;
+
+/// Save and restore snapshots of mod settings.
+pub mod snapshots;
+
 /// Listen to logs from the game.
 pub mod socket;
 
@@ -0,0 +1,595 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use futures::{stream::FuturesUnordered, StreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+use crate::{
+    cache::find_cached_archive,
+    config::Config,
+    db::{LocalDatabase, RemoteDatabase},
+    download::{download_zip, install_mod_version},
+    file::{deserialize_from_json, get_file_hash, serialize_to_json},
+    graph::DependentsMode,
+    mods::local::LocalMod,
+    toggle::toggle_mod,
+};
+
+/// The version of the lockfile format, bumped when the format changes in a non-compatible way
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// A mod pinned to an exact version in a [Lockfile]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedMod {
+    /// The unique name of the mod
+    pub unique_name: String,
+    /// The exact version of the mod
+    pub version: String,
+    /// The URL the archive for this version was downloaded from
+    pub download_url: String,
+    /// The SHA-256 hash of the archive, as a lowercase hex string
+    pub hash: String,
+}
+
+/// Records the exact state of a mod install so it can be reproduced with [install_from_lock]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Lockfile {
+    /// The version of the lockfile format, see [LOCKFILE_VERSION]
+    pub lockfile_version: u32,
+    /// The version of OWML that was installed, if OWML was installed
+    pub owml_version: Option<String>,
+    /// The mods that were enabled, sorted by unique name
+    pub mods: Vec<LockedMod>,
+}
+
+/// A difference between a [Lockfile] and the current state of OWML or the remote database
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(
+    tag = "driftType",
+    content = "payload",
+    rename_all_fields = "camelCase"
+)]
+pub enum LockDrift {
+    /// The installed version of OWML isn't the locked one
+    OwmlVersion {
+        /// The version of OWML in the lockfile
+        locked: Option<String>,
+        /// The version of OWML that's installed
+        installed: Option<String>,
+    },
+    /// The remote database's version of a mod differs from the locked one,
+    /// the locked version will still be installed from its recorded URL
+    ModVersion {
+        /// The unique name of the mod
+        unique_name: String,
+        /// The version in the lockfile
+        locked: String,
+        /// The version in the remote database, `None` if the mod is no longer in the database
+        remote: Option<String>,
+    },
+}
+
+impl std::fmt::Display for LockDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockDrift::OwmlVersion { locked, installed } => write!(
+                f,
+                "OWML is locked to {} but {} is installed",
+                locked.as_deref().unwrap_or("nothing"),
+                installed.as_deref().unwrap_or("nothing")
+            ),
+            LockDrift::ModVersion {
+                unique_name,
+                locked,
+                remote: Some(remote),
+            } => write!(
+                f,
+                "{unique_name} is locked to {locked} but the database has {remote}"
+            ),
+            LockDrift::ModVersion {
+                unique_name,
+                locked,
+                remote: None,
+            } => write!(
+                f,
+                "{unique_name} is locked to {locked} but is no longer in the database"
+            ),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Load a lockfile from the given path
+    ///
+    /// ## Errors
+    ///
+    /// If we can't read the file, or it was made with a newer lockfile format.
+    ///
+    pub fn load(path: &Path) -> Result<Self> {
+        let lock: Self = deserialize_from_json(path)
+            .with_context(|| format!("Failed to read lockfile at {}", path.display()))?;
+        if lock.lockfile_version > LOCKFILE_VERSION {
+            Err(anyhow!(
+                "Lockfile version {} isn't supported, please update the manager",
+                lock.lockfile_version
+            ))
+        } else {
+            Ok(lock)
+        }
+    }
+
+    /// Save this lockfile to the given path
+    ///
+    /// ## Errors
+    ///
+    /// If we can't write to the file
+    ///
+    pub fn save(&self, path: &Path) -> Result<()> {
+        serialize_to_json(self, path, true)
+    }
+}
+
+async fn lock_mod(
+    local_mod: &LocalMod,
+    config: &Config,
+    remote_db: &RemoteDatabase,
+) -> Result<LockedMod> {
+    let unique_name = &local_mod.manifest.unique_name;
+    let remote_mod = remote_db
+        .get_mod(unique_name)
+        .with_context(|| format!("Can't lock {unique_name}, it isn't in the database"))?;
+    let version = &local_mod.manifest.version;
+    let (download_url, known_hash) = if &remote_mod.version == version {
+        (&remote_mod.download_url, remote_mod.hash.clone())
+    } else {
+        remote_mod
+            .prerelease
+            .as_ref()
+            .filter(|p| p.version == local_mod.manifest.version)
+            .map(|p| (&p.download_url, None))
+            .with_context(|| {
+                format!(
+                    "Can't lock {unique_name}, version {} is installed but the database has {}, update it first",
+                    local_mod.manifest.version, remote_mod.version
+                )
+            })?
+    };
+    // Only download the archive if neither the database nor the cache knows its hash
+    let cached_hash = || {
+        find_cached_archive(config, unique_name, version, None)
+            .filter(|a| get_file_hash(&a.path).is_ok_and(|h| h == a.hash))
+            .map(|a| a.hash)
+    };
+    let hash = match known_hash.or_else(cached_hash) {
+        Some(hash) => hash.to_ascii_lowercase(),
+        None => {
+            let temp_dir = TempDir::new()?;
+            let download_path = temp_dir.path().join("mod.zip");
+            let hash = download_zip(
+                download_url,
+                Some(unique_name),
+                &download_path,
+                None,
+                &config.retry_policy,
+            )
+            .await?;
+            temp_dir.close()?;
+            hash
+        }
+    };
+    Ok(LockedMod {
+        unique_name: unique_name.clone(),
+        version: local_mod.manifest.version.clone(),
+        download_url: download_url.clone(),
+        hash,
+    })
+}
+
+/// Generate a lockfile for all enabled mods and OWML.
+/// Hashes come from the remote database or the cache, archives are only downloaded to hash them if neither has one.
+///
+/// ## Errors
+///
+/// - If an enabled mod isn't in the remote database
+/// - If an enabled mod's version isn't the current version (or prerelease) in the remote database,
+///   as we have no way to get the download URL of older versions
+/// - If we need to download an archive and can't
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::lock::generate_lockfile;
+/// use std::path::PathBuf;
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let remote_db = RemoteDatabase::fetch(&config.database_url).await.unwrap();
+///
+/// let lock = generate_lockfile(&config, &local_db, &remote_db).await.unwrap();
+/// lock.save(&PathBuf::from("owmods.lock.json")).unwrap();
+/// # });
+/// ```
+///
+pub async fn generate_lockfile(
+    config: &Config,
+    local_db: &LocalDatabase,
+    remote_db: &RemoteDatabase,
+) -> Result<Lockfile> {
+    let mut set = FuturesUnordered::new();
+    for local_mod in local_db.active() {
+        set.push(lock_mod(local_mod, config, remote_db));
+    }
+    let mut mods: Vec<LockedMod> = Vec::with_capacity(set.len());
+    while let Some(res) = set.next().await {
+        mods.push(res?);
+    }
+    mods.sort_by(|a, b| a.unique_name.cmp(&b.unique_name));
+    Ok(Lockfile {
+        lockfile_version: LOCKFILE_VERSION,
+        owml_version: LocalDatabase::get_owml(&config.owml_path).map(|o| o.manifest.version),
+        mods,
+    })
+}
+
+/// Check how the installed OWML and the remote database have drifted from a lockfile.
+///
+/// ## Returns
+///
+/// Every difference found, empty if there's no drift.
+///
+pub fn check_lock_drift(
+    lock: &Lockfile,
+    config: &Config,
+    remote_db: &RemoteDatabase,
+) -> Vec<LockDrift> {
+    let mut drift: Vec<LockDrift> = vec![];
+    let owml_version = LocalDatabase::get_owml(&config.owml_path).map(|o| o.manifest.version);
+    if owml_version != lock.owml_version {
+        drift.push(LockDrift::OwmlVersion {
+            locked: lock.owml_version.clone(),
+            installed: owml_version,
+        });
+    }
+    for locked_mod in lock.mods.iter() {
+        let remote_version = remote_db
+            .get_mod(&locked_mod.unique_name)
+            .map(|m| m.version.clone());
+        if remote_version.as_ref() != Some(&locked_mod.version) {
+            drift.push(LockDrift::ModVersion {
+                unique_name: locked_mod.unique_name.clone(),
+                locked: locked_mod.version.clone(),
+                remote: remote_version,
+            });
+        }
+    }
+    drift
+}
+
+async fn install_locked_mod(
+    locked_mod: &LockedMod,
+    config: &Config,
+    local_db: &LocalDatabase,
+) -> Result<LocalMod> {
+    install_mod_version(
+        &locked_mod.download_url,
+        &locked_mod.unique_name,
+        &locked_mod.version,
+        Some(&locked_mod.hash),
+        config,
+        local_db,
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "Failed to install {} {} from lockfile",
+            locked_mod.unique_name, locked_mod.version
+        )
+    })
+}
+
+/// Reproduce the state recorded in a lockfile.
+/// Mods that aren't installed at their locked version are downloaded from their recorded URL,
+/// mods in the lockfile are enabled, and all other mods are disabled.
+///
+/// If `strict` is set, any drift (see [check_lock_drift]) will stop the install before anything is changed,
+/// otherwise drift is logged as a warning.
+///
+/// ## Returns
+///
+/// The drift that was found.
+///
+/// ## Errors
+///
+/// - If `strict` is set and there's any drift
+/// - If we can't download a locked mod, or its archive doesn't match the hash in the lockfile (see [crate::download::HashMismatchError])
+/// - If we can't toggle a mod
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::lock::{install_from_lock, Lockfile};
+/// use std::path::PathBuf;
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let remote_db = RemoteDatabase::fetch(&config.database_url).await.unwrap();
+///
+/// let lock = Lockfile::load(&PathBuf::from("owmods.lock.json")).unwrap();
+/// install_from_lock(&lock, &config, &local_db, &remote_db, true).await.unwrap();
+/// # });
+/// ```
+///
+pub async fn install_from_lock(
+    lock: &Lockfile,
+    config: &Config,
+    local_db: &LocalDatabase,
+    remote_db: &RemoteDatabase,
+    strict: bool,
+) -> Result<Vec<LockDrift>> {
+    let drift = check_lock_drift(lock, config, remote_db);
+    if strict && !drift.is_empty() {
+        let list = drift
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(anyhow!("Refusing to install, lockfile has drifted: {list}"));
+    }
+    for d in drift.iter() {
+        warn!("{d}");
+    }
+
+    let mut set = FuturesUnordered::new();
+    for locked_mod in lock.mods.iter() {
+        let local_mod = local_db.get_mod(&locked_mod.unique_name);
+        if local_mod.is_none_or(|m| m.manifest.version != locked_mod.version) {
+            info!(
+                "Installing {} {} from lockfile",
+                locked_mod.unique_name, locked_mod.version
+            );
+            set.push(install_locked_mod(locked_mod, config, local_db));
+        }
+    }
+    while let Some(res) = set.next().await {
+        res?;
+    }
+
+    // Installing keeps a mod's config, so mods that were disabled before need to be enabled too
+    let local_db = LocalDatabase::fetch(&config.owml_path)?;
+    for locked_mod in lock.mods.iter() {
+        toggle_mod(
+            &locked_mod.unique_name,
+            &local_db,
+            true,
+            false,
+            DependentsMode::Ignore,
+        )?;
+    }
+
+    for local_mod in local_db.active() {
+        if !lock
+            .mods
+            .iter()
+            .any(|m| m.unique_name == local_mod.manifest.unique_name)
+        {
+            toggle_mod(
+                &local_mod.manifest.unique_name,
+                &local_db,
+                false,
+                false,
+                DependentsMode::Ignore,
+            )?;
+        }
+    }
+
+    Ok(drift)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        cache::add_to_cache,
+        mods::remote::RemoteMod,
+        test_utils::{get_test_file, TestContext},
+        toggle::get_mod_enabled,
+    };
+
+    use super::*;
+
+    fn make_lock(ctx: &TestContext, mods: Vec<LockedMod>) -> Lockfile {
+        Lockfile {
+            lockfile_version: LOCKFILE_VERSION,
+            owml_version: LocalDatabase::get_owml(&ctx.config.owml_path)
+                .map(|o| o.manifest.version),
+            mods,
+        }
+    }
+
+    fn locked(local_mod: &LocalMod) -> LockedMod {
+        LockedMod {
+            unique_name: local_mod.manifest.unique_name.clone(),
+            version: local_mod.manifest.version.clone(),
+            download_url: "https://example.com/mod.zip".to_string(),
+            hash: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lock_mod_without_download() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+            let mut remote_mod = RemoteMod::get_test(0);
+            remote_mod.unique_name = local_mod.manifest.unique_name.clone();
+            remote_mod.version = local_mod.manifest.version.clone();
+            // Nothing is listening here, so the test fails if we try to download
+            remote_mod.download_url = "http://127.0.0.1:1/mod.zip".to_string();
+            remote_mod.hash = Some("ABC123".to_string());
+            ctx.remote_db
+                .mods
+                .insert(remote_mod.unique_name.clone(), remote_mod.clone());
+            let from_db = lock_mod(&local_mod, &ctx.config, &ctx.remote_db)
+                .await
+                .unwrap();
+            assert_eq!(from_db.hash, "abc123");
+
+            let zip = get_test_file("Bwc9876.TimeSaver.zip");
+            let hash = get_file_hash(&zip).unwrap();
+            add_to_cache(
+                &ctx.config,
+                &zip,
+                &local_mod.manifest.unique_name,
+                &local_mod.manifest.version,
+                &hash,
+            )
+            .unwrap();
+            remote_mod.hash = None;
+            ctx.remote_db
+                .mods
+                .insert(remote_mod.unique_name.clone(), remote_mod);
+            let from_cache = lock_mod(&local_mod, &ctx.config, &ctx.remote_db)
+                .await
+                .unwrap();
+            assert_eq!(from_cache.hash, hash);
+        });
+    }
+
+    #[test]
+    fn test_lockfile_save_load() {
+        let ctx = TestContext::new();
+        let lock = make_lock(&ctx, vec![]);
+        let path = ctx.temp_dir.path().join("owmods.lock.json");
+        lock.save(&path).unwrap();
+        assert_eq!(Lockfile::load(&path).unwrap(), lock);
+    }
+
+    #[test]
+    fn test_lockfile_load_newer_version() {
+        let ctx = TestContext::new();
+        let mut lock = make_lock(&ctx, vec![]);
+        lock.lockfile_version = LOCKFILE_VERSION + 1;
+        let path = ctx.temp_dir.path().join("owmods.lock.json");
+        lock.save(&path).unwrap();
+        assert!(Lockfile::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_check_lock_drift() {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        let lock = make_lock(&ctx, vec![locked(&local_mod)]);
+        let drift = check_lock_drift(&lock, &ctx.config, &ctx.remote_db);
+        assert_eq!(
+            drift,
+            vec![LockDrift::ModVersion {
+                unique_name: "Bwc9876.TimeSaver".to_string(),
+                locked: local_mod.manifest.version.clone(),
+                remote: None,
+            }]
+        );
+        let mut remote_mod = RemoteMod::get_test(1);
+        remote_mod.unique_name = "Bwc9876.TimeSaver".to_string();
+        remote_mod.version = local_mod.manifest.version.clone();
+        ctx.remote_db
+            .mods
+            .insert(remote_mod.unique_name.clone(), remote_mod);
+        assert!(check_lock_drift(&lock, &ctx.config, &ctx.remote_db).is_empty());
+    }
+
+    #[test]
+    fn test_install_from_lock_strict() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+            let lock = make_lock(&ctx, vec![locked(&local_mod)]);
+            let res =
+                install_from_lock(&lock, &ctx.config, &ctx.local_db, &ctx.remote_db, true).await;
+            assert!(res.is_err());
+        });
+    }
+
+    #[test]
+    fn test_install_from_lock_toggles() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            let time_saver = ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
+            ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+            toggle_mod(
+                "Bwc9876.TimeSaver",
+                &ctx.local_db,
+                false,
+                false,
+                DependentsMode::Ignore,
+            )
+            .unwrap();
+            ctx.fetch_local_db();
+            let lock = make_lock(&ctx, vec![locked(&time_saver)]);
+            let drift = install_from_lock(&lock, &ctx.config, &ctx.local_db, &ctx.remote_db, false)
+                .await
+                .unwrap();
+            assert_eq!(drift.len(), 1);
+            assert!(get_mod_enabled(&ctx.get_test_path("Bwc9876.TimeSaver")).unwrap());
+            assert!(!get_mod_enabled(&ctx.get_test_path("Bwc9876.SaveEditor")).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_install_from_lock_enables_reinstalled_mod() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            let time_saver = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+            let zip = get_test_file("Bwc9876.TimeSaver.zip");
+            let hash = get_file_hash(&zip).unwrap();
+            add_to_cache(
+                &ctx.config,
+                &zip,
+                &time_saver.manifest.unique_name,
+                &time_saver.manifest.version,
+                &hash,
+            )
+            .unwrap();
+            let mut lock_entry = locked(&time_saver);
+            lock_entry.hash = hash;
+            // Nothing is listening here, so the test fails if we don't use the cache
+            lock_entry.download_url = "http://127.0.0.1:1/mod.zip".to_string();
+            let lock = make_lock(&ctx, vec![lock_entry]);
+
+            let mut manifest = time_saver.manifest.clone();
+            manifest.version = "9.9.9".to_string();
+            serialize_to_json(
+                &manifest,
+                &Path::new(&time_saver.mod_path).join("manifest.json"),
+                false,
+            )
+            .unwrap();
+            toggle_mod(
+                "Bwc9876.TimeSaver",
+                &ctx.local_db,
+                false,
+                false,
+                DependentsMode::Ignore,
+            )
+            .unwrap();
+            ctx.fetch_local_db();
+
+            install_from_lock(&lock, &ctx.config, &ctx.local_db, &ctx.remote_db, false)
+                .await
+                .unwrap();
+            ctx.fetch_local_db();
+            let installed = ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap();
+            assert_eq!(installed.manifest.version, time_saver.manifest.version);
+            assert!(get_mod_enabled(&ctx.get_test_path("Bwc9876.TimeSaver")).unwrap());
+        });
+    }
+}
@@ -0,0 +1,299 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+
+use crate::{
+    search::{matches_query, normalize_value},
+    socket::{SocketMessage, SocketMessageType},
+};
+
+/// A filter for game logs, made by parsing a query like `type:error,fatal sender:NewHorizons -"shader" /regex/`.
+///
+/// A query is a list of terms separated by spaces, a message has to match every term to match the query:
+///
+/// - `type:error,fatal` matches messages of any of the listed types
+/// - `sender:NewHorizons` matches messages where the sender's name contains any of the listed values
+/// - `"some text"` or `text` matches messages containing the text, the same way searching does
+/// - `/regex/` matches messages where the regex matches the text of the message, use `\/` for a literal `/`
+///
+/// Any term can be prefixed with `-` to only match messages that *don't* match it.
+/// An empty query matches everything.
+///
+/// ## Examples
+///
+/// ```
+/// use owmods_core::log_query::LogQuery;
+/// use owmods_core::socket::{SocketMessage, SocketMessageType};
+///
+/// let query = LogQuery::parse("type:error,fatal -\"shader\"").unwrap();
+///
+/// let message = SocketMessage::make_internal("Couldn't load planet", SocketMessageType::Error);
+/// assert!(query.matches(&message));
+///
+/// let message = SocketMessage::make_internal("Couldn't compile shader", SocketMessageType::Error);
+/// assert!(!query.matches(&message));
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    terms: Vec<QueryTerm>,
+}
+
+#[derive(Debug, Clone)]
+struct QueryTerm {
+    negated: bool,
+    kind: TermKind,
+}
+
+#[derive(Debug, Clone)]
+enum TermKind {
+    Types(Vec<SocketMessageType>),
+    Senders(Vec<String>),
+    Text(String),
+    Regex(Regex),
+}
+
+impl QueryTerm {
+    fn matches(&self, message: &SocketMessage) -> bool {
+        let matched = match &self.kind {
+            TermKind::Types(types) => types.contains(&message.message_type),
+            TermKind::Senders(senders) => {
+                let name = normalize_value(message.sender_name.as_deref().unwrap_or_default());
+                senders.iter().any(|s| name.contains(s))
+            }
+            TermKind::Text(text) => matches_query(message, text),
+            TermKind::Regex(regex) => regex.is_match(&message.message),
+        };
+        matched != self.negated
+    }
+}
+
+struct Token {
+    negated: bool,
+    text: String,
+    first_quote: Option<usize>,
+    regex: bool,
+}
+
+fn parse_type(value: &str) -> Result<SocketMessageType> {
+    match value.to_ascii_lowercase().as_str() {
+        "message" => Ok(SocketMessageType::Message),
+        "error" => Ok(SocketMessageType::Error),
+        "warning" => Ok(SocketMessageType::Warning),
+        "info" => Ok(SocketMessageType::Info),
+        "success" => Ok(SocketMessageType::Success),
+        "quit" => Ok(SocketMessageType::Quit),
+        "fatal" => Ok(SocketMessageType::Fatal),
+        "debug" => Ok(SocketMessageType::Debug),
+        _ => Err(anyhow!(
+            "Unknown message type \"{value}\", expected one of message, error, warning, info, success, quit, fatal, or debug"
+        )),
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(mut c) = chars.next() else {
+            break;
+        };
+        let negated = c == '-' && chars.peek().is_some_and(|n| !n.is_whitespace());
+        if negated {
+            c = chars.next().unwrap();
+        }
+        let mut token = Token {
+            negated,
+            text: String::new(),
+            first_quote: None,
+            regex: c == '/',
+        };
+        if token.regex {
+            let mut closed = false;
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' if chars.peek() == Some(&'/') => {
+                        token.text.push(chars.next().unwrap());
+                    }
+                    '/' => {
+                        closed = true;
+                        break;
+                    }
+                    _ => token.text.push(c),
+                }
+            }
+            if !closed {
+                return Err(anyhow!("Regex \"/{}\" is missing a closing /", token.text));
+            }
+        } else {
+            let mut in_quotes = false;
+            let mut next = Some(c);
+            while let Some(c) = next {
+                match c {
+                    '"' => {
+                        in_quotes = !in_quotes;
+                        token.first_quote.get_or_insert(token.text.chars().count());
+                    }
+                    c if c.is_whitespace() && !in_quotes => break,
+                    c => token.text.push(c),
+                }
+                next = chars.next();
+            }
+            if in_quotes {
+                return Err(anyhow!("Text \"{}\" is missing a closing \"", token.text));
+            }
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+impl LogQuery {
+    /// Parse a query, see [LogQuery] for the syntax.
+    ///
+    /// ## Errors
+    ///
+    /// If a quote or regex isn't closed, a regex is invalid, or a `type:` term has an unknown type.
+    ///
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut terms = vec![];
+        for token in tokenize(query)? {
+            let kind = if token.regex {
+                TermKind::Regex(
+                    Regex::new(&token.text)
+                        .with_context(|| format!("Invalid regex \"/{}/\"", token.text))?,
+                )
+            } else {
+                let key_value = token
+                    .text
+                    .split_once(':')
+                    .filter(|(key, _)| token.first_quote.is_none_or(|q| q > key.chars().count()));
+                match key_value {
+                    Some((key, value)) if key.eq_ignore_ascii_case("type") => TermKind::Types(
+                        value
+                            .split(',')
+                            .filter(|v| !v.is_empty())
+                            .map(parse_type)
+                            .collect::<Result<_>>()?,
+                    ),
+                    Some((key, value)) if key.eq_ignore_ascii_case("sender") => TermKind::Senders(
+                        value
+                            .split(',')
+                            .map(normalize_value)
+                            .filter(|v| !v.is_empty())
+                            .collect(),
+                    ),
+                    _ => TermKind::Text(token.text),
+                }
+            };
+            terms.push(QueryTerm {
+                negated: token.negated,
+                kind,
+            });
+        }
+        Ok(Self { terms })
+    }
+
+    /// Make a query that matches messages containing the given text, without parsing it.
+    /// Useful as a fallback when a query the user is still typing can't be parsed yet.
+    pub fn text(text: &str) -> Self {
+        let terms = if text.trim().is_empty() {
+            vec![]
+        } else {
+            vec![QueryTerm {
+                negated: false,
+                kind: TermKind::Text(text.to_string()),
+            }]
+        };
+        Self { terms }
+    }
+
+    /// Check if this query has no terms, meaning it matches every message
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Check if a message matches every term of this query
+    pub fn matches(&self, message: &SocketMessage) -> bool {
+        self.terms.iter().all(|t| t.matches(message))
+    }
+}
+
+impl FromStr for LogQuery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_query_terms() {
+        let error = SocketMessage::get_test(
+            "NewHorizons",
+            "Couldn't load planet",
+            SocketMessageType::Error,
+        );
+        let shader = SocketMessage::get_test(
+            "New Horizons",
+            "Couldn't compile shader",
+            SocketMessageType::Fatal,
+        );
+        let info = SocketMessage::get_test("Example.Mod", "Loaded", SocketMessageType::Info);
+
+        let query = LogQuery::parse("type:error,FATAL sender:newhorizons").unwrap();
+        assert!(query.matches(&error));
+        assert!(query.matches(&shader));
+        assert!(!query.matches(&info));
+
+        let query = LogQuery::parse("-\"compile shader\" -type:info").unwrap();
+        assert!(query.matches(&error));
+        assert!(!query.matches(&shader));
+        assert!(!query.matches(&info));
+
+        let query = LogQuery::parse("/^Load(ed)?$/").unwrap();
+        assert!(!query.matches(&error));
+        assert!(query.matches(&info));
+
+        let query = LogQuery::parse("sender:\"New Horizons\" planet").unwrap();
+        assert!(query.matches(&error));
+        assert!(!query.matches(&shader));
+    }
+
+    #[test]
+    fn test_query_empty() {
+        let query = LogQuery::parse("   ").unwrap();
+        assert!(query.is_empty());
+        assert!(query.matches(&SocketMessage::get_test("A", "B", SocketMessageType::Debug)));
+    }
+
+    #[test]
+    fn test_query_regex_escape() {
+        let query = LogQuery::parse(r"/a\/b c/").unwrap();
+        assert!(query.matches(&SocketMessage::get_test(
+            "A",
+            "xa/b cx",
+            SocketMessageType::Message
+        )));
+        assert!(!query.matches(&SocketMessage::get_test(
+            "A",
+            "a b c",
+            SocketMessageType::Message
+        )));
+    }
+
+    #[test]
+    fn test_query_errors() {
+        assert!(LogQuery::parse("type:nope").is_err());
+        assert!(LogQuery::parse("/unclosed").is_err());
+        assert!(LogQuery::parse("/(/").is_err());
+        assert!(LogQuery::parse("\"unclosed").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -174,6 +174,10 @@ pub struct ModManifest {
     /// The dependencies of the mod
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dependencies: Option<Vec<String>>,
+    /// Version requirements for the mod's dependencies, maps unique names in `dependencies` to a requirement like `>=1.2.0`.
+    /// Multiple requirements can be separated with commas, and a plain version is treated as a minimum version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency_versions: Option<HashMap<String, String>>,
     /// The mods this mod will conflict with
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conflicts: Option<Vec<String>>,
@@ -199,6 +203,14 @@ pub struct ModManifest {
 }
 
 impl ModManifest {
+    /// Get the version requirement this mod has for one of its dependencies, if it has one
+    pub fn get_dependency_version(&self, unique_name: &str) -> Option<&str> {
+        self.dependency_versions
+            .as_ref()
+            .and_then(|v| v.get(unique_name))
+            .map(|v| v.as_str())
+    }
+
     #[allow(deprecated)]
     /// Migrates the `donate_link` field to `donate_links`
     /// Simply adds the `donate_link` to the `donate_links` vec (or creates it if it doesn't exist)
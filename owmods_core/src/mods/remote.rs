@@ -10,6 +10,9 @@ use crate::{constants::REQUIRES_DLC_TAG, search::Searchable};
 pub struct RemoteMod {
     /// The URL to download the mod from, always GitHub
     pub download_url: String,
+    /// The SHA-256 hash of the archive at `download_url`, if the database provides one.
+    /// Installs from the database will be verified against this.
+    pub hash: Option<String>,
     /// The number of times the mod has been downloaded, this uses GitHub releases
     pub download_count: u32,
     /// The version of the mod, usually in the format `major.minor.patch`
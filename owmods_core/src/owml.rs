@@ -80,7 +80,8 @@ impl OWMLConfig {
         serialize_to_json(self, path, true)
     }
 
-    /// Get the default OWML config (OWML.DefaultConfig.json)
+    /// Get the default OWML config (OWML.DefaultConfig.json).
+    /// The game path is set to the best install found by [crate::game_paths::find_game_paths], falling back to the default Steam location.
     ///
     /// ## Errors
     ///
@@ -88,6 +89,7 @@ impl OWMLConfig {
     ///
     #[cfg(not(windows))]
     pub fn default(config: &Config) -> Result<OWMLConfig> {
+        use crate::game_paths::find_game_paths;
         use anyhow::Context;
         use directories::UserDirs;
 
@@ -95,13 +97,17 @@ impl OWMLConfig {
 
         let path = Path::new(&config.owml_path).join(OWML_DEFAULT_CONFIG_NAME);
         let mut conf: OWMLConfig = deserialize_from_json(&path)?;
-        let dirs = UserDirs::new().context("Can't get user data dir")?;
-        conf.game_path = dirs
-            .home_dir()
-            .join(LINUX_GAME_PATH)
-            .to_str()
-            .unwrap()
-            .to_string();
+        conf.game_path = match find_game_paths().into_iter().next() {
+            Some(candidate) => candidate.path,
+            None => {
+                let dirs = UserDirs::new().context("Can't get user data dir")?;
+                dirs.home_dir()
+                    .join(LINUX_GAME_PATH)
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            }
+        };
         Ok(conf)
     }
 
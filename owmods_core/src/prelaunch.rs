@@ -0,0 +1,551 @@
+use std::path::Path;
+
+use serde::Serialize;
+use typeshare::typeshare;
+
+use crate::{
+    alerts::get_warnings,
+    config::Config,
+    db::{LocalDatabase, RemoteDatabase},
+    diagnostics::ModIssues,
+    game::has_mono,
+    game_paths::{validate_game_path, GamePathProblem},
+    mods::local::ModWarning,
+    owml::OWMLConfig,
+    updates::{check_mod_held_back, check_mod_needs_update},
+    validate::ModValidationError,
+};
+
+/// How serious a pre-launch finding is
+#[typeshare]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PrelaunchSeverity {
+    /// Good to know, but nothing is wrong
+    Info,
+    /// Something the user should see, but the game can still launch
+    Warning,
+    /// The game or some mods probably won't work, launching is refused unless forced
+    Error,
+}
+
+/// Something a pre-launch check found
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "checkType", content = "payload")]
+pub enum PrelaunchCheck {
+    /// An enabled mod has a warning the user hasn't seen yet
+    #[serde(rename_all = "camelCase")]
+    UnseenWarning {
+        /// The unique name of the mod
+        unique_name: String,
+        /// The warning to show
+        warning: ModWarning,
+    },
+    /// A mod failed to load or an enabled mod has issues, like a missing DLL or a missing or disabled dependency.
+    /// Conflicts and updates are reported separately.
+    InvalidMod(ModIssues),
+    /// Two enabled mods conflict with each other
+    #[serde(rename_all = "camelCase")]
+    Conflict {
+        /// The unique name of the mod that declares the conflict
+        unique_name: String,
+        /// The unique name of the mod it conflicts with
+        conflicts_with: String,
+    },
+    /// An enabled mod has an update that isn't held back by a pin
+    #[serde(rename_all = "camelCase")]
+    OutdatedMod {
+        /// The unique name of the mod
+        unique_name: String,
+        /// The version that's installed
+        installed: String,
+        /// The newest version
+        latest: String,
+    },
+    /// OWML isn't installed
+    MissingOwml,
+    /// OWML has an update that isn't held back by a pin
+    OutdatedOwml {
+        /// The version that's installed
+        installed: String,
+        /// The newest version
+        latest: String,
+    },
+    /// OWML's game path doesn't point to a usable Outer Wilds install
+    #[serde(rename_all = "camelCase")]
+    InvalidGamePath {
+        /// The game path in OWML's config
+        game_path: String,
+        /// What's wrong with it
+        problems: Vec<GamePathProblem>,
+    },
+    /// An enabled mod needs the Echoes of the Eye DLC, but it doesn't seem to be installed
+    #[serde(rename_all = "camelCase")]
+    MissingDlc {
+        /// The unique name of the mod
+        unique_name: String,
+    },
+    /// Mono couldn't be found, it's needed to launch the game on Linux and macOS
+    MissingMono,
+}
+
+/// A single finding from [run_prelaunch_checks]
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PrelaunchFinding {
+    /// How serious the finding is
+    pub severity: PrelaunchSeverity,
+    /// What was found
+    pub check: PrelaunchCheck,
+}
+
+/// Everything the pre-launch checks found, most severe first
+#[typeshare]
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PrelaunchReport {
+    /// The findings, sorted from most to least severe
+    pub findings: Vec<PrelaunchFinding>,
+}
+
+impl PrelaunchReport {
+    fn push(&mut self, severity: PrelaunchSeverity, check: PrelaunchCheck) {
+        self.findings.push(PrelaunchFinding { severity, check });
+    }
+
+    /// Check if any finding is an error
+    pub fn has_errors(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|f| f.severity == PrelaunchSeverity::Error)
+    }
+
+    /// Check if the game shouldn't be launched.
+    /// Forcing a launch only ever skips findings with [PrelaunchSeverity::Error], nothing else.
+    pub fn blocks_launch(&self, force: bool) -> bool {
+        !force && self.has_errors()
+    }
+
+    /// Get the mod warnings that haven't been shown yet, as unique names and warnings
+    pub fn unseen_warnings(&self) -> impl Iterator<Item = (&str, &ModWarning)> {
+        self.findings.iter().filter_map(|f| match &f.check {
+            PrelaunchCheck::UnseenWarning {
+                unique_name,
+                warning,
+            } => Some((unique_name.as_str(), warning)),
+            _ => None,
+        })
+    }
+
+    /// Mark every unseen warning in this report as shown in the config, call this after showing them.
+    /// Note this doesn't save the config.
+    pub fn mark_warnings_shown(&self, config: &mut Config) {
+        for (unique_name, _) in self.unseen_warnings() {
+            config.set_warning_shown(unique_name);
+        }
+    }
+}
+
+fn check_mods(report: &mut PrelaunchReport, local_db: &LocalDatabase) {
+    let mut invalid = local_db.invalid().collect::<Vec<_>>();
+    invalid.sort_by_key(|m| m.get_unique_name());
+    for local_mod in invalid {
+        let mut errors = vec![];
+        for error in local_mod.get_errs() {
+            match error {
+                ModValidationError::ConflictingMod(conflicts_with) => report.push(
+                    PrelaunchSeverity::Error,
+                    PrelaunchCheck::Conflict {
+                        unique_name: local_mod.get_unique_name().clone(),
+                        conflicts_with: conflicts_with.clone(),
+                    },
+                ),
+                // Updates are checked against the remote database instead
+                ModValidationError::Outdated(_) => {}
+                _ => errors.push(error.clone()),
+            }
+        }
+        if !errors.is_empty() {
+            report.push(
+                PrelaunchSeverity::Error,
+                PrelaunchCheck::InvalidMod(ModIssues {
+                    unique_name: local_mod.get_unique_name().clone(),
+                    name: local_mod.get_name().clone(),
+                    errors,
+                }),
+            );
+        }
+    }
+}
+
+fn check_owml(report: &mut PrelaunchReport, config: &Config, remote_db: Option<&RemoteDatabase>) {
+    let Some(owml) = LocalDatabase::get_owml(&config.owml_path) else {
+        report.push(PrelaunchSeverity::Error, PrelaunchCheck::MissingOwml);
+        return;
+    };
+    if let Some(remote_db) = remote_db {
+        if let (true, Some(remote_owml)) = check_mod_needs_update(&owml, remote_db) {
+            // Don't offer to update OWML past its pin
+            if check_mod_held_back(&owml, remote_db, config).is_some() {
+                return;
+            }
+            report.push(
+                PrelaunchSeverity::Warning,
+                PrelaunchCheck::OutdatedOwml {
+                    installed: owml.manifest.version.clone(),
+                    latest: remote_owml.version.clone(),
+                },
+            );
+        }
+    }
+}
+
+fn check_game(
+    report: &mut PrelaunchReport,
+    config: &Config,
+    local_db: &LocalDatabase,
+    remote_db: Option<&RemoteDatabase>,
+) {
+    let Ok(owml_config) = OWMLConfig::get(config) else {
+        return;
+    };
+    // OWML looks for the game itself if the path is left empty
+    if owml_config.game_path.trim().is_empty() {
+        return;
+    }
+    let install = validate_game_path(Path::new(&owml_config.game_path));
+    if !install.is_valid() {
+        // OWML can sometimes find the game on its own, but never if we point it at the wrong one
+        let severity = if install.problems.contains(&GamePathProblem::OuterWorlds) {
+            PrelaunchSeverity::Error
+        } else {
+            PrelaunchSeverity::Warning
+        };
+        report.push(
+            severity,
+            PrelaunchCheck::InvalidGamePath {
+                game_path: install.path,
+                problems: install.problems,
+            },
+        );
+        return;
+    }
+    if let (false, Some(remote_db)) = (install.has_dlc, remote_db) {
+        let mut active = local_db.active().collect::<Vec<_>>();
+        active.sort_by_key(|m| &m.manifest.unique_name);
+        for local_mod in active {
+            let unique_name = &local_mod.manifest.unique_name;
+            if remote_db
+                .get_mod(unique_name)
+                .is_some_and(|m| m.requires_dlc())
+            {
+                report.push(
+                    PrelaunchSeverity::Warning,
+                    PrelaunchCheck::MissingDlc {
+                        unique_name: unique_name.clone(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Run every check that should happen before the game is launched.
+///
+/// - Enabled mods with warnings the user hasn't seen yet ([PrelaunchSeverity::Warning])
+/// - Mods that failed to load or enabled mods with validation errors ([PrelaunchSeverity::Error])
+/// - Enabled mods that conflict with each other ([PrelaunchSeverity::Error])
+/// - Enabled mods with updates that aren't held back by a pin, if `remote_db` is given ([PrelaunchSeverity::Info])
+/// - OWML not being installed ([PrelaunchSeverity::Error]) or having an update that isn't held back by a pin, if `remote_db` is given ([PrelaunchSeverity::Warning])
+/// - OWML's game path not containing the game ([PrelaunchSeverity::Warning]) or pointing to The Outer Worlds ([PrelaunchSeverity::Error]), see [validate_game_path]
+/// - Enabled mods that need the DLC when it doesn't seem to be installed, if `remote_db` is given ([PrelaunchSeverity::Warning]).
+///   DLC detection is a best guess, see [crate::game_paths::GameInstall::has_dlc]
+/// - Mono not being installed on Linux and macOS ([PrelaunchSeverity::Error])
+///
+/// Frontends should show every finding, show and then mark unseen warnings with [PrelaunchReport::mark_warnings_shown],
+/// and refuse to launch if [PrelaunchReport::blocks_launch] says so.
+///
+/// ## Returns
+///
+/// A report with everything that was found, most severe first.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::prelaunch::run_prelaunch_checks;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+///
+/// let report = run_prelaunch_checks(&config, &local_db, None);
+/// for finding in report.findings.iter() {
+///     println!("{:?}: {:?}", finding.severity, finding.check);
+/// }
+/// if report.blocks_launch(false) {
+///     println!("Not launching the game");
+/// }
+/// ```
+///
+pub fn run_prelaunch_checks(
+    config: &Config,
+    local_db: &LocalDatabase,
+    remote_db: Option<&RemoteDatabase>,
+) -> PrelaunchReport {
+    let mut report = PrelaunchReport::default();
+
+    let viewed = config.viewed_alerts.iter().map(|a| a.as_str()).collect();
+    for (unique_name, warning) in get_warnings(local_db.active().collect(), viewed) {
+        report.push(
+            PrelaunchSeverity::Warning,
+            PrelaunchCheck::UnseenWarning {
+                unique_name: unique_name.to_string(),
+                warning: warning.clone(),
+            },
+        );
+    }
+
+    check_mods(&mut report, local_db);
+
+    if let Some(remote_db) = remote_db {
+        let mut active = local_db.active().collect::<Vec<_>>();
+        active.sort_by_key(|m| &m.manifest.unique_name);
+        for local_mod in active {
+            if let (true, Some(remote_mod)) = check_mod_needs_update(local_mod, remote_db) {
+                if check_mod_held_back(local_mod, remote_db, config).is_some() {
+                    continue;
+                }
+                report.push(
+                    PrelaunchSeverity::Info,
+                    PrelaunchCheck::OutdatedMod {
+                        unique_name: local_mod.manifest.unique_name.clone(),
+                        installed: local_mod.manifest.version.clone(),
+                        latest: remote_mod.version.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    check_owml(&mut report, config, remote_db);
+    check_game(&mut report, config, local_db, remote_db);
+
+    if !has_mono() {
+        report.push(PrelaunchSeverity::Error, PrelaunchCheck::MissingMono);
+    }
+
+    report
+        .findings
+        .sort_by_key(|f| std::cmp::Reverse(f.severity));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs::{create_dir_all, write};
+
+    use crate::{
+        constants::{OWML_MANIFEST_NAME, OWML_UNIQUE_NAME, REQUIRES_DLC_TAG},
+        file::serialize_to_json,
+        mods::{local::LocalMod, remote::RemoteMod},
+        test_utils::TestContext,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_prelaunch_checks() {
+        let mut ctx = TestContext::new();
+        let mut warned = LocalMod::get_test(1);
+        warned.manifest.warning = Some(ModWarning {
+            title: "Careful".to_string(),
+            body: "This mod is scary".to_string(),
+        });
+        ctx.insert_test_mod(&warned);
+        let mut broken = LocalMod::get_test(2);
+        broken.errors = vec![
+            ModValidationError::MissingDep("Example.Missing".to_string()),
+            ModValidationError::ConflictingMod("Example.TestMod1".to_string()),
+        ];
+        ctx.insert_test_mod(&broken);
+        let mut remote_db = RemoteDatabase::default();
+        let mut remote_mod = RemoteMod::get_test(1);
+        remote_mod.version = "99.0.0".to_string();
+        remote_db
+            .mods
+            .insert(remote_mod.unique_name.clone(), remote_mod);
+
+        let mut report = run_prelaunch_checks(&ctx.config, &ctx.local_db, Some(&remote_db));
+        // Whether these show up depends on the machine running the tests
+        report.findings.retain(|f| {
+            !matches!(
+                f.check,
+                PrelaunchCheck::MissingOwml | PrelaunchCheck::MissingMono
+            )
+        });
+
+        let severities = report
+            .findings
+            .iter()
+            .map(|f| f.severity)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            severities,
+            vec![
+                PrelaunchSeverity::Error,
+                PrelaunchSeverity::Error,
+                PrelaunchSeverity::Warning,
+                PrelaunchSeverity::Info
+            ]
+        );
+        assert!(matches!(
+            &report.findings[0].check,
+            PrelaunchCheck::Conflict { unique_name, conflicts_with }
+                if unique_name == "Example.TestMod2" && conflicts_with == "Example.TestMod1"
+        ));
+        assert!(matches!(
+            &report.findings[1].check,
+            PrelaunchCheck::InvalidMod(issues) if issues.errors.len() == 1
+        ));
+        assert!(matches!(
+            &report.findings[3].check,
+            PrelaunchCheck::OutdatedMod { latest, .. } if latest == "99.0.0"
+        ));
+        assert!(report.blocks_launch(false));
+        assert!(!report.blocks_launch(true));
+
+        let warnings = report.unseen_warnings().collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].0, "Example.TestMod1");
+        report.mark_warnings_shown(&mut ctx.config);
+        let report = run_prelaunch_checks(&ctx.config, &ctx.local_db, None);
+        assert_eq!(report.unseen_warnings().count(), 0);
+    }
+
+    #[test]
+    fn test_prelaunch_checks_pinned() {
+        let mut ctx = TestContext::new();
+        ctx.insert_test_mod(&LocalMod::get_test(1));
+        let mut owml = LocalMod::get_test(0);
+        owml.manifest.unique_name = OWML_UNIQUE_NAME.to_string();
+        owml.manifest.version = "1.0.0".to_string();
+        create_dir_all(&ctx.owml_dir).unwrap();
+        serialize_to_json(
+            &owml.manifest,
+            &ctx.owml_dir.join(OWML_MANIFEST_NAME),
+            false,
+        )
+        .unwrap();
+        let mut remote_db = RemoteDatabase::default();
+        let mut remote_mod = RemoteMod::get_test(1);
+        remote_mod.version = "99.0.0".to_string();
+        remote_db
+            .mods
+            .insert(remote_mod.unique_name.clone(), remote_mod);
+        let mut remote_owml = RemoteMod::get_test(0);
+        remote_owml.unique_name = OWML_UNIQUE_NAME.to_string();
+        remote_owml.version = "2.0.0".to_string();
+        remote_db.owml = Some(remote_owml);
+        let outdated = |config: &Config| {
+            run_prelaunch_checks(config, &ctx.local_db, Some(&remote_db))
+                .findings
+                .into_iter()
+                .filter(|f| {
+                    matches!(
+                        f.check,
+                        PrelaunchCheck::OutdatedMod { .. } | PrelaunchCheck::OutdatedOwml { .. }
+                    )
+                })
+                .count()
+        };
+
+        assert_eq!(outdated(&ctx.config), 2);
+        let mut config = ctx.config.clone();
+        config
+            .pins
+            .insert("Example.TestMod1".to_string(), "hold".to_string());
+        config
+            .pins
+            .insert(OWML_UNIQUE_NAME.to_string(), "hold".to_string());
+        assert_eq!(outdated(&config), 0);
+    }
+
+    #[test]
+    fn test_prelaunch_game_checks() {
+        let mut ctx = TestContext::new();
+        ctx.insert_test_mod(&LocalMod::get_test(1));
+        let mut remote_db = RemoteDatabase::default();
+        let mut remote_mod = RemoteMod::get_test(1);
+        remote_mod.tags = Some(vec![REQUIRES_DLC_TAG.to_string()]);
+        remote_db
+            .mods
+            .insert(remote_mod.unique_name.clone(), remote_mod);
+
+        let game = ctx.temp_dir.path().join("game");
+        create_dir_all(game.join("OuterWilds_Data")).unwrap();
+        write(game.join("OuterWilds.exe"), "").unwrap();
+        let write_game_path = |path: &Path| {
+            create_dir_all(&ctx.owml_dir).unwrap();
+            write(
+                ctx.owml_dir.join("OWML.Config.json"),
+                serde_json::json!({ "gamePath": path, "socketPort": 0 }).to_string(),
+            )
+            .unwrap();
+        };
+        let game_checks = |report: PrelaunchReport| {
+            report
+                .findings
+                .into_iter()
+                .filter(|f| {
+                    matches!(
+                        f.check,
+                        PrelaunchCheck::InvalidGamePath { .. } | PrelaunchCheck::MissingDlc { .. }
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        write_game_path(&game);
+        let findings = game_checks(run_prelaunch_checks(
+            &ctx.config,
+            &ctx.local_db,
+            Some(&remote_db),
+        ));
+        assert_eq!(findings.len(), 1);
+        assert!(matches!(
+            &findings[0].check,
+            PrelaunchCheck::MissingDlc { unique_name } if unique_name == "Example.TestMod1"
+        ));
+
+        write_game_path(&ctx.temp_dir.path().join("nope"));
+        let findings = game_checks(run_prelaunch_checks(&ctx.config, &ctx.local_db, None));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, PrelaunchSeverity::Warning);
+        assert!(matches!(
+            &findings[0].check,
+            PrelaunchCheck::InvalidGamePath { problems, .. } if problems == &[GamePathProblem::NotFound]
+        ));
+    }
+
+    #[test]
+    fn test_prelaunch_checks_missing_owml() {
+        let ctx = TestContext::new();
+        let mut config = ctx.config.clone();
+        config.owml_path = ctx
+            .temp_dir
+            .path()
+            .join("nope")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let report = run_prelaunch_checks(&config, &ctx.local_db, None);
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| matches!(f.check, PrelaunchCheck::MissingOwml)));
+        assert!(report.blocks_launch(false));
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    collections::HashMap,
+    fs::{read_dir, remove_file},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use typeshare::typeshare;
+
+use crate::{
+    config::Config,
+    constants::PROFILES_FOLDER_NAME,
+    db::{LocalDatabase, RemoteDatabase},
+    download::install_mods_parallel,
+    file::{deserialize_from_json, serialize_to_json, validate_file_name},
+    graph::DependentsMode,
+    mods::local::LocalMod,
+    toggle::{read_config, toggle_mod, write_config},
+};
+
+/// A named set of enabled mods, optionally with the settings each mod should use.
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// The name of the profile, also used as its file name
+    pub name: String,
+    /// The unique names of the mods that should be enabled, all others will be disabled
+    pub enabled: Vec<String>,
+    /// Settings to write to each mod's config.json when the profile is applied, keyed by unique name
+    #[serde(default)]
+    #[typeshare(skip)]
+    pub settings: HashMap<String, Map<String, Value>>,
+}
+
+fn get_profiles_dir(config: &Config) -> PathBuf {
+    config.data_dir().join(PROFILES_FOLDER_NAME)
+}
+
+fn get_profile_path(name: &str, config: &Config) -> Result<PathBuf> {
+    let name = validate_file_name(name, "profile")?;
+    Ok(get_profiles_dir(config).join(format!("{name}.json")))
+}
+
+fn read_mod_settings(local_mod: &LocalMod) -> Result<Option<Map<String, Value>>> {
+    let config_path = PathBuf::from(&local_mod.mod_path).join("config.json");
+    if config_path.is_file() {
+        Ok(read_config(&config_path)?.settings)
+    } else {
+        Ok(None)
+    }
+}
+
+fn write_mod_settings(local_mod: &LocalMod, settings: &Map<String, Value>) -> Result<()> {
+    let config_path = PathBuf::from(&local_mod.mod_path).join("config.json");
+    let mut mod_config = read_config(&config_path)?;
+    mod_config.settings = Some(settings.clone());
+    write_config(&mod_config, &config_path)
+}
+
+impl Profile {
+    /// Create a profile from the mods that are currently enabled in the local database.
+    ///
+    /// ## Errors
+    ///
+    /// If `include_settings` is true and we can't read the config of an enabled mod.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    /// use owmods_core::db::LocalDatabase;
+    /// use owmods_core::profiles::Profile;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+    /// let profile = Profile::from_local_db("Speedrun", &local_db, true).unwrap();
+    /// profile.save(&config).unwrap();
+    /// ```
+    ///
+    pub fn from_local_db(
+        name: &str,
+        local_db: &LocalDatabase,
+        include_settings: bool,
+    ) -> Result<Self> {
+        let mut enabled: Vec<String> = vec![];
+        let mut settings: HashMap<String, Map<String, Value>> = HashMap::new();
+        for local_mod in local_db.active() {
+            let unique_name = &local_mod.manifest.unique_name;
+            enabled.push(unique_name.clone());
+            if include_settings {
+                let mod_settings = read_mod_settings(local_mod)
+                    .with_context(|| format!("Couldn't read settings of {unique_name}"))?;
+                if let Some(mod_settings) = mod_settings {
+                    settings.insert(unique_name.clone(), mod_settings);
+                }
+            }
+        }
+        enabled.sort();
+        Ok(Self {
+            name: name.trim().to_string(),
+            enabled,
+            settings,
+        })
+    }
+
+    /// Save this profile, overwriting any profile with the same name.
+    ///
+    /// ## Errors
+    ///
+    /// If the profile's name isn't a valid file name (empty, ending with a dot, or containing a path separator or a character Windows doesn't allow) or we can't write the file.
+    ///
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = get_profile_path(&self.name, config)?;
+        debug!("Saving profile {} to {}", self.name, path.display());
+        serialize_to_json(self, &path, true)
+    }
+
+    /// Load a saved profile by name.
+    ///
+    /// ## Errors
+    ///
+    /// If no profile with that name exists or we can't read it.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    /// use owmods_core::profiles::Profile;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let profile = Profile::load("Speedrun", &config).unwrap();
+    /// println!("{} mods enabled in {}", profile.enabled.len(), profile.name);
+    /// ```
+    ///
+    pub fn load(name: &str, config: &Config) -> Result<Self> {
+        let path = get_profile_path(name, config)?;
+        if !path.is_file() {
+            return Err(anyhow!("Profile \"{name}\" not found"));
+        }
+        deserialize_from_json(&path).with_context(|| format!("Couldn't read profile \"{name}\""))
+    }
+}
+
+/// List all saved profiles, sorted by name.
+/// Profiles that can't be read are skipped with a warning.
+///
+/// ## Errors
+///
+/// If we can't read the profiles folder.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::profiles::list_profiles;
+///
+/// let config = Config::get(None).unwrap();
+/// for profile in list_profiles(&config).unwrap() {
+///     println!("{}", profile.name);
+/// }
+/// ```
+///
+pub fn list_profiles(config: &Config) -> Result<Vec<Profile>> {
+    let dir = get_profiles_dir(config);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut profiles: Vec<Profile> = read_dir(&dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                deserialize_from_json(&path)
+                    .map_err(|e| warn!("Couldn't read profile at {}: {e:?}", path.display()))
+                    .ok()
+            } else {
+                None
+            }
+        })
+        .collect();
+    profiles.sort_by(|a: &Profile, b: &Profile| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+/// Delete a saved profile.
+///
+/// ## Errors
+///
+/// If no profile with that name exists or we can't delete it.
+///
+pub fn delete_profile(name: &str, config: &Config) -> Result<()> {
+    let path = get_profile_path(name, config)?;
+    if !path.is_file() {
+        return Err(anyhow!("Profile \"{name}\" not found"));
+    }
+    remove_file(&path).with_context(|| format!("Couldn't delete profile \"{name}\""))
+}
+
+/// Apply a profile: enable every mod in it, disable every other mod, and install any mods it needs that aren't installed.
+/// Mods that aren't installed and aren't in the remote database are skipped with a warning.
+/// If the profile has settings for a mod, they replace the settings in that mod's config.json.
+///
+/// ## Returns
+///
+/// A list of mod unique names that were disabled and use pre patchers,
+/// and therefore **should alert the user to check the mod's README for instructions on how to fully disable it**.
+///
+/// ## Errors
+///
+/// If we can't toggle a mod, install a missing mod, or write a mod's settings.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::profiles::{apply_profile, Profile};
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let remote_db = RemoteDatabase::fetch(&config.database_url).await.unwrap();
+/// let profile = Profile::load("Speedrun", &config).unwrap();
+///
+/// apply_profile(&profile, &config, &local_db, &remote_db).await.unwrap();
+/// # });
+/// ```
+///
+pub async fn apply_profile(
+    profile: &Profile,
+    config: &Config,
+    local_db: &LocalDatabase,
+    remote_db: &RemoteDatabase,
+) -> Result<Vec<String>> {
+    let mut show_warnings_for: Vec<String> = vec![];
+    let mut needed_install: Vec<String> = vec![];
+
+    for local_mod in local_db.valid() {
+        let unique_name = &local_mod.manifest.unique_name;
+        if local_mod.enabled && !profile.enabled.contains(unique_name) {
+            show_warnings_for.extend(toggle_mod(
+                unique_name,
+                local_db,
+                false,
+                false,
+                DependentsMode::Ignore,
+            )?);
+        }
+    }
+
+    for unique_name in profile.enabled.iter() {
+        if let Some(local_mod) = local_db.get_mod(unique_name) {
+            if !local_mod.enabled {
+                toggle_mod(unique_name, local_db, true, false, DependentsMode::Ignore)?;
+            }
+        } else if remote_db.get_mod(unique_name).is_some() {
+            needed_install.push(unique_name.clone());
+        } else {
+            warn!("Mod {unique_name} isn't installed and wasn't found in the database, skipping");
+        }
+    }
+
+    let installed = install_mods_parallel(needed_install, config, remote_db, local_db).await?;
+
+    for (unique_name, settings) in profile.settings.iter() {
+        let local_mod = local_db.get_mod(unique_name).or_else(|| {
+            installed
+                .iter()
+                .find(|m| &m.manifest.unique_name == unique_name)
+        });
+        if let Some(local_mod) = local_mod {
+            write_mod_settings(local_mod, settings)
+                .with_context(|| format!("Couldn't write settings of {unique_name}"))?;
+        }
+    }
+
+    Ok(show_warnings_for)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{test_utils::TestContext, toggle::get_mod_enabled};
+
+    use super::*;
+
+    fn make_profile(name: &str, enabled: &[&str]) -> Profile {
+        Profile {
+            name: name.to_string(),
+            enabled: enabled.iter().map(|s| s.to_string()).collect(),
+            settings: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_profile_save_load() {
+        let ctx = TestContext::new();
+        let profile = make_profile("Test", &["Bwc9876.TimeSaver"]);
+        profile.save(&ctx.config).unwrap();
+        assert!(ctx.temp_dir.path().join("profiles/Test.json").is_file());
+        let loaded = Profile::load("Test", &ctx.config).unwrap();
+        assert_eq!(profile, loaded);
+    }
+
+    #[test]
+    fn test_profile_invalid_name() {
+        let ctx = TestContext::new();
+        let profile = make_profile("../Test", &[]);
+        assert!(profile.save(&ctx.config).is_err());
+        assert!(make_profile("  ", &[]).save(&ctx.config).is_err());
+    }
+
+    #[test]
+    fn test_profile_load_missing() {
+        let ctx = TestContext::new();
+        assert!(Profile::load("Missing", &ctx.config).is_err());
+    }
+
+    #[test]
+    fn test_list_and_delete_profiles() {
+        let ctx = TestContext::new();
+        assert!(list_profiles(&ctx.config).unwrap().is_empty());
+        make_profile("B", &[]).save(&ctx.config).unwrap();
+        make_profile("A", &[]).save(&ctx.config).unwrap();
+        let names: Vec<String> = list_profiles(&ctx.config)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["A", "B"]);
+        delete_profile("A", &ctx.config).unwrap();
+        assert_eq!(list_profiles(&ctx.config).unwrap().len(), 1);
+        assert!(delete_profile("A", &ctx.config).is_err());
+    }
+
+    #[test]
+    fn test_profile_from_local_db() {
+        let mut ctx = TestContext::new();
+        ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
+        ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+        toggle_mod(
+            "Bwc9876.SaveEditor",
+            &ctx.local_db,
+            false,
+            false,
+            DependentsMode::Ignore,
+        )
+        .unwrap();
+        ctx.fetch_local_db();
+        let profile = Profile::from_local_db("Test", &ctx.local_db, true).unwrap();
+        assert_eq!(profile.enabled, vec!["Bwc9876.TimeSaver"]);
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        tokio_test::block_on(async {
+            let mut ctx = TestContext::new();
+            ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
+            ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+            toggle_mod(
+                "Bwc9876.SaveEditor",
+                &ctx.local_db,
+                false,
+                false,
+                DependentsMode::Ignore,
+            )
+            .unwrap();
+            ctx.fetch_local_db();
+            let mut profile = make_profile("Test", &["Bwc9876.SaveEditor"]);
+            let mut settings = Map::new();
+            settings.insert("test".to_string(), Value::Bool(true));
+            profile
+                .settings
+                .insert("Bwc9876.SaveEditor".to_string(), settings.clone());
+            apply_profile(&profile, &ctx.config, &ctx.local_db, &ctx.remote_db)
+                .await
+                .unwrap();
+            assert!(!get_mod_enabled(&ctx.get_test_path("Bwc9876.TimeSaver")).unwrap());
+            assert!(get_mod_enabled(&ctx.get_test_path("Bwc9876.SaveEditor")).unwrap());
+            let save_editor = ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap();
+            assert_eq!(read_mod_settings(save_editor).unwrap(), Some(settings));
+        });
+    }
+}
@@ -196,6 +196,18 @@ impl ProgressBar {
         }
     }
 
+    /// Set the progress of the progress bar to an exact value, this isn't throttled like [ProgressBar::inc]
+    /// Useful for when a task has to go back, like a download restarting
+    pub fn set_progress(&mut self, progress: ProgressValue) {
+        self.progress = progress.min(self.len);
+        self.throttled_progress = self.progress;
+        let payload = ProgressPayload::Increment(ProgressIncrementPayload {
+            id: self.id.clone(),
+            progress: self.progress,
+        });
+        self.emit_event(payload);
+    }
+
     /// Set the message of the progress bar
     pub fn set_msg(&self, msg: &str) {
         let payload = ProgressPayload::Msg(ProgressMessagePayload {
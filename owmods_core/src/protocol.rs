@@ -47,6 +47,7 @@ impl ProtocolVerb {
 /// Some examples of valid URIs are:
 /// - owmods://install-mod/Bwc9876.TimeSaver
 /// - owmods://install-url/https://example.com/Mod.zip
+/// - owmods://install-url/https://example.com/Mod.zip#sha256=2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824
 /// - owmods://install-zip//home/user/Downloads/Mod.zip
 /// - owmods://install-prerelease/Raicuparta.NomaiVR
 /// - owmods://run-game/Bwc9876.TimeSaver
@@ -93,6 +94,34 @@ impl ProtocolPayload {
     }
 }
 
+/// Split an expected hash off the end of a URL, these are given as a `#sha256=<hash>` fragment.
+/// This lets `install-url` links say what the archive they point to should hash to.
+///
+/// ## Returns
+///
+/// The URL without the fragment, and the hash if there was one.
+///
+/// ## Examples
+///
+/// ```
+/// use owmods_core::protocol::split_url_hash;
+///
+/// let (url, hash) = split_url_hash("https://example.com/Mod.zip#sha256=abc123");
+/// assert_eq!(url, "https://example.com/Mod.zip");
+/// assert_eq!(hash, Some("abc123"));
+///
+/// let (url, hash) = split_url_hash("https://example.com/Mod.zip");
+/// assert_eq!(url, "https://example.com/Mod.zip");
+/// assert_eq!(hash, None);
+/// ```
+///
+pub fn split_url_hash(url: &str) -> (&str, Option<&str>) {
+    match url.rsplit_once("#sha256=") {
+        Some((url, hash)) if !hash.is_empty() => (url, Some(hash)),
+        _ => (url, None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,4 +167,18 @@ mod tests {
         assert!(matches!(payload.verb, ProtocolVerb::Unknown));
         assert_eq!(payload.payload, "");
     }
+
+    #[test]
+    fn test_split_url_hash() {
+        let payload =
+            ProtocolPayload::parse("owmods://install-url/https://example.com/Mod.zip#sha256=abc");
+        assert!(matches!(payload.verb, ProtocolVerb::InstallURL));
+        let (url, hash) = split_url_hash(&payload.payload);
+        assert_eq!(url, "https://example.com/Mod.zip");
+        assert_eq!(hash, Some("abc"));
+        assert_eq!(
+            split_url_hash("https://example.com/Mod.zip#sha256="),
+            ("https://example.com/Mod.zip#sha256=", None)
+        );
+    }
 }
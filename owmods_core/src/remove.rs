@@ -5,14 +5,54 @@ use std::{
 
 use anyhow::Result;
 use glob::glob;
+use log::info;
 
 use crate::{
     db::LocalDatabase,
     file::check_file_matches_paths,
+    graph::{check_enabled_dependents, DependencyGraph, DependentsMode},
     mods::local::{get_paths_to_preserve, FailedMod, LocalMod},
 };
 
-/// Uninstall a mod
+/// Get the dependencies of a mod (recursively) that nothing else would need if it was removed
+fn get_unneeded_deps<'a>(local_mod: &'a LocalMod, db: &'a LocalDatabase) -> Vec<&'a LocalMod> {
+    let graph = DependencyGraph::from_local_db(db);
+    let root = local_mod.manifest.unique_name.as_str();
+    let mut removing: Vec<&str> = vec![root];
+    let mut frontier: Vec<&str> = local_mod
+        .manifest
+        .dependencies
+        .iter()
+        .flatten()
+        .map(|d| d.as_str())
+        .collect();
+    while !frontier.is_empty() {
+        let next = frontier
+            .drain(..)
+            .filter(|d| !removing.contains(d) && db.get_mod(d).is_some())
+            .filter(|d| {
+                graph
+                    .direct_dependents(d)
+                    .iter()
+                    .all(|dependent| removing.contains(dependent))
+            })
+            .collect::<Vec<_>>();
+        for dep in next {
+            if !removing.contains(&dep) {
+                removing.push(dep);
+                frontier.extend(graph.direct_dependencies(dep));
+            }
+        }
+    }
+    removing
+        .into_iter()
+        .filter(|m| *m != root)
+        .filter_map(|m| db.get_mod(m))
+        .collect()
+}
+
+/// Uninstall a mod, if `recursive` is set its dependencies are removed too unless other installed mods still need them.
+/// `dependents` decides what happens to enabled mods that depend on this one, see [DependentsMode].
 ///
 /// ## Returns
 ///
@@ -21,15 +61,38 @@ use crate::{
 ///
 /// ## Errors
 ///
-/// If we can't delete the mod's folder.
+/// - If `dependents` is [DependentsMode::Refuse] and enabled mods depend on this one.
+///   This will be an [crate::graph::EnabledDependentsError].
+/// - If we can't delete the mod's folder.
 ///
 pub fn remove_mod(
     local_mod: &LocalMod,
     db: &LocalDatabase,
     recursive: bool,
+    dependents: DependentsMode,
 ) -> Result<Vec<String>> {
     let mut show_warnings_for: Vec<String> = vec![];
 
+    let unneeded_deps = if recursive {
+        get_unneeded_deps(local_mod, db)
+    } else {
+        vec![]
+    };
+    let changing = unneeded_deps
+        .iter()
+        .map(|m| m.manifest.unique_name.as_str())
+        .collect::<Vec<_>>();
+
+    for dependent in
+        check_enabled_dependents(db, &local_mod.manifest.unique_name, &changing, dependents)?
+    {
+        info!(
+            "Uninstalling {} as it depends on {}",
+            dependent.manifest.name, local_mod.manifest.name
+        );
+        show_warnings_for.extend(remove_mod(dependent, db, false, DependentsMode::Ignore)?);
+    }
+
     if PathBuf::from(&local_mod.mod_path).is_dir() {
         // In case weird circular dep stuff happens, just don't delete it if it doesn't exist
         remove_dir_all(&local_mod.mod_path)?;
@@ -38,15 +101,8 @@ pub fn remove_mod(
         }
     }
 
-    if recursive {
-        let empty: &Vec<String> = &vec![];
-        let deps = local_mod.manifest.dependencies.as_ref().unwrap_or(empty);
-        for dep in deps.iter() {
-            let dep = db.get_mod(dep);
-            if let Some(dep) = dep {
-                show_warnings_for.extend(remove_mod(dep, db, true)?);
-            }
-        }
+    for dep in unneeded_deps {
+        show_warnings_for.extend(remove_mod(dep, db, false, DependentsMode::Ignore)?);
     }
 
     Ok(show_warnings_for)
@@ -98,7 +154,10 @@ mod tests {
 
     use std::fs;
 
-    use crate::{file::create_all_parents, mods::local::UnsafeLocalMod, test_utils::TestContext};
+    use crate::{
+        file::create_all_parents, graph::EnabledDependentsError, mods::local::UnsafeLocalMod,
+        test_utils::TestContext,
+    };
 
     use super::*;
 
@@ -106,7 +165,7 @@ mod tests {
     fn test_remove_mod() {
         let mut ctx = TestContext::new();
         let new_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
-        remove_mod(&new_mod, &ctx.local_db, false).unwrap();
+        remove_mod(&new_mod, &ctx.local_db, false, DependentsMode::Refuse).unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.get_test_path("Bwc9876.TimeSaver").is_dir());
         assert!(ctx.local_db.get_mod("Bwc9876.TimeSaver").is_none());
@@ -118,7 +177,7 @@ mod tests {
         let mut new_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
         ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
         new_mod.manifest.dependencies = Some(vec!["Bwc9876.SaveEditor".to_string()]);
-        remove_mod(&new_mod, &ctx.local_db, true).unwrap();
+        remove_mod(&new_mod, &ctx.local_db, true, DependentsMode::Refuse).unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.get_test_path("Bwc9876.TimeSaver").is_dir());
         assert!(ctx.local_db.get_mod("Bwc9876.TimeSaver").is_none());
@@ -126,6 +185,50 @@ mod tests {
         assert!(ctx.local_db.get_mod("Bwc9876.SaveEditor").is_none());
     }
 
+    #[test]
+    fn test_remove_mod_recursive_keeps_needed_deps() {
+        let mut ctx = TestContext::new();
+        let mut new_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
+        ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+        new_mod.manifest.dependencies = Some(vec!["Bwc9876.SaveEditor".to_string()]);
+        let mut other_mod = LocalMod::get_test(0);
+        other_mod.manifest.dependencies = Some(vec!["Bwc9876.SaveEditor".to_string()]);
+        ctx.insert_test_mod(&other_mod);
+        remove_mod(&new_mod, &ctx.local_db, true, DependentsMode::Refuse).unwrap();
+        ctx.fetch_local_db();
+        assert!(!ctx.get_test_path("Bwc9876.TimeSaver").is_dir());
+        assert!(ctx.get_test_path("Bwc9876.SaveEditor").is_dir());
+        assert!(ctx.local_db.get_mod("Bwc9876.SaveEditor").is_some());
+    }
+
+    #[test]
+    fn test_remove_mod_refuses_enabled_dependents() {
+        let mut ctx = TestContext::new();
+        ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+        let mut other_mod = LocalMod::get_test(0);
+        other_mod.manifest.dependencies = Some(vec!["Bwc9876.SaveEditor".to_string()]);
+        ctx.insert_test_mod(&other_mod);
+        let save_editor = ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap();
+        let err =
+            remove_mod(save_editor, &ctx.local_db, false, DependentsMode::Refuse).unwrap_err();
+        assert!(err.downcast_ref::<EnabledDependentsError>().is_some());
+        assert!(ctx.get_test_path("Bwc9876.SaveEditor").is_dir());
+    }
+
+    #[test]
+    fn test_remove_mod_cascade() {
+        let mut ctx = TestContext::new();
+        let mut new_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
+        ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+        new_mod.manifest.dependencies = Some(vec!["Bwc9876.SaveEditor".to_string()]);
+        ctx.insert_test_mod(&new_mod);
+        let save_editor = ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap();
+        remove_mod(save_editor, &ctx.local_db, false, DependentsMode::Cascade).unwrap();
+        ctx.fetch_local_db();
+        assert!(ctx.local_db.get_mod("Bwc9876.SaveEditor").is_none());
+        assert!(ctx.local_db.get_mod("Bwc9876.TimeSaver").is_none());
+    }
+
     #[test]
     fn test_remove_mod_recursive_cyclical_deps() {
         let mut ctx = TestContext::new();
@@ -144,7 +247,7 @@ mod tests {
             .unwrap()
             .manifest
             .dependencies = Some(vec!["Bwc9876.TimeSaver".to_string()]);
-        remove_mod(&new_mod, &ctx.local_db, true).unwrap();
+        remove_mod(&new_mod, &ctx.local_db, true, DependentsMode::Refuse).unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.get_test_path("Bwc9876.TimeSaver").is_dir());
         assert!(ctx.local_db.get_mod("Bwc9876.TimeSaver").is_none());
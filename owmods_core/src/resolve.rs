@@ -0,0 +1,680 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use typeshare::typeshare;
+
+use crate::{
+    cache::find_cached_archive, config::Config, constants::OWML_UNIQUE_NAME, db::LocalDatabase,
+    db::RemoteDatabase, download::read_manifest_from_zip,
+};
+
+/// Why a mod is part of an [InstallPlan]
+#[typeshare]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "reason", content = "payload")]
+pub enum PlanReason {
+    /// The mod was requested directly
+    Requested,
+    /// The mod is a dependency of another mod in the plan, contains the unique name of that mod
+    Dependency(String),
+}
+
+/// A mod that will be downloaded and installed when executing an [InstallPlan]
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedMod {
+    /// The unique name of the mod
+    pub unique_name: String,
+    /// The name of the mod
+    pub name: String,
+    /// The version that will be installed
+    pub version: String,
+    /// The URL the mod will be downloaded from
+    pub download_url: String,
+    /// The hash the archive is expected to have, if known
+    pub hash: Option<String>,
+    /// The size of the archive in bytes, only known once the archive is downloaded
+    pub size: Option<u32>,
+    /// Whether the archive is already downloaded (in the cache or by [crate::download::prepare_install])
+    pub cached: bool,
+    /// Whether the mod's dependencies and conflicts are known.
+    /// They're read from the mod's manifest, which we only have once its archive is downloaded or if the same version is installed.
+    pub dependencies_known: bool,
+    /// The version that's currently installed, if the mod is installed
+    pub installed_version: Option<String>,
+    /// Why this mod is in the plan
+    pub reason: PlanReason,
+}
+
+/// A problem found while resolving an [InstallPlan]
+#[typeshare]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "problemType", content = "payload")]
+pub enum ResolveProblem {
+    /// A mod isn't installed and isn't in the database
+    #[serde(rename_all = "camelCase")]
+    Missing {
+        /// The unique name of the missing mod
+        unique_name: String,
+        /// The mod that needs it, if it isn't one of the requested mods
+        required_by: Option<String>,
+    },
+    /// A prerelease was requested for a mod that doesn't have one, contains the unique name of the mod
+    NoPrerelease(String),
+    /// Some mods in the plan depend on each other in a cycle, contains the unique names in the cycle
+    Cycle(Vec<String>),
+    /// A mod in the plan conflicts with another mod that's enabled or in the plan
+    #[serde(rename_all = "camelCase")]
+    Conflict {
+        /// The unique name of the mod that declares the conflict
+        unique_name: String,
+        /// The unique name of the mod it conflicts with
+        conflicts_with: String,
+    },
+}
+
+impl ResolveProblem {
+    /// Whether this problem stops the plan from being installed.
+    /// Cycles and conflicts are only warnings, the mods can still be installed.
+    pub fn is_blocking(&self) -> bool {
+        matches!(self, Self::Missing { .. } | Self::NoPrerelease(_))
+    }
+}
+
+impl Display for ResolveProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing {
+                unique_name,
+                required_by: Some(required_by),
+            } => write!(
+                f,
+                "{unique_name} (required by {required_by}) was not found in the database"
+            ),
+            Self::Missing {
+                unique_name,
+                required_by: None,
+            } => {
+                write!(f, "{unique_name} was not found in the database")
+            }
+            Self::NoPrerelease(unique_name) => write!(f, "No prerelease for {unique_name} found"),
+            Self::Cycle(cycle) => write!(f, "Dependency cycle: {}", cycle.join(" -> ")),
+            Self::Conflict {
+                unique_name,
+                conflicts_with,
+            } => {
+                write!(f, "{unique_name} conflicts with {conflicts_with}")
+            }
+        }
+    }
+}
+
+/// An ordered plan of mods to install, dependencies are resolved from the mods' manifests.
+#[typeshare]
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPlan {
+    /// The mods to install, dependencies always come before the mods that need them
+    pub mods: Vec<PlannedMod>,
+    /// Problems found while resolving the plan
+    pub problems: Vec<ResolveProblem>,
+    /// The total size of the archives in the plan in bytes, not counting archives that aren't downloaded yet
+    pub total_size: u32,
+    /// The number of mods that still need to be downloaded, their sizes aren't known until they are
+    pub unknown_size_count: u32,
+}
+
+impl InstallPlan {
+    /// Whether the plan has no blocking problems and can be installed
+    pub fn is_ok(&self) -> bool {
+        !self.problems.iter().any(|p| p.is_blocking())
+    }
+
+    /// Make sure the plan can be installed.
+    ///
+    /// ## Errors
+    ///
+    /// If the plan has any blocking problems, see [ResolveProblem::is_blocking].
+    ///
+    pub fn ensure_ok(&self) -> Result<()> {
+        let blocking = self
+            .problems
+            .iter()
+            .filter(|p| p.is_blocking())
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        if !blocking.is_empty() {
+            bail!("Can't install: {}", blocking.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Get a planned mod by its unique name
+    pub fn get_mod(&self, unique_name: &str) -> Option<&PlannedMod> {
+        self.mods.iter().find(|m| m.unique_name == unique_name)
+    }
+
+    /// Whether every mod's dependencies are known, see [PlannedMod::dependencies_known]
+    pub fn is_complete(&self) -> bool {
+        self.mods.iter().all(|m| m.dependencies_known)
+    }
+
+    /// Get the unique names of every mod in the plan that's only being installed as a dependency
+    pub fn dependencies(&self) -> Vec<String> {
+        self.mods
+            .iter()
+            .filter(|m| matches!(m.reason, PlanReason::Dependency(_)))
+            .map(|m| m.unique_name.clone())
+            .collect()
+    }
+}
+
+struct Resolver<'a> {
+    config: &'a Config,
+    remote_db: &'a RemoteDatabase,
+    local_db: &'a LocalDatabase,
+    /// Archives that were already downloaded, by unique name
+    archives: &'a HashMap<String, PathBuf>,
+    prerelease: bool,
+    requested: HashSet<&'a str>,
+    /// Mods currently being visited, in order, used to find cycles
+    stack: Vec<String>,
+    visited: HashSet<String>,
+    /// The conflicts of each planned mod we have a manifest for
+    conflicts: HashMap<String, Vec<String>>,
+    plan: InstallPlan,
+}
+
+impl Resolver<'_> {
+    fn visit(&mut self, unique_name: &str, required_by: Option<&str>) {
+        if let Some(pos) = self.stack.iter().position(|m| m == unique_name) {
+            let mut cycle = self.stack[pos..].to_vec();
+            cycle.push(unique_name.to_string());
+            self.plan.problems.push(ResolveProblem::Cycle(cycle));
+            return;
+        }
+        if !self.visited.insert(unique_name.to_string()) {
+            return;
+        }
+
+        let is_requested = self.requested.contains(unique_name);
+        let installed = self.local_db.get_mod(unique_name);
+        if installed.is_some() && !is_requested {
+            // Already installed dependencies are left alone, just like a normal recursive install
+            return;
+        }
+
+        let Some(remote_mod) = self.remote_db.get_mod(unique_name) else {
+            self.plan.problems.push(ResolveProblem::Missing {
+                unique_name: unique_name.to_string(),
+                required_by: required_by.map(|r| r.to_string()),
+            });
+            return;
+        };
+
+        let (download_url, version, hash) = if self.prerelease && is_requested {
+            let Some(prerelease) = remote_mod.prerelease.as_ref() else {
+                self.plan
+                    .problems
+                    .push(ResolveProblem::NoPrerelease(unique_name.to_string()));
+                return;
+            };
+            (&prerelease.download_url, &prerelease.version, None)
+        } else {
+            (
+                &remote_mod.download_url,
+                &remote_mod.version,
+                remote_mod.hash.clone(),
+            )
+        };
+
+        // The database doesn't list dependencies, so they come from the manifest of the version we'd install
+        let archive = self.archives.get(unique_name).cloned().or_else(|| {
+            find_cached_archive(self.config, unique_name, version, hash.as_deref()).map(|a| a.path)
+        });
+        let manifest = match &archive {
+            Some(archive) => read_manifest_from_zip(archive).ok(),
+            None => installed
+                .filter(|m| &m.manifest.version == version)
+                .map(|m| m.manifest.clone()),
+        };
+
+        self.stack.push(unique_name.to_string());
+        for dep in manifest
+            .iter()
+            .flat_map(|m| m.dependencies.iter().flatten())
+        {
+            if dep != OWML_UNIQUE_NAME {
+                self.visit(dep, Some(unique_name));
+            }
+        }
+        self.stack.pop();
+
+        if let Some(conflicts) = manifest.as_ref().and_then(|m| m.conflicts.clone()) {
+            self.conflicts.insert(unique_name.to_string(), conflicts);
+        }
+        self.plan.mods.push(PlannedMod {
+            unique_name: unique_name.to_string(),
+            name: remote_mod.name.clone(),
+            version: version.clone(),
+            download_url: download_url.clone(),
+            hash,
+            size: archive.as_deref().and_then(get_archive_size),
+            cached: archive.is_some(),
+            dependencies_known: manifest.is_some(),
+            installed_version: installed.map(|m| m.manifest.version.clone()),
+            reason: match required_by {
+                Some(required_by) if !is_requested => {
+                    PlanReason::Dependency(required_by.to_string())
+                }
+                _ => PlanReason::Requested,
+            },
+        });
+    }
+
+    fn check_conflicts(&mut self) {
+        let planned = self
+            .plan
+            .mods
+            .iter()
+            .map(|m| m.unique_name.as_str())
+            .collect::<HashSet<_>>();
+        let mut conflicts: HashMap<&str, Vec<&String>> = HashMap::new();
+        for planned_mod in self.plan.mods.iter() {
+            conflicts
+                .entry(&planned_mod.unique_name)
+                .or_default()
+                .extend(
+                    self.conflicts
+                        .get(&planned_mod.unique_name)
+                        .into_iter()
+                        .flatten(),
+                );
+        }
+        for local_mod in self
+            .local_db
+            .active()
+            .filter(|m| !planned.contains(m.manifest.unique_name.as_str()))
+        {
+            conflicts
+                .entry(&local_mod.manifest.unique_name)
+                .or_default()
+                .extend(local_mod.manifest.conflicts.iter().flatten());
+        }
+
+        let mut found: Vec<(String, String)> = vec![];
+        for (unique_name, others) in conflicts.iter() {
+            for other in others.iter() {
+                let other_present = planned.contains(other.as_str())
+                    || self
+                        .local_db
+                        .active()
+                        .any(|m| &m.manifest.unique_name == *other);
+                let involves_plan =
+                    planned.contains(unique_name) || planned.contains(other.as_str());
+                let already_found = found.iter().any(|(a, b)| {
+                    (a == *other && b == unique_name) || (a == unique_name && b == *other)
+                });
+                if other_present && involves_plan && !already_found {
+                    found.push((unique_name.to_string(), other.to_string()));
+                }
+            }
+        }
+        found.sort();
+        self.plan
+            .problems
+            .extend(found.into_iter().map(|(unique_name, conflicts_with)| {
+                ResolveProblem::Conflict {
+                    unique_name,
+                    conflicts_with,
+                }
+            }));
+    }
+}
+
+fn get_archive_size(path: &Path) -> Option<u32> {
+    let size = path.metadata().ok()?.len();
+    Some(u32::try_from(size).unwrap_or(u32::MAX))
+}
+
+/// Resolve the given mods and all of their dependencies into an [InstallPlan] without downloading anything or changing any files.
+///
+/// The database doesn't list dependencies, so they're read from each mod's manifest.
+/// Manifests come from archives in the cache, or from the installed mod if the same version is installed.
+/// Mods we don't have a manifest for are planned on their own and marked with [PlannedMod::dependencies_known] set to `false`,
+/// use [crate::download::prepare_install] to download them and get a complete plan.
+/// Mods that are already installed are only included if they were requested.
+///
+/// ## Returns
+///
+/// The plan, check [InstallPlan::problems] or call [InstallPlan::ensure_ok] before installing it.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::db::{LocalDatabase, RemoteDatabase};
+/// use owmods_core::config::Config;
+/// use owmods_core::resolve::resolve_install;
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let remote_db = RemoteDatabase::fetch(&config.database_url).await.unwrap();
+///
+/// let plan = resolve_install(&["xen.NewHorizons".to_string()], &config, &remote_db, &local_db, false);
+/// for planned_mod in plan.mods.iter() {
+///     println!("{} {}", planned_mod.unique_name, planned_mod.version);
+/// }
+/// if !plan.is_complete() {
+///     println!("Some dependencies won't be known until the mods are downloaded");
+/// }
+/// plan.ensure_ok().unwrap();
+/// # });
+/// ```
+///
+pub fn resolve_install(
+    unique_names: &[String],
+    config: &Config,
+    remote_db: &RemoteDatabase,
+    local_db: &LocalDatabase,
+    prerelease: bool,
+) -> InstallPlan {
+    resolve_install_with_archives(
+        unique_names,
+        config,
+        remote_db,
+        local_db,
+        prerelease,
+        &HashMap::new(),
+    )
+}
+
+// Resolve a plan, reading manifests from the given downloaded archives (by unique name) first
+pub(crate) fn resolve_install_with_archives(
+    unique_names: &[String],
+    config: &Config,
+    remote_db: &RemoteDatabase,
+    local_db: &LocalDatabase,
+    prerelease: bool,
+    archives: &HashMap<String, PathBuf>,
+) -> InstallPlan {
+    let mut resolver = Resolver {
+        config,
+        remote_db,
+        local_db,
+        archives,
+        prerelease,
+        requested: unique_names.iter().map(|n| n.as_str()).collect(),
+        stack: vec![],
+        visited: HashSet::new(),
+        conflicts: HashMap::new(),
+        plan: InstallPlan::default(),
+    };
+    for unique_name in unique_names.iter() {
+        resolver.visit(unique_name, None);
+    }
+    resolver.check_conflicts();
+
+    resolver.plan.total_size = resolver
+        .plan
+        .mods
+        .iter()
+        .fold(0_u32, |acc, m| acc.saturating_add(m.size.unwrap_or(0)));
+    resolver.plan.unknown_size_count =
+        resolver.plan.mods.iter().filter(|m| !m.cached).count() as u32;
+    resolver.plan
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{fs::File, io::Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use crate::{
+        cache::add_to_cache,
+        mods::{local::LocalMod, remote::RemoteMod},
+        test_utils::TestContext,
+    };
+
+    use super::*;
+
+    fn test_name(num: u8) -> String {
+        format!("Example.TestMod{num}")
+    }
+
+    fn insert_remote(ctx: &mut TestContext, num: u8) {
+        let remote_mod = RemoteMod::get_test(num);
+        ctx.remote_db
+            .mods
+            .insert(remote_mod.unique_name.clone(), remote_mod);
+    }
+
+    // Insert a mod into the remote database and write an archive for it with the given dependencies and conflicts
+    fn insert_with_archive(
+        ctx: &mut TestContext,
+        archives: &mut HashMap<String, PathBuf>,
+        num: u8,
+        deps: &[u8],
+        conflicts: &[u8],
+    ) {
+        insert_remote(ctx, num);
+        let mut manifest = LocalMod::get_test(num).manifest;
+        manifest.version = "0.0.0".to_string();
+        manifest.dependencies = Some(deps.iter().map(|d| test_name(*d)).collect());
+        manifest.conflicts = Some(conflicts.iter().map(|c| test_name(*c)).collect());
+        let path = ctx.temp_dir.path().join(format!("{}.zip", test_name(num)));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file("manifest.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(serde_json::to_string(&manifest).unwrap().as_bytes())
+            .unwrap();
+        zip.finish().unwrap();
+        archives.insert(test_name(num), path);
+    }
+
+    fn resolve(
+        ctx: &TestContext,
+        archives: &HashMap<String, PathBuf>,
+        prerelease: bool,
+    ) -> InstallPlan {
+        resolve_install_with_archives(
+            &[test_name(1)],
+            &ctx.config,
+            &ctx.remote_db,
+            &ctx.local_db,
+            prerelease,
+            archives,
+        )
+    }
+
+    fn plan_names(plan: &InstallPlan) -> Vec<&str> {
+        plan.mods.iter().map(|m| m.unique_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_resolve_install_order() {
+        let mut ctx = TestContext::new();
+        let mut archives = HashMap::new();
+        insert_with_archive(&mut ctx, &mut archives, 1, &[2, 3], &[]);
+        insert_with_archive(&mut ctx, &mut archives, 2, &[3], &[]);
+        insert_with_archive(&mut ctx, &mut archives, 3, &[], &[]);
+        let plan = resolve(&ctx, &archives, false);
+        assert!(plan.is_ok());
+        assert!(plan.is_complete());
+        assert_eq!(
+            plan_names(&plan),
+            vec!["Example.TestMod3", "Example.TestMod2", "Example.TestMod1"]
+        );
+        assert_eq!(plan.mods[2].reason, PlanReason::Requested);
+        assert_eq!(
+            plan.mods[1].reason,
+            PlanReason::Dependency("Example.TestMod1".to_string())
+        );
+        let archive_sizes = archives
+            .values()
+            .map(|p| p.metadata().unwrap().len() as u32)
+            .sum::<u32>();
+        assert_eq!(plan.total_size, archive_sizes);
+        assert_eq!(plan.unknown_size_count, 0);
+        assert_eq!(
+            plan.dependencies(),
+            vec![
+                "Example.TestMod3".to_string(),
+                "Example.TestMod2".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_install_unknown_dependencies() {
+        let mut ctx = TestContext::new();
+        insert_remote(&mut ctx, 1);
+        let plan = resolve_install(
+            &[test_name(1)],
+            &ctx.config,
+            &ctx.remote_db,
+            &ctx.local_db,
+            false,
+        );
+        assert!(plan.is_ok());
+        assert!(!plan.is_complete());
+        assert_eq!(plan_names(&plan), vec!["Example.TestMod1"]);
+        assert!(!plan.mods[0].cached);
+        assert_eq!(plan.mods[0].size, None);
+        assert_eq!(plan.total_size, 0);
+        assert_eq!(plan.unknown_size_count, 1);
+    }
+
+    #[test]
+    fn test_resolve_install_from_cache() {
+        let mut ctx = TestContext::new();
+        let mut archives = HashMap::new();
+        insert_with_archive(&mut ctx, &mut archives, 1, &[2], &[]);
+        insert_remote(&mut ctx, 2);
+        let cached = add_to_cache(
+            &ctx.config,
+            &archives[&test_name(1)],
+            &test_name(1),
+            "0.0.0",
+            "abc",
+        )
+        .unwrap();
+        let plan = resolve(&ctx, &HashMap::new(), false);
+        assert_eq!(
+            plan_names(&plan),
+            vec!["Example.TestMod2", "Example.TestMod1"]
+        );
+        assert!(plan.mods[1].cached);
+        assert!(plan.mods[1].dependencies_known);
+        assert!(!plan.mods[0].dependencies_known);
+        // Planning doesn't check hashes, so the mismatched archive is left alone
+        assert!(cached.is_file());
+    }
+
+    #[test]
+    fn test_resolve_install_uses_installed_manifest() {
+        let mut ctx = TestContext::new();
+        insert_remote(&mut ctx, 1);
+        insert_remote(&mut ctx, 2);
+        let mut installed = LocalMod::get_test(1);
+        installed.manifest.version = "0.0.0".to_string();
+        installed.manifest.dependencies = Some(vec![test_name(2)]);
+        ctx.insert_test_mod(&installed);
+        let plan = resolve(&ctx, &HashMap::new(), false);
+        assert_eq!(
+            plan_names(&plan),
+            vec!["Example.TestMod2", "Example.TestMod1"]
+        );
+        assert!(plan.mods[1].dependencies_known);
+    }
+
+    #[test]
+    fn test_resolve_install_skips_installed_deps() {
+        let mut ctx = TestContext::new();
+        let mut archives = HashMap::new();
+        insert_with_archive(&mut ctx, &mut archives, 1, &[0], &[]);
+        insert_remote(&mut ctx, 0);
+        ctx.insert_test_mod(&LocalMod::get_test(0));
+        let plan = resolve(&ctx, &archives, false);
+        assert_eq!(plan_names(&plan), vec!["Example.TestMod1"]);
+    }
+
+    #[test]
+    fn test_resolve_install_missing() {
+        let mut ctx = TestContext::new();
+        let mut archives = HashMap::new();
+        insert_with_archive(&mut ctx, &mut archives, 1, &[2], &[]);
+        let plan = resolve(&ctx, &archives, false);
+        assert!(!plan.is_ok());
+        assert!(plan.ensure_ok().is_err());
+        assert_eq!(
+            plan.problems,
+            vec![ResolveProblem::Missing {
+                unique_name: "Example.TestMod2".to_string(),
+                required_by: Some("Example.TestMod1".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn test_resolve_install_no_prerelease() {
+        let mut ctx = TestContext::new();
+        insert_remote(&mut ctx, 1);
+        let plan = resolve(&ctx, &HashMap::new(), true);
+        assert_eq!(
+            plan.problems,
+            vec![ResolveProblem::NoPrerelease("Example.TestMod1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_resolve_install_cycle() {
+        let mut ctx = TestContext::new();
+        let mut archives = HashMap::new();
+        insert_with_archive(&mut ctx, &mut archives, 1, &[2], &[]);
+        insert_with_archive(&mut ctx, &mut archives, 2, &[1], &[]);
+        let plan = resolve(&ctx, &archives, false);
+        assert!(plan.is_ok());
+        assert_eq!(
+            plan_names(&plan),
+            vec!["Example.TestMod2", "Example.TestMod1"]
+        );
+        assert_eq!(
+            plan.problems,
+            vec![ResolveProblem::Cycle(vec![
+                "Example.TestMod1".to_string(),
+                "Example.TestMod2".to_string(),
+                "Example.TestMod1".to_string()
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_resolve_install_conflicts() {
+        let mut ctx = TestContext::new();
+        let mut archives = HashMap::new();
+        insert_with_archive(&mut ctx, &mut archives, 1, &[], &[2]);
+        let mut installed = LocalMod::get_test(0);
+        installed.manifest.conflicts = Some(vec!["Example.TestMod1".to_string()]);
+        ctx.insert_test_mod(&installed);
+        ctx.insert_test_mod(&LocalMod::get_test(2));
+        let plan = resolve(&ctx, &archives, false);
+        assert!(plan.is_ok());
+        assert!(plan.problems.contains(&ResolveProblem::Conflict {
+            unique_name: "Example.TestMod0".to_string(),
+            conflicts_with: "Example.TestMod1".to_string()
+        }));
+        assert!(plan.problems.contains(&ResolveProblem::Conflict {
+            unique_name: "Example.TestMod1".to_string(),
+            conflicts_with: "Example.TestMod2".to_string()
+        }));
+    }
+}
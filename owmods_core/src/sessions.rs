@@ -0,0 +1,455 @@
+use std::{
+    fs::{create_dir_all, read_dir, remove_file, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    constants::LOG_SESSIONS_FOLDER_NAME,
+    socket::{LogServerSender, SocketMessage},
+};
+
+/// A message from the game along with when it was received
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedMessage {
+    /// When the message was received, in milliseconds since the unix epoch
+    pub timestamp: u64,
+    /// The message itself
+    pub message: SocketMessage,
+}
+
+/// A recorded log session, one is made each time the game is launched with a log server
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSession {
+    /// The ID of the session, this is when it started in milliseconds since the unix epoch
+    pub id: String,
+    /// Where the session is stored
+    pub path: PathBuf,
+    /// When the session started, in milliseconds since the unix epoch
+    pub started: u64,
+    /// The size of the session's file in bytes
+    pub size: u64,
+}
+
+impl LogSession {
+    fn read(path: PathBuf) -> Option<Self> {
+        if path.extension()? != "jsonl" {
+            return None;
+        }
+        let id = path.file_stem()?.to_str()?.to_string();
+        let started = id.parse::<u64>().ok()?;
+        let size = path.metadata().ok()?.len();
+        Some(Self {
+            id,
+            path,
+            started,
+            size,
+        })
+    }
+
+    /// Get when this session started
+    pub fn started_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.started)
+    }
+
+    /// Read every message recorded in this session, see [read_session_file].
+    ///
+    /// ## Errors
+    ///
+    /// If we can't open the session's file.
+    ///
+    pub fn read_messages(&self) -> Result<Vec<RecordedMessage>> {
+        read_session_file(&self.path)
+            .with_context(|| format!("Couldn't read log session {}", self.id))
+    }
+}
+
+/// Read every message recorded in a session file, this doesn't need to be in the sessions folder
+/// so it works with sessions shared by other people.
+/// Lines that can't be parsed (like one cut off by the game crashing) are skipped with a warning.
+///
+/// ## Errors
+///
+/// If we can't open or read the file.
+///
+pub fn read_session_file(path: &Path) -> Result<Vec<RecordedMessage>> {
+    let file = File::open(path)?;
+    let mut messages: Vec<RecordedMessage> = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(message) => messages.push(message),
+            Err(why) => warn!("Skipping line {} of {}: {why}", i + 1, path.display()),
+        }
+    }
+    Ok(messages)
+}
+
+/// How fast [replay_session] sends messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayPacing {
+    /// Send every message right away
+    Instant,
+    /// Wait between messages for as long as the game did when the session was recorded
+    RealTime,
+}
+
+/// Replay recorded messages into a channel, the same kind of channel [crate::socket::LogServer::listen] sends to,
+/// so anything that handles logs from the game can handle a replay the same way.
+/// Unlike the log server, this waits for room in the channel instead of dropping messages, so every message arrives.
+///
+/// ## Errors
+///
+/// If the receiving end of the channel is closed before every message is sent.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::sessions::{get_session, replay_session, ReplayPacing};
+/// use tokio::sync::mpsc;
+///
+/// # tokio_test::block_on(async {
+/// let config = Config::get(None).unwrap();
+/// let messages = get_session(&config, "latest").unwrap().read_messages().unwrap();
+/// let (tx, mut rx) = mpsc::channel(32);
+///
+/// let replay = replay_session(messages, tx, ReplayPacing::RealTime);
+/// let print = async {
+///     while let Some(message) = rx.recv().await {
+///         println!("{}", message.message);
+///     }
+///     Ok(())
+/// };
+/// tokio::try_join!(replay, print).unwrap();
+/// # });
+/// ```
+///
+pub async fn replay_session(
+    messages: Vec<RecordedMessage>,
+    tx: LogServerSender,
+    pacing: ReplayPacing,
+) -> Result<()> {
+    let mut last_timestamp: Option<u64> = None;
+    for recorded in messages {
+        if pacing == ReplayPacing::RealTime {
+            if let Some(last) = last_timestamp {
+                let wait = recorded.timestamp.saturating_sub(last);
+                if wait > 0 {
+                    tokio::time::sleep(Duration::from_millis(wait)).await;
+                }
+            }
+            last_timestamp = Some(recorded.timestamp);
+        }
+        tx.send(recorded.message)
+            .await
+            .map_err(|_| anyhow!("Log receiver closed before the replay finished"))?;
+    }
+    Ok(())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Get the folder log sessions are stored in
+pub fn get_sessions_dir(config: &Config) -> PathBuf {
+    config.data_dir().join(LOG_SESSIONS_FOLDER_NAME)
+}
+
+/// List all recorded log sessions, newest first.
+///
+/// ## Errors
+///
+/// If we can't read the sessions folder.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::sessions::list_sessions;
+///
+/// let config = Config::get(None).unwrap();
+/// for session in list_sessions(&config).unwrap() {
+///     println!("{} ({} bytes)", session.id, session.size);
+/// }
+/// ```
+///
+pub fn list_sessions(config: &Config) -> Result<Vec<LogSession>> {
+    let dir = get_sessions_dir(config);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut sessions = read_dir(&dir)?
+        .filter_map(|entry| LogSession::read(entry.ok()?.path()))
+        .collect::<Vec<_>>();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.started));
+    Ok(sessions)
+}
+
+/// Get a recorded log session by its ID, or the newest session if `id` is `"latest"`.
+///
+/// ## Errors
+///
+/// If we can't read the sessions folder or there's no session with that ID.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::sessions::get_session;
+///
+/// let config = Config::get(None).unwrap();
+/// let session = get_session(&config, "latest").unwrap();
+/// for recorded in session.read_messages().unwrap() {
+///     println!("{}", recorded.message.message);
+/// }
+/// ```
+///
+pub fn get_session(config: &Config, id: &str) -> Result<LogSession> {
+    let sessions = list_sessions(config)?;
+    let session = if id == "latest" {
+        sessions.into_iter().next()
+    } else {
+        sessions.into_iter().find(|s| s.id == id)
+    };
+    session.ok_or_else(|| anyhow!("Log session \"{id}\" not found"))
+}
+
+/// Delete old log sessions so there are at most `keep` left,
+/// also delete sessions older than the config's `max_log_session_age_days` (unless it's 0).
+fn prune_sessions(config: &Config, keep: usize) -> Result<Vec<LogSession>> {
+    let max_age = Duration::from_secs(config.max_log_session_age_days as u64 * 60 * 60 * 24);
+    let mut pruned: Vec<LogSession> = vec![];
+    for (i, session) in list_sessions(config)?.into_iter().enumerate() {
+        let too_old = config.max_log_session_age_days != 0
+            && session.started_time().elapsed().unwrap_or_default() > max_age;
+        if i >= keep || too_old {
+            debug!("Removing old log session {}", session.id);
+            remove_file(&session.path)
+                .with_context(|| format!("Couldn't remove log session {}", session.id))?;
+            pruned.push(session);
+        }
+    }
+    Ok(pruned)
+}
+
+/// Records messages from the game to a new JSONL file in the sessions folder, one message per line.
+pub struct SessionRecorder {
+    /// The ID of the session being recorded
+    pub id: String,
+    /// The file the session is being recorded to
+    pub path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl SessionRecorder {
+    /// Start recording a new session. Old sessions are removed first so that there are at most
+    /// `max_log_sessions` sessions (including this one), and none older than `max_log_session_age_days`.
+    ///
+    /// ## Returns
+    ///
+    /// The new recorder, or `None` if `max_log_sessions` is 0 (recording is disabled).
+    ///
+    /// ## Errors
+    ///
+    /// If we can't remove old sessions or create the file for the new one.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    /// use owmods_core::sessions::SessionRecorder;
+    /// use owmods_core::socket::{SocketMessage, SocketMessageType};
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// if let Some(mut recorder) = SessionRecorder::start(&config).unwrap() {
+    ///     let message = SocketMessage::make_internal("Hello!", SocketMessageType::Info);
+    ///     recorder.record(&message).unwrap();
+    /// }
+    /// ```
+    ///
+    pub fn start(config: &Config) -> Result<Option<Self>> {
+        if config.max_log_sessions == 0 {
+            return Ok(None);
+        }
+        prune_sessions(config, config.max_log_sessions as usize - 1)?;
+        let dir = get_sessions_dir(config);
+        create_dir_all(&dir)?;
+        let mut started = now_millis();
+        while dir.join(format!("{started}.jsonl")).exists() {
+            started += 1;
+        }
+        let id = started.to_string();
+        let path = dir.join(format!("{id}.jsonl"));
+        let file = File::create(&path)
+            .with_context(|| format!("Couldn't create log session at {}", path.display()))?;
+        debug!("Recording log session to {}", path.display());
+        Ok(Some(Self {
+            id,
+            path,
+            writer: BufWriter::new(file),
+        }))
+    }
+
+    /// Record a message with the current time.
+    /// Each message is flushed to disk right away so nothing is lost if the manager is closed mid-session.
+    ///
+    /// ## Errors
+    ///
+    /// If we can't write to the session's file.
+    ///
+    pub fn record(&mut self, message: &SocketMessage) -> Result<()> {
+        let recorded = RecordedMessage {
+            timestamp: now_millis(),
+            message: message.clone(),
+        };
+        serde_json::to_writer(&mut self.writer, &recorded)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{fs::write, time::Instant};
+
+    use tokio::sync::mpsc;
+
+    use crate::{socket::SocketMessageType, test_utils::TestContext};
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_read_session() {
+        let ctx = TestContext::new();
+        let mut recorder = SessionRecorder::start(&ctx.config).unwrap().unwrap();
+        let first = SocketMessage::make_internal("Hello", SocketMessageType::Info);
+        let second = SocketMessage::make_internal("Oh no", SocketMessageType::Error);
+        recorder.record(&first).unwrap();
+        recorder.record(&second).unwrap();
+        let session = get_session(&ctx.config, "latest").unwrap();
+        assert_eq!(session.id, recorder.id);
+        let messages = session
+            .read_messages()
+            .unwrap()
+            .into_iter()
+            .map(|m| m.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec![first, second]);
+        assert!(get_session(&ctx.config, "0").is_err());
+    }
+
+    #[test]
+    fn test_read_session_skips_bad_lines() {
+        let ctx = TestContext::new();
+        let recorder = SessionRecorder::start(&ctx.config).unwrap().unwrap();
+        let good = serde_json::to_string(&RecordedMessage {
+            timestamp: 1,
+            message: SocketMessage::make_internal("Hello", SocketMessageType::Info),
+        })
+        .unwrap();
+        write(&recorder.path, format!("{good}\n{{\"timest")).unwrap();
+        let session = get_session(&ctx.config, &recorder.id).unwrap();
+        assert_eq!(session.read_messages().unwrap().len(), 1);
+    }
+
+    fn make_recorded(timestamps: &[u64]) -> Vec<RecordedMessage> {
+        timestamps
+            .iter()
+            .map(|t| RecordedMessage {
+                timestamp: *t,
+                message: SocketMessage::make_internal(&t.to_string(), SocketMessageType::Info),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_session() {
+        tokio_test::block_on(async {
+            let recorded = make_recorded(&[0, 10, 10, 20]);
+            let (tx, mut rx) = mpsc::channel(1);
+            let replay = replay_session(recorded.clone(), tx, ReplayPacing::Instant);
+            let receive = async {
+                let mut received: Vec<SocketMessage> = vec![];
+                while let Some(message) = rx.recv().await {
+                    received.push(message);
+                }
+                Ok(received)
+            };
+            let (_, received) = tokio::try_join!(replay, receive).unwrap();
+            let expected = recorded.into_iter().map(|r| r.message).collect::<Vec<_>>();
+            assert_eq!(received, expected);
+        });
+    }
+
+    #[test]
+    fn test_replay_session_real_time() {
+        tokio_test::block_on(async {
+            let (tx, mut rx) = mpsc::channel(32);
+            let start = Instant::now();
+            replay_session(
+                make_recorded(&[1000, 1100, 1150]),
+                tx,
+                ReplayPacing::RealTime,
+            )
+            .await
+            .unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(150));
+            let mut count = 0;
+            while rx.recv().await.is_some() {
+                count += 1;
+            }
+            assert_eq!(count, 3);
+        });
+    }
+
+    #[test]
+    fn test_replay_session_receiver_closed() {
+        tokio_test::block_on(async {
+            let (tx, rx) = mpsc::channel(32);
+            drop(rx);
+            let res = replay_session(make_recorded(&[0]), tx, ReplayPacing::Instant).await;
+            assert!(res.is_err());
+        });
+    }
+
+    #[test]
+    fn test_session_retention() {
+        let mut ctx = TestContext::new();
+        ctx.config.max_log_sessions = 2;
+        let dir = get_sessions_dir(&ctx.config);
+        create_dir_all(&dir).unwrap();
+        // One session that's too old and two recent ones
+        write(dir.join("1000.jsonl"), "").unwrap();
+        let recent = now_millis() - 1000;
+        write(dir.join(format!("{recent}.jsonl")), "").unwrap();
+        write(dir.join(format!("{}.jsonl", recent - 1)), "").unwrap();
+        let recorder = SessionRecorder::start(&ctx.config).unwrap().unwrap();
+        let ids = list_sessions(&ctx.config)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![recorder.id, recent.to_string()]);
+
+        ctx.config.max_log_sessions = 0;
+        assert!(SessionRecorder::start(&ctx.config).unwrap().is_none());
+    }
+}
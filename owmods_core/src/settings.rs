@@ -0,0 +1,499 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    file::deserialize_from_json,
+    mods::local::{LocalMod, ModStubConfig},
+    toggle::{read_config, write_config},
+};
+
+/// The kind of control a mod setting uses, matching the `type` OWML reads from `default-config.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SettingKind {
+    /// An on/off setting, the value is a bool
+    Toggle,
+    /// A number between `min` and `max`
+    Slider,
+    /// One of a set of `options`
+    Selector,
+    /// Free-form text
+    Text,
+    /// Any number
+    Number,
+    /// A header in the settings menu, it doesn't hold a value
+    Separator,
+}
+
+impl SettingKind {
+    fn from_type(ty: &str) -> Option<Self> {
+        match ty.to_ascii_lowercase().as_str() {
+            "toggle" => Some(Self::Toggle),
+            "slider" => Some(Self::Slider),
+            "selector" => Some(Self::Selector),
+            "text" | "input" => Some(Self::Text),
+            "number" => Some(Self::Number),
+            "separator" => Some(Self::Separator),
+            _ => None,
+        }
+    }
+
+    fn infer(value: &Value) -> Self {
+        match value {
+            Value::Bool(_) => Self::Toggle,
+            Value::Number(_) => Self::Number,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// A single setting of a mod, with the user's value overlaid on the default from `default-config.json`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModSetting {
+    /// The key of the setting in the config
+    pub key: String,
+    /// The kind of setting, either set explicitly by the mod or inferred from the default value
+    pub kind: SettingKind,
+    /// The title the mod wants shown instead of the key
+    pub title: Option<String>,
+    /// The tooltip the mod wants shown for the setting
+    pub tooltip: Option<String>,
+    /// The value of the setting, this is the default if the user hasn't changed it
+    pub value: Value,
+    /// The default value from `default-config.json`
+    pub default_value: Value,
+    /// The lowest value a slider allows
+    pub min: Option<f64>,
+    /// The highest value a slider allows
+    pub max: Option<f64>,
+    /// The values a selector allows
+    pub options: Option<Vec<String>>,
+}
+
+/// Get the value out of a setting entry, OWML allows both plain values and objects with a `value` key
+pub(crate) fn unwrap_value(entry: &Value) -> &Value {
+    match entry {
+        Value::Object(obj) => obj.get("value").unwrap_or(&Value::Null),
+        _ => entry,
+    }
+}
+
+impl ModSetting {
+    fn from_default(key: &str, entry: &Value) -> Self {
+        let default_value = unwrap_value(entry).clone();
+        let obj = entry.as_object();
+        let get_str = |k: &str| {
+            obj.and_then(|o| o.get(k))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        };
+        let get_num = |k: &str| obj.and_then(|o| o.get(k)).and_then(|v| v.as_f64());
+        let options = obj.and_then(|o| o.get("options")).and_then(|v| {
+            v.as_array().map(|a| {
+                a.iter()
+                    .map(|o| o.as_str().map(|s| s.to_string()).unwrap_or(o.to_string()))
+                    .collect::<Vec<_>>()
+            })
+        });
+        let (min, max) = (get_num("min"), get_num("max"));
+        let kind = get_str("type")
+            .and_then(|t| SettingKind::from_type(&t))
+            .unwrap_or_else(|| {
+                if options.is_some() {
+                    SettingKind::Selector
+                } else if min.is_some() && max.is_some() {
+                    SettingKind::Slider
+                } else {
+                    SettingKind::infer(&default_value)
+                }
+            });
+        Self {
+            key: key.to_string(),
+            kind,
+            title: get_str("title"),
+            tooltip: get_str("tooltip"),
+            value: default_value.clone(),
+            default_value,
+            min,
+            max,
+            options,
+        }
+    }
+
+    /// Whether the setting is at its default value
+    pub fn is_default(&self) -> bool {
+        self.value == self.default_value
+    }
+
+    /// Check that a value can be used for this setting.
+    ///
+    /// ## Errors
+    ///
+    /// If the value is the wrong type for the setting, a slider value is out of range,
+    /// a selector value isn't one of the options, or the setting is a separator.
+    ///
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        match self.kind {
+            SettingKind::Toggle if !value.is_boolean() => {
+                Err(anyhow!("{} must be true or false", self.key))
+            }
+            SettingKind::Slider | SettingKind::Number => {
+                let num = value
+                    .as_f64()
+                    .ok_or_else(|| anyhow!("{} must be a number", self.key))?;
+                let below = self.min.is_some_and(|min| num < min);
+                let above = self.max.is_some_and(|max| num > max);
+                if self.kind == SettingKind::Slider && (below || above) {
+                    Err(anyhow!(
+                        "{} must be between {} and {}",
+                        self.key,
+                        self.min.unwrap_or(f64::MIN),
+                        self.max.unwrap_or(f64::MAX)
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            SettingKind::Selector => {
+                let options = self.options.as_deref().unwrap_or_default();
+                match value.as_str() {
+                    Some(s) if options.iter().any(|o| o == s) => Ok(()),
+                    _ => Err(anyhow!(
+                        "{} must be one of: {}",
+                        self.key,
+                        options.join(", ")
+                    )),
+                }
+            }
+            SettingKind::Text if !value.is_string() => Err(anyhow!("{} must be text", self.key)),
+            SettingKind::Separator => Err(anyhow!("{} is a separator and can't be set", self.key)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse a value for this setting from text (like a command line argument), and validate it.
+    ///
+    /// ## Errors
+    ///
+    /// If the text can't be parsed as the setting's type or the value isn't valid, see [ModSetting::validate].
+    ///
+    pub fn parse_value(&self, raw: &str) -> Result<Value> {
+        let raw = raw.trim();
+        let value = match self.kind {
+            SettingKind::Toggle => match raw.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Value::Bool(true),
+                "false" | "no" | "off" | "0" => Value::Bool(false),
+                _ => return Err(anyhow!("{} must be true or false", self.key)),
+            },
+            SettingKind::Slider | SettingKind::Number => {
+                if let Ok(int) = raw.parse::<i64>() {
+                    Value::from(int)
+                } else {
+                    let num = raw
+                        .parse::<f64>()
+                        .with_context(|| format!("{} must be a number", self.key))?;
+                    serde_json::Number::from_f64(num)
+                        .map(Value::Number)
+                        .ok_or_else(|| anyhow!("{} must be a finite number", self.key))?
+                }
+            }
+            _ => Value::String(raw.to_string()),
+        };
+        self.validate(&value)?;
+        Ok(value)
+    }
+}
+
+fn get_config_path(local_mod: &LocalMod) -> PathBuf {
+    PathBuf::from(&local_mod.mod_path).join("config.json")
+}
+
+fn read_default_settings(mod_path: &Path) -> Result<Map<String, Value>> {
+    let path = mod_path.join("default-config.json");
+    if !path.is_file() {
+        return Ok(Map::new());
+    }
+    let default_config: Value = deserialize_from_json(&path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    Ok(default_config
+        .get("settings")
+        .and_then(|s| s.as_object())
+        .cloned()
+        .unwrap_or_default())
+}
+
+pub(crate) fn read_user_settings(local_mod: &LocalMod) -> Result<Map<String, Value>> {
+    let config_path = get_config_path(local_mod);
+    if config_path.is_file() {
+        Ok(read_config(&config_path)?.settings.unwrap_or_default())
+    } else {
+        Ok(Map::new())
+    }
+}
+
+pub(crate) fn write_user_settings(
+    local_mod: &LocalMod,
+    settings: Map<String, Value>,
+) -> Result<()> {
+    let config_path = get_config_path(local_mod);
+    let mut config = if config_path.is_file() {
+        read_config(&config_path)?
+    } else {
+        // Changing a setting shouldn't enable or disable the mod
+        ModStubConfig {
+            enabled: local_mod.enabled,
+            settings: None,
+        }
+    };
+    config.settings = Some(settings);
+    write_config(&config, &config_path)
+}
+
+/// Get every setting of a mod, the defaults come from the mod's `default-config.json`
+/// and are overlaid with the values in its `config.json`.
+/// Settings in `config.json` the mod doesn't declare defaults for are left out.
+///
+/// ## Returns
+///
+/// The settings sorted by key, this is empty if the mod has no `default-config.json`.
+///
+/// ## Errors
+///
+/// If we can't read `default-config.json` or `config.json`.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::settings::get_mod_settings;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let local_mod = local_db.get_mod("Bwc9876.TimeSaver").unwrap();
+///
+/// for setting in get_mod_settings(local_mod).unwrap() {
+///     println!("{}: {}", setting.key, setting.value);
+/// }
+/// ```
+///
+pub fn get_mod_settings(local_mod: &LocalMod) -> Result<Vec<ModSetting>> {
+    let defaults = read_default_settings(Path::new(&local_mod.mod_path))?;
+    let user = read_user_settings(local_mod)?;
+    Ok(defaults
+        .iter()
+        .map(|(key, entry)| {
+            let mut setting = ModSetting::from_default(key, entry);
+            if let Some(user_entry) = user.get(key) {
+                setting.value = unwrap_value(user_entry).clone();
+            }
+            setting
+        })
+        .collect())
+}
+
+/// Get a single setting of a mod, see [get_mod_settings].
+///
+/// ## Errors
+///
+/// If we can't read the mod's configs or the mod has no setting with the given key.
+///
+pub fn get_mod_setting(local_mod: &LocalMod, key: &str) -> Result<ModSetting> {
+    get_mod_settings(local_mod)?
+        .into_iter()
+        .find(|s| s.key == key)
+        .ok_or_else(|| {
+            anyhow!(
+                "{} has no setting named \"{key}\"",
+                local_mod.manifest.unique_name
+            )
+        })
+}
+
+/// Change a setting of a mod, the value is validated against the setting's type first.
+/// If `config.json` stores the setting as an object its `value` is replaced, otherwise the plain value is written.
+///
+/// ## Errors
+///
+/// If the setting doesn't exist, the value isn't valid for it (see [ModSetting::validate]),
+/// or we can't read or save the mod's config.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::settings::set_mod_setting;
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let local_mod = local_db.get_mod("Bwc9876.TimeSaver").unwrap();
+///
+/// set_mod_setting(local_mod, "skipSplash", serde_json::Value::Bool(true)).unwrap();
+/// ```
+///
+pub fn set_mod_setting(local_mod: &LocalMod, key: &str, value: Value) -> Result<()> {
+    let setting = get_mod_setting(local_mod, key)?;
+    setting.validate(&value)?;
+    let mut user = read_user_settings(local_mod)?;
+    match user.get_mut(key) {
+        Some(Value::Object(obj)) => {
+            obj.insert("value".to_string(), value);
+        }
+        _ => {
+            user.insert(key.to_string(), value);
+        }
+    }
+    write_user_settings(local_mod, user)
+}
+
+/// Reset a setting of a mod to its default by removing it from `config.json`, OWML will use the default instead.
+/// If `key` is `None` every setting is reset.
+///
+/// ## Errors
+///
+/// If `key` is given and the mod has no setting with that key, or we can't read or save the mod's config.
+///
+pub fn reset_mod_setting(local_mod: &LocalMod, key: Option<&str>) -> Result<()> {
+    let user = if let Some(key) = key {
+        get_mod_setting(local_mod, key)?;
+        let mut user = read_user_settings(local_mod)?;
+        user.remove(key);
+        user
+    } else {
+        Map::new()
+    };
+    write_user_settings(local_mod, user)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs::write;
+
+    use serde_json::json;
+
+    use crate::test_utils::TestContext;
+
+    use super::*;
+
+    fn setup() -> (TestContext, LocalMod) {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        let default_config = json!({
+            "enabled": true,
+            "settings": {
+                "skipSplash": true,
+                "speed": { "type": "slider", "value": 1.5, "min": 0, "max": 2, "title": "Speed" },
+                "mode": { "type": "selector", "value": "Fast", "options": ["Fast", "Slow"] },
+                "name": "Hatchling",
+                "count": 3
+            }
+        });
+        write(
+            Path::new(&local_mod.mod_path).join("default-config.json"),
+            default_config.to_string(),
+        )
+        .unwrap();
+        (ctx, local_mod)
+    }
+
+    #[test]
+    fn test_get_mod_settings() {
+        let (_ctx, local_mod) = setup();
+        let settings = get_mod_settings(&local_mod).unwrap();
+        let kinds = settings
+            .iter()
+            .map(|s| (s.key.as_str(), s.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("count", SettingKind::Number),
+                ("mode", SettingKind::Selector),
+                ("name", SettingKind::Text),
+                ("skipSplash", SettingKind::Toggle),
+                ("speed", SettingKind::Slider),
+            ]
+        );
+        let speed = get_mod_setting(&local_mod, "speed").unwrap();
+        assert_eq!(speed.title.as_deref(), Some("Speed"));
+        assert_eq!(speed.max, Some(2.0));
+        assert!(speed.is_default());
+        assert!(get_mod_setting(&local_mod, "nope").is_err());
+    }
+
+    #[test]
+    fn test_set_and_reset_mod_setting() {
+        let (_ctx, local_mod) = setup();
+        set_mod_setting(&local_mod, "skipSplash", json!(false)).unwrap();
+        set_mod_setting(&local_mod, "speed", json!(0.5)).unwrap();
+        assert!(set_mod_setting(&local_mod, "speed", json!(3)).is_err());
+        assert!(set_mod_setting(&local_mod, "mode", json!("Medium")).is_err());
+        assert!(set_mod_setting(&local_mod, "skipSplash", json!("no")).is_err());
+        let speed = get_mod_setting(&local_mod, "speed").unwrap();
+        assert_eq!(speed.value, json!(0.5));
+        assert!(!speed.is_default());
+        assert!(read_config(&get_config_path(&local_mod)).unwrap().enabled);
+
+        reset_mod_setting(&local_mod, Some("speed")).unwrap();
+        assert!(get_mod_setting(&local_mod, "speed").unwrap().is_default());
+        assert_eq!(
+            get_mod_setting(&local_mod, "skipSplash").unwrap().value,
+            json!(false)
+        );
+        reset_mod_setting(&local_mod, None).unwrap();
+        assert!(get_mod_settings(&local_mod)
+            .unwrap()
+            .iter()
+            .all(|s| s.is_default()));
+    }
+
+    #[test]
+    fn test_set_mod_setting_without_config() {
+        let (_ctx, mut local_mod) = setup();
+        local_mod.enabled = false;
+        std::fs::remove_file(get_config_path(&local_mod)).unwrap();
+        set_mod_setting(&local_mod, "count", json!(5)).unwrap();
+        let config = read_config(&get_config_path(&local_mod)).unwrap();
+        assert!(!config.enabled);
+        assert_eq!(config.settings.unwrap()["count"], json!(5));
+    }
+
+    #[test]
+    fn test_set_mod_setting_keeps_object_entries() {
+        let (_ctx, local_mod) = setup();
+        write(
+            get_config_path(&local_mod),
+            json!({ "enabled": true, "settings": { "speed": { "type": "slider", "value": 1 } } })
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            get_mod_setting(&local_mod, "speed").unwrap().value,
+            json!(1)
+        );
+        set_mod_setting(&local_mod, "speed", json!(2)).unwrap();
+        let user = read_user_settings(&local_mod).unwrap();
+        assert_eq!(user["speed"]["value"], json!(2));
+        assert_eq!(user["speed"]["type"], json!("slider"));
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let (_ctx, local_mod) = setup();
+        let toggle = get_mod_setting(&local_mod, "skipSplash").unwrap();
+        assert_eq!(toggle.parse_value("off").unwrap(), json!(false));
+        assert!(toggle.parse_value("maybe").is_err());
+        let speed = get_mod_setting(&local_mod, "speed").unwrap();
+        assert_eq!(speed.parse_value("1").unwrap(), json!(1));
+        assert_eq!(speed.parse_value("0.25").unwrap(), json!(0.25));
+        assert!(speed.parse_value("fast").is_err());
+        let mode = get_mod_setting(&local_mod, "mode").unwrap();
+        assert_eq!(mode.parse_value("Slow").unwrap(), json!("Slow"));
+    }
+}
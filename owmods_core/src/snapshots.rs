@@ -0,0 +1,406 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{read_dir, remove_file},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    config::Config,
+    constants::SNAPSHOTS_FOLDER_NAME,
+    db::LocalDatabase,
+    file::{deserialize_from_json, serialize_to_json, validate_file_name},
+    settings::{read_user_settings, unwrap_value, write_user_settings},
+};
+
+/// A named copy of the settings in one or more mods' `config.json`, stored outside the Mods folder
+/// so it survives reinstalls.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsSnapshot {
+    /// The name of the snapshot, also used as its file name
+    pub name: String,
+    /// When the snapshot was taken, in milliseconds since the Unix epoch
+    pub created: u64,
+    /// The settings of each mod in the snapshot, keyed by unique name
+    pub mods: BTreeMap<String, Map<String, Value>>,
+}
+
+/// A setting whose value in a snapshot differs from the mod's current `config.json`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingDiff {
+    /// The unique name of the mod the setting belongs to
+    pub unique_name: String,
+    /// The key of the setting
+    pub key: String,
+    /// The value in the snapshot, `None` if the snapshot doesn't set it
+    pub snapshot: Option<Value>,
+    /// The value in the mod's config, `None` if the config doesn't set it (or the mod isn't installed)
+    pub current: Option<Value>,
+}
+
+fn get_snapshots_dir(config: &Config) -> PathBuf {
+    config.data_dir().join(SNAPSHOTS_FOLDER_NAME)
+}
+
+fn get_snapshot_path(name: &str, config: &Config) -> Result<PathBuf> {
+    let name = validate_file_name(name, "snapshot")?;
+    Ok(get_snapshots_dir(config).join(format!("{name}.json")))
+}
+
+impl SettingsSnapshot {
+    /// Take a snapshot of the settings of the given mods, or every installed mod if `unique_names` is empty.
+    /// Mods with no settings in their `config.json` are recorded with no settings, so restoring the snapshot resets them.
+    ///
+    /// ## Errors
+    ///
+    /// If one of the given mods isn't installed or we can't read a mod's config.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// use owmods_core::config::Config;
+    /// use owmods_core::db::LocalDatabase;
+    /// use owmods_core::snapshots::SettingsSnapshot;
+    ///
+    /// let config = Config::get(None).unwrap();
+    /// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+    /// let snapshot = SettingsSnapshot::take("Tuned", &local_db, &[]).unwrap();
+    /// snapshot.save(&config).unwrap();
+    /// ```
+    ///
+    pub fn take(name: &str, local_db: &LocalDatabase, unique_names: &[String]) -> Result<Self> {
+        let local_mods = if unique_names.is_empty() {
+            local_db.valid().collect::<Vec<_>>()
+        } else {
+            unique_names
+                .iter()
+                .map(|unique_name| {
+                    local_db
+                        .get_mod(unique_name)
+                        .with_context(|| format!("Mod {unique_name} not found in local database."))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        let mut mods: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+        for local_mod in local_mods {
+            let unique_name = &local_mod.manifest.unique_name;
+            let settings = read_user_settings(local_mod)
+                .with_context(|| format!("Couldn't read settings of {unique_name}"))?;
+            mods.insert(unique_name.clone(), settings);
+        }
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Ok(Self {
+            name: name.trim().to_string(),
+            created,
+            mods,
+        })
+    }
+
+    /// Save this snapshot, overwriting any snapshot with the same name.
+    ///
+    /// ## Errors
+    ///
+    /// If the snapshot's name isn't a valid file name (empty, ending with a dot, or containing a path separator or a character Windows doesn't allow) or we can't write the file.
+    ///
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let path = get_snapshot_path(&self.name, config)?;
+        debug!("Saving snapshot {} to {}", self.name, path.display());
+        serialize_to_json(self, &path, true)
+    }
+
+    /// Load a saved snapshot by name.
+    ///
+    /// ## Errors
+    ///
+    /// If no snapshot with that name exists or we can't read it.
+    ///
+    pub fn load(name: &str, config: &Config) -> Result<Self> {
+        let path = get_snapshot_path(name, config)?;
+        if !path.is_file() {
+            return Err(anyhow!("Snapshot \"{name}\" not found"));
+        }
+        deserialize_from_json(&path).with_context(|| format!("Couldn't read snapshot \"{name}\""))
+    }
+
+    /// Write this snapshot to a file so it can be shared, see [import_snapshot].
+    ///
+    /// ## Errors
+    ///
+    /// If we can't write the file.
+    ///
+    pub fn export(&self, path: &Path) -> Result<()> {
+        serialize_to_json(self, path, true)
+    }
+
+    /// Compare this snapshot to the current settings of the mods in it.
+    ///
+    /// ## Returns
+    ///
+    /// Every setting that [restore_snapshot] would change, sorted by mod and then key.
+    /// Settings that are stored as objects (`{ "value": ... }`) are compared by their value.
+    ///
+    /// ## Errors
+    ///
+    /// If we can't read the config of a mod in the snapshot.
+    ///
+    pub fn diff(&self, local_db: &LocalDatabase) -> Result<Vec<SettingDiff>> {
+        let mut diffs: Vec<SettingDiff> = vec![];
+        for (unique_name, snapshot_settings) in self.mods.iter() {
+            let current_settings = match local_db.get_mod(unique_name) {
+                Some(local_mod) => read_user_settings(local_mod)
+                    .with_context(|| format!("Couldn't read settings of {unique_name}"))?,
+                None => Map::new(),
+            };
+            let keys = snapshot_settings
+                .keys()
+                .chain(current_settings.keys())
+                .collect::<BTreeSet<_>>();
+            for key in keys {
+                let snapshot = snapshot_settings.get(key).map(unwrap_value);
+                let current = current_settings.get(key).map(unwrap_value);
+                if snapshot != current {
+                    diffs.push(SettingDiff {
+                        unique_name: unique_name.clone(),
+                        key: key.clone(),
+                        snapshot: snapshot.cloned(),
+                        current: current.cloned(),
+                    });
+                }
+            }
+        }
+        Ok(diffs)
+    }
+}
+
+/// Restore a snapshot, replacing the settings in each of its mods' `config.json` with the ones in the snapshot.
+/// Whether each mod is enabled is left alone.
+///
+/// ## Returns
+///
+/// The unique names of mods in the snapshot that aren't installed, these are skipped.
+///
+/// ## Errors
+///
+/// If we can't read or write the config of a mod.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::db::LocalDatabase;
+/// use owmods_core::snapshots::{restore_snapshot, SettingsSnapshot};
+///
+/// let config = Config::get(None).unwrap();
+/// let local_db = LocalDatabase::fetch(&config.owml_path).unwrap();
+/// let snapshot = SettingsSnapshot::load("Tuned", &config).unwrap();
+/// let skipped = restore_snapshot(&snapshot, &local_db).unwrap();
+/// for unique_name in skipped {
+///     println!("{unique_name} isn't installed, skipped it");
+/// }
+/// ```
+///
+pub fn restore_snapshot(
+    snapshot: &SettingsSnapshot,
+    local_db: &LocalDatabase,
+) -> Result<Vec<String>> {
+    let mut skipped: Vec<String> = vec![];
+    for (unique_name, settings) in snapshot.mods.iter() {
+        if let Some(local_mod) = local_db.get_mod(unique_name) {
+            write_user_settings(local_mod, settings.clone())
+                .with_context(|| format!("Couldn't restore settings of {unique_name}"))?;
+        } else {
+            warn!("{unique_name} isn't installed, skipping its settings");
+            skipped.push(unique_name.clone());
+        }
+    }
+    Ok(skipped)
+}
+
+/// Import a snapshot that was exported with [SettingsSnapshot::export] and save it,
+/// overwriting any snapshot with the same name.
+/// If `name` is given the snapshot is saved under that name instead of the one in the file.
+///
+/// ## Errors
+///
+/// If we can't read the file, it isn't a snapshot, or we can't save it.
+///
+pub fn import_snapshot(
+    path: &Path,
+    name: Option<&str>,
+    config: &Config,
+) -> Result<SettingsSnapshot> {
+    let mut snapshot: SettingsSnapshot = deserialize_from_json(path)
+        .with_context(|| format!("Couldn't read snapshot from {}", path.display()))?;
+    if let Some(name) = name {
+        snapshot.name = name.trim().to_string();
+    }
+    snapshot.save(config)?;
+    Ok(snapshot)
+}
+
+/// List all saved snapshots, sorted by name.
+/// Snapshots that can't be read are skipped with a warning.
+///
+/// ## Errors
+///
+/// If we can't read the snapshots folder.
+///
+pub fn list_snapshots(config: &Config) -> Result<Vec<SettingsSnapshot>> {
+    let dir = get_snapshots_dir(config);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut snapshots: Vec<SettingsSnapshot> = read_dir(&dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                deserialize_from_json(&path)
+                    .map_err(|e| warn!("Couldn't read snapshot at {}: {e:?}", path.display()))
+                    .ok()
+            } else {
+                None
+            }
+        })
+        .collect();
+    snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(snapshots)
+}
+
+/// Delete a saved snapshot.
+///
+/// ## Errors
+///
+/// If no snapshot with that name exists or we can't delete it.
+///
+pub fn delete_snapshot(name: &str, config: &Config) -> Result<()> {
+    let path = get_snapshot_path(name, config)?;
+    if !path.is_file() {
+        return Err(anyhow!("Snapshot \"{name}\" not found"));
+    }
+    remove_file(&path).with_context(|| format!("Couldn't delete snapshot \"{name}\""))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs::write;
+
+    use serde_json::json;
+
+    use crate::{test_utils::TestContext, toggle::read_config};
+
+    use super::*;
+
+    fn setup() -> TestContext {
+        let mut ctx = TestContext::new();
+        let local_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
+        write(
+            PathBuf::from(&local_mod.mod_path).join("config.json"),
+            json!({ "enabled": true, "settings": { "speed": 2, "mode": { "type": "selector", "value": "Fast" } } })
+                .to_string(),
+        )
+        .unwrap();
+        ctx
+    }
+
+    fn current_settings(ctx: &TestContext) -> Map<String, Value> {
+        read_user_settings(ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_save_load() {
+        let ctx = setup();
+        let snapshot = SettingsSnapshot::take("Test", &ctx.local_db, &[]).unwrap();
+        assert_eq!(snapshot.mods.len(), 1);
+        snapshot.save(&ctx.config).unwrap();
+        assert_eq!(
+            SettingsSnapshot::load("Test", &ctx.config).unwrap(),
+            snapshot
+        );
+        assert!(SettingsSnapshot::load("Missing", &ctx.config).is_err());
+        assert!(SettingsSnapshot::take("Test", &ctx.local_db, &["Missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_diff_and_restore() {
+        let ctx = setup();
+        let mut snapshot = SettingsSnapshot::take("Test", &ctx.local_db, &[]).unwrap();
+        assert!(snapshot.diff(&ctx.local_db).unwrap().is_empty());
+        let local_mod = ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap();
+        write(
+            PathBuf::from(&local_mod.mod_path).join("config.json"),
+            json!({ "enabled": false, "settings": { "speed": 3, "mode": "Fast", "extra": true } })
+                .to_string(),
+        )
+        .unwrap();
+        let diff = snapshot.diff(&ctx.local_db).unwrap();
+        let keys = diff.iter().map(|d| d.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["extra", "speed"]);
+        assert_eq!(diff[1].snapshot, Some(json!(2)));
+        assert_eq!(diff[1].current, Some(json!(3)));
+
+        snapshot
+            .mods
+            .insert("Example.Missing".to_string(), Map::new());
+        let skipped = restore_snapshot(&snapshot, &ctx.local_db).unwrap();
+        assert_eq!(skipped, vec!["Example.Missing".to_string()]);
+        assert_eq!(current_settings(&ctx)["speed"], json!(2));
+        assert!(!current_settings(&ctx).contains_key("extra"));
+        let config_path = PathBuf::from(&local_mod.mod_path).join("config.json");
+        assert!(!read_config(&config_path).unwrap().enabled);
+    }
+
+    #[test]
+    fn test_snapshot_mod_without_settings() {
+        let mut ctx = setup();
+        let local_mod = ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+        let snapshot = SettingsSnapshot::take("Test", &ctx.local_db, &[]).unwrap();
+        assert_eq!(snapshot.mods["Bwc9876.SaveEditor"], Map::new());
+        let config_path = PathBuf::from(&local_mod.mod_path).join("config.json");
+        write(
+            &config_path,
+            json!({ "enabled": true, "settings": { "debug": true } }).to_string(),
+        )
+        .unwrap();
+        let diff = snapshot.diff(&ctx.local_db).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].unique_name, "Bwc9876.SaveEditor");
+        assert_eq!(diff[0].snapshot, None);
+        restore_snapshot(&snapshot, &ctx.local_db).unwrap();
+        assert!(read_config(&config_path)
+            .unwrap()
+            .settings
+            .unwrap()
+            .is_empty());
+        assert!(snapshot.diff(&ctx.local_db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_export_import() {
+        let ctx = setup();
+        let snapshot = SettingsSnapshot::take("Test", &ctx.local_db, &[]).unwrap();
+        let path = ctx.temp_dir.path().join("shared.json");
+        snapshot.export(&path).unwrap();
+        let imported = import_snapshot(&path, Some("Shared"), &ctx.config).unwrap();
+        assert_eq!(imported.name, "Shared");
+        assert_eq!(imported.mods, snapshot.mods);
+        let names = list_snapshots(&ctx.config)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Shared".to_string()]);
+        delete_snapshot("Shared", &ctx.config).unwrap();
+        assert!(list_snapshots(&ctx.config).unwrap().is_empty());
+    }
+}
@@ -1,11 +1,21 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+
 use anyhow::{anyhow, Result};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use tempfile::NamedTempFile;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     net::{TcpListener, TcpStream},
-    sync::mpsc,
+    sync::{mpsc, mpsc::error::TrySendError, Notify},
 };
 use typeshare::typeshare;
 
@@ -83,6 +93,25 @@ impl SocketMessage {
             sender_type: Some("LogServer".to_string()),
         }
     }
+
+    /// Check if this message was made by the manager with [SocketMessage::make_internal] instead of sent by the game
+    pub fn is_internal(&self) -> bool {
+        self.sender_name.as_deref() == Some("Manager")
+            && self.sender_type.as_deref() == Some("LogServer")
+    }
+}
+
+#[cfg(test)]
+impl SocketMessage {
+    /// Get a test SocketMessage sent by a mod for use in unit tests
+    pub fn get_test(sender: &str, message: &str, message_type: SocketMessageType) -> Self {
+        Self {
+            sender_name: Some(sender.to_string()),
+            sender_type: Some("ModHelper".to_string()),
+            message: message.to_string(),
+            message_type,
+        }
+    }
 }
 
 impl Searchable for SocketMessage {
@@ -95,10 +124,223 @@ impl Searchable for SocketMessage {
     }
 }
 
+/// What the log server does when logs come in faster than they're taken out of the channel
+#[typeshare]
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize, Default)]
+pub enum LogOverflowPolicy {
+    /// Wait for room in the channel, this stops reading from the game until there's room
+    Block,
+    /// Write logs that don't fit to a buffer on disk, they're sent in order once there's room
+    Spill,
+    /// Drop logs that don't fit, a message saying how many were dropped is sent along with the next log that fits
+    #[default]
+    Drop,
+}
+
+// Logs that didn't fit in the channel, stored in a temp file until there's room
+struct SpillBuffer {
+    file: NamedTempFile,
+    reader: io::BufReader<File>,
+    pending: usize,
+}
+
+impl SpillBuffer {
+    fn new() -> Result<Self> {
+        let file = NamedTempFile::new()?;
+        let reader = io::BufReader::new(file.reopen()?);
+        Ok(Self {
+            file,
+            reader,
+            pending: 0,
+        })
+    }
+
+    fn push(&mut self, message: &SocketMessage) -> Result<()> {
+        let line = serde_json::to_string(message)?;
+        writeln!(self.file.as_file_mut(), "{line}")?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<SocketMessage>> {
+        if self.pending == 0 {
+            return Ok(None);
+        }
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        self.pending -= 1;
+        Ok(Some(serde_json::from_str(&line)?))
+    }
+}
+
+// Where the log server sends logs, handles logs that don't fit in the channel according to a [LogOverflowPolicy]
+struct LogSink {
+    tx: LogServerSender,
+    policy: LogOverflowPolicy,
+    dropped: AtomicU32,
+    spill: Mutex<Option<SpillBuffer>>,
+    overflowed: Notify,
+}
+
+impl LogSink {
+    fn new(tx: LogServerSender, policy: LogOverflowPolicy) -> Self {
+        Self {
+            tx,
+            policy,
+            dropped: AtomicU32::new(0),
+            spill: Mutex::new(None),
+            overflowed: Notify::new(),
+        }
+    }
+
+    fn make_dropped_message(count: u32) -> SocketMessage {
+        SocketMessage::make_internal(
+            &format!("{count} log(s) were dropped because they came in too fast"),
+            SocketMessageType::Warning,
+        )
+    }
+
+    // Send a message, or spill/drop it if there's no room
+    async fn send(&self, message: SocketMessage) {
+        let res = match self.policy {
+            LogOverflowPolicy::Block => self.tx.send(message).await.map_err(|e| anyhow!("{e}")),
+            LogOverflowPolicy::Spill => self.send_or_spill(message),
+            LogOverflowPolicy::Drop => self.send_or_drop(message),
+        };
+        if let Err(why) = res {
+            error!("Couldn't Yield Log: {why:?}")
+        }
+    }
+
+    fn send_or_drop(&self, message: SocketMessage) -> Result<()> {
+        let dropped = self.dropped.swap(0, Ordering::SeqCst);
+        if dropped != 0 {
+            if let Err(why) = self.tx.try_send(Self::make_dropped_message(dropped)) {
+                self.dropped.fetch_add(dropped + 1, Ordering::SeqCst);
+                return match why {
+                    TrySendError::Full(_) => Ok(()),
+                    TrySendError::Closed(_) => Err(anyhow!("Channel closed")),
+                };
+            }
+        }
+        match self.tx.try_send(message) {
+            Ok(_) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                if self.dropped.fetch_add(1, Ordering::SeqCst) == 0 {
+                    warn!("Logs incoming too fast! Logs are being dropped!");
+                    self.overflowed.notify_one();
+                }
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => Err(anyhow!("Channel closed")),
+        }
+    }
+
+    fn send_or_spill(&self, message: SocketMessage) -> Result<()> {
+        let mut spill = self.spill.lock().unwrap();
+        // Once we start spilling everything goes to the buffer until it's empty, so logs stay in order
+        let message = if spill.as_ref().is_none_or(|s| s.pending == 0) {
+            match self.tx.try_send(message) {
+                Ok(_) => return Ok(()),
+                Err(TrySendError::Full(message)) => message,
+                Err(TrySendError::Closed(_)) => return Err(anyhow!("Channel closed")),
+            }
+        } else {
+            message
+        };
+        if spill.is_none() {
+            warn!("Logs incoming too fast! Logs are being buffered to disk");
+            *spill = Some(SpillBuffer::new()?);
+        }
+        spill.as_mut().unwrap().push(&message)?;
+        self.overflowed.notify_one();
+        Ok(())
+    }
+
+    // Send spilled logs or how many logs were dropped as room opens up in the channel, runs until the channel is closed
+    async fn drain(&self) {
+        match self.policy {
+            LogOverflowPolicy::Block => std::future::pending().await,
+            LogOverflowPolicy::Spill => self.drain_spill().await,
+            LogOverflowPolicy::Drop => self.drain_dropped().await,
+        }
+    }
+
+    // Without this, if the game stops sending logs after some were dropped we'd never say they were
+    async fn drain_dropped(&self) {
+        loop {
+            self.overflowed.notified().await;
+            let Ok(permit) = self.tx.reserve().await else {
+                return;
+            };
+            let dropped = self.dropped.swap(0, Ordering::SeqCst);
+            if dropped != 0 {
+                permit.send(Self::make_dropped_message(dropped));
+            }
+        }
+    }
+
+    async fn drain_spill(&self) {
+        loop {
+            let is_empty = self
+                .spill
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_none_or(|s| s.pending == 0);
+            if is_empty {
+                self.overflowed.notified().await;
+                continue;
+            }
+            let Ok(permit) = self.tx.reserve().await else {
+                return;
+            };
+            // Send while holding the lock so new logs can't get sent before this one
+            let mut spill = self.spill.lock().unwrap();
+            match spill.as_mut().map(|s| s.pop()).transpose() {
+                Ok(Some(Some(message))) => permit.send(message),
+                Ok(_) => {}
+                Err(why) => {
+                    error!("Couldn't Read Spilled Logs, they will be dropped: {why:?}");
+                    *spill = None;
+                }
+            }
+        }
+    }
+
+    // Send every log still in the spill buffer and how many logs were dropped, waiting for room as needed
+    async fn flush(&self) {
+        let dropped = self.dropped.swap(0, Ordering::SeqCst);
+        if dropped != 0 {
+            self.tx.send(Self::make_dropped_message(dropped)).await.ok();
+        }
+        if self.policy != LogOverflowPolicy::Spill {
+            return;
+        }
+        loop {
+            let Ok(permit) = self.tx.reserve().await else {
+                return;
+            };
+            let mut spill = self.spill.lock().unwrap();
+            match spill.as_mut().map(|s| s.pop()).transpose() {
+                Ok(Some(Some(message))) => permit.send(message),
+                Ok(_) => return,
+                Err(why) => {
+                    error!("Couldn't Read Spilled Logs, they will be dropped: {why:?}");
+                    *spill = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
 /// A server used to listen to logs from the game
 pub struct LogServer {
     /// The port the server is bound to
     pub port: u16,
+    /// What to do when logs come in faster than they're taken out of the channel
+    pub overflow_policy: LogOverflowPolicy,
     listener: TcpListener,
 }
 
@@ -122,25 +364,17 @@ impl LogServer {
         let listener = TcpListener::bind(&address).await?;
         // Get the actual port we bound to in case the user passed port 0
         let port = listener.local_addr()?.port();
-        Ok(Self { port, listener })
-    }
-
-    // Give a log to the tx channel
-    async fn yield_log(tx: &LogServerSender, message: SocketMessage) {
-        if tx.capacity() == 0 {
-            warn!("Logs incoming too fast! Logs may be dropped!");
-        } else {
-            let res = tx.send(message).await;
-            if let Err(why) = res {
-                error!("Couldn't Yield Log: {why:?}")
-            }
-        }
+        Ok(Self {
+            port,
+            overflow_policy: LogOverflowPolicy::default(),
+            listener,
+        })
     }
 
     // Loop that runs when a client connects to the server
     // Handles all messages from the client
     // Returns true if the client sent a quit message
-    async fn client_loop(mut stream: TcpStream, tx: &LogServerSender) -> bool {
+    async fn client_loop(mut stream: TcpStream, sink: &LogSink) -> bool {
         let mut reader = BufReader::with_capacity(16000, &mut stream);
         let mut body = String::new();
         let mut flag = false;
@@ -160,18 +394,15 @@ impl LogServer {
                             break;
                         }
                         _ => {
-                            Self::yield_log(tx, message).await;
+                            sink.send(message).await;
                         }
                     };
                 }
                 Err(why) => {
-                    Self::yield_log(
-                        tx,
-                        SocketMessage::make_internal(
-                            &format!("Invalid Log From Game Received: {why:?}"),
-                            SocketMessageType::Error,
-                        ),
-                    )
+                    sink.send(SocketMessage::make_internal(
+                        &format!("Invalid Log From Game Received: {why:?}"),
+                        SocketMessageType::Error,
+                    ))
                     .await;
                 }
             }
@@ -184,7 +415,7 @@ impl LogServer {
     // Makes a new client_loop for each client
     async fn server_loop(
         &self,
-        tx: &LogServerSender,
+        sink: &Arc<LogSink>,
         shutdown_sender: mpsc::Sender<()>,
         disconnect_on_quit: bool,
     ) {
@@ -192,42 +423,35 @@ impl LogServer {
             let stream = self.listener.accept().await;
             match stream {
                 Ok((stream, _)) => {
-                    let tx2 = tx.clone();
+                    let sink2 = sink.clone();
                     let shutdown_sender2 = shutdown_sender.clone();
                     tokio::spawn(async move {
-                        Self::yield_log(
-                            &tx2,
-                            SocketMessage::make_internal(
+                        sink2
+                            .send(SocketMessage::make_internal(
                                 Self::CLIENT_CONNECTED,
                                 SocketMessageType::Info,
-                            ),
-                        )
-                        .await;
+                            ))
+                            .await;
 
-                        let quit_received = Self::client_loop(stream, &tx2).await;
+                        let quit_received = Self::client_loop(stream, &sink2).await;
 
                         if quit_received && disconnect_on_quit {
                             shutdown_sender2.send(()).await.ok();
                         }
 
-                        Self::yield_log(
-                            &tx2,
-                            SocketMessage::make_internal(
+                        sink2
+                            .send(SocketMessage::make_internal(
                                 Self::CLIENT_DISCONNECTED,
                                 SocketMessageType::Info,
-                            ),
-                        )
-                        .await;
+                            ))
+                            .await;
                     });
                 }
                 Err(why) => {
-                    Self::yield_log(
-                        tx,
-                        SocketMessage::make_internal(
-                            &format!("Client Connection Failure! {why:?}"),
-                            SocketMessageType::Error,
-                        ),
-                    )
+                    sink.send(SocketMessage::make_internal(
+                        &format!("Client Connection Failure! {why:?}"),
+                        SocketMessageType::Error,
+                    ))
                     .await;
                 }
             }
@@ -239,14 +463,15 @@ impl LogServer {
     /// - tx will send [SocketMessage]s from the game
     /// - disconnect_on_quit will make the server stop listening if the game sends a [SocketMessageType::Quit] message
     ///
+    /// If logs come in faster than they're taken out of tx, they're handled according to [LogServer::overflow_policy].
+    ///
     pub async fn listen(&self, tx: LogServerSender, disconnect_on_quit: bool) -> Result<()> {
-        Self::yield_log(
-            &tx,
-            SocketMessage::make_internal(
-                &format!("Ready to receive game logs on port {}!", self.port),
-                SocketMessageType::Info,
-            ),
-        )
+        let sink = Arc::new(LogSink::new(tx, self.overflow_policy));
+
+        sink.send(SocketMessage::make_internal(
+            &format!("Ready to receive game logs on port {}!", self.port),
+            SocketMessageType::Info,
+        ))
         .await;
 
         // Make a channel to listen for a shutdown message
@@ -256,11 +481,14 @@ impl LogServer {
         tokio::select! {
             _ = async {
                 let shutdown_sender = &shutdown_sender.clone();
-                self.server_loop(&tx, shutdown_sender.clone(), disconnect_on_quit).await;
+                self.server_loop(&sink, shutdown_sender.clone(), disconnect_on_quit).await;
             } => {},
+            _ = sink.drain() => {},
             _ = shutdown_receiver.recv() => info!("Quit Message Received")
         };
 
+        sink.flush().await;
+
         Ok(())
     }
 }
@@ -490,4 +718,96 @@ mod tests {
             server.assert_logs(expected);
         });
     }
+
+    fn make_numbered(count: u32) -> Vec<SocketMessage> {
+        (0..count)
+            .map(|i| MockGame::make_test_msg(&i.to_string(), SocketMessageType::Info))
+            .collect()
+    }
+
+    #[test]
+    fn test_overflow_block() {
+        tokio_test::block_on(async {
+            let (tx, mut rx) = mpsc::channel(1);
+            let sink = LogSink::new(tx, LogOverflowPolicy::Block);
+            let messages = make_numbered(10);
+            let mut received = vec![];
+            tokio::join!(
+                async {
+                    for msg in messages.clone() {
+                        sink.send(msg).await;
+                    }
+                },
+                async {
+                    while received.len() < messages.len() {
+                        received.push(rx.recv().await.unwrap());
+                    }
+                }
+            );
+            assert_eq!(received, messages);
+        });
+    }
+
+    #[test]
+    fn test_overflow_spill() {
+        tokio_test::block_on(async {
+            let (tx, mut rx) = mpsc::channel(2);
+            let sink = LogSink::new(tx, LogOverflowPolicy::Spill);
+            let messages = make_numbered(10);
+            // Nothing's reading yet, so everything past the first 2 is spilled
+            for msg in messages.clone() {
+                sink.send(msg).await;
+            }
+            assert_eq!(sink.spill.lock().unwrap().as_ref().unwrap().pending, 8);
+            let mut received = vec![];
+            tokio::select! {
+                _ = sink.drain() => panic!("Drain should never finish"),
+                _ = async {
+                    while received.len() < messages.len() {
+                        received.push(rx.recv().await.unwrap());
+                    }
+                } => {}
+            };
+            assert_eq!(received, messages);
+        });
+    }
+
+    #[test]
+    fn test_overflow_drop() {
+        tokio_test::block_on(async {
+            let (tx, mut rx) = mpsc::channel(2);
+            let sink = LogSink::new(tx, LogOverflowPolicy::Drop);
+            let messages = make_numbered(6);
+            for msg in messages.clone() {
+                sink.send(msg).await;
+            }
+            assert_eq!(rx.recv().await.unwrap(), messages[0]);
+            assert_eq!(rx.recv().await.unwrap(), messages[1]);
+            let last = MockGame::make_test_msg("Last", SocketMessageType::Info);
+            sink.send(last.clone()).await;
+            assert_eq!(rx.recv().await.unwrap(), LogSink::make_dropped_message(4));
+            assert_eq!(rx.recv().await.unwrap(), last);
+        });
+    }
+
+    #[test]
+    fn test_overflow_drop_reported_when_quiet() {
+        tokio_test::block_on(async {
+            let (tx, mut rx) = mpsc::channel(2);
+            let sink = LogSink::new(tx, LogOverflowPolicy::Drop);
+            for msg in make_numbered(5) {
+                sink.send(msg).await;
+            }
+            let mut received = vec![];
+            tokio::select! {
+                _ = sink.drain() => panic!("Drain should never finish"),
+                _ = async {
+                    while received.len() < 3 {
+                        received.push(rx.recv().await.unwrap());
+                    }
+                } => {}
+            };
+            assert_eq!(received[2], LogSink::make_dropped_message(3));
+        });
+    }
 }
@@ -1,19 +1,20 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use log::warn;
+use log::{info, warn};
 
 use crate::{
     db::LocalDatabase,
     file::{deserialize_from_json, serialize_to_json},
+    graph::{check_enabled_dependents, DependencyGraph, DependentsMode},
     mods::local::{LocalMod, ModStubConfig},
 };
 
-fn read_config(config_path: &Path) -> Result<ModStubConfig> {
+pub(crate) fn read_config(config_path: &Path) -> Result<ModStubConfig> {
     deserialize_from_json(config_path)
 }
 
-fn write_config(conf: &ModStubConfig, config_path: &Path) -> Result<()> {
+pub(crate) fn write_config(conf: &ModStubConfig, config_path: &Path) -> Result<()> {
     serialize_to_json(&conf, config_path, false)?;
     Ok(())
 }
@@ -75,6 +76,7 @@ fn _toggle_mod(local_mod: &LocalMod, enabled: bool) -> Result<bool> {
 
 /// Toggle a mod to a given enabled value.
 /// Also supports applying this action recursively.
+/// When disabling, `dependents` decides what happens to enabled mods that depend on this one, see [DependentsMode].
 ///
 /// ## Returns
 ///
@@ -83,19 +85,44 @@ fn _toggle_mod(local_mod: &LocalMod, enabled: bool) -> Result<bool> {
 ///
 /// ## Errors
 ///
-/// If we can't read/save to the config files of the mod or (if recursive is true) any of it's dependents.
+/// - If we're disabling the mod, `dependents` is [DependentsMode::Refuse], and enabled mods depend on it.
+///   This will be an [crate::graph::EnabledDependentsError].
+/// - If we can't read/save to the config files of the mod or (if recursive is true) any of it's dependents.
 ///
 pub fn toggle_mod(
     unique_name: &str,
     local_db: &LocalDatabase,
     enabled: bool,
     recursive: bool,
+    dependents: DependentsMode,
 ) -> Result<Vec<String>> {
     let mut show_warnings_for: Vec<String> = vec![];
 
     let local_mod = local_db
         .get_mod(unique_name)
         .with_context(|| format!("Mod {unique_name} not found in local database."))?;
+
+    let cascade = if enabled {
+        vec![]
+    } else {
+        let graph = DependencyGraph::from_local_db(local_db);
+        let changing = if recursive {
+            graph.dependencies(unique_name)
+        } else {
+            vec![]
+        };
+        check_enabled_dependents(local_db, unique_name, &changing, dependents)?
+    };
+    for dependent in cascade.iter() {
+        info!(
+            "Disabling {} as it depends on {}",
+            dependent.manifest.name, local_mod.manifest.name
+        );
+        if _toggle_mod(dependent, false)? {
+            show_warnings_for.push(dependent.manifest.unique_name.clone());
+        }
+    }
+
     let show_warning = _toggle_mod(local_mod, enabled)?;
 
     if show_warning {
@@ -112,6 +139,7 @@ pub fn toggle_mod(
     {
         let mut to_check: Vec<String> = local_mod.manifest.dependencies.clone().unwrap_or_default();
         let mut toggled_mods: Vec<String> = vec![unique_name.to_string()];
+        toggled_mods.extend(cascade.iter().map(|m| m.manifest.unique_name.clone()));
         while !to_check.is_empty() {
             for dep in std::mem::take(&mut to_check) {
                 if toggled_mods.contains(&dep) {
@@ -167,6 +195,7 @@ mod tests {
     use std::fs::remove_file;
 
     use crate::{
+        graph::EnabledDependentsError,
         mods::local::{LocalMod, UnsafeLocalMod},
         test_utils::TestContext,
     };
@@ -177,10 +206,24 @@ mod tests {
     fn test_toggle_mod() {
         let mut ctx = TestContext::new();
         ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, false).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, true, false).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            true,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
     }
@@ -195,7 +238,14 @@ mod tests {
             .mods
             .get_mut(&String::from("Bwc9876.TimeSaver"))
             .unwrap() = UnsafeLocalMod::Valid(Box::new(new_mod));
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, true).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            true,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
         assert!(!ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap().enabled);
@@ -216,7 +266,14 @@ mod tests {
             .mods
             .get_mut(&String::from("Bwc9876.SaveEditor"))
             .unwrap() = UnsafeLocalMod::Valid(Box::new(new_mod_2));
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, true).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            true,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
         assert!(!ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap().enabled);
@@ -235,7 +292,14 @@ mod tests {
         let mut test_mod = LocalMod::get_test(0);
         test_mod.manifest.dependencies = Some(vec![String::from("Bwc9876.SaveEditor")]);
         ctx.insert_test_mod(&test_mod);
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, true).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            true,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
         assert!(ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap().enabled);
@@ -255,7 +319,14 @@ mod tests {
         test_mod.enabled = false;
         test_mod.manifest.dependencies = Some(vec![String::from("Bwc9876.SaveEditor")]);
         ctx.insert_test_mod(&test_mod);
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, true).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            true,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
         assert!(!ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap().enabled);
@@ -266,14 +337,73 @@ mod tests {
         let mut ctx = TestContext::new();
         ctx.install_test_zip("Bwc9876.TimeSaver.zip", true);
         remove_file(ctx.get_test_path("Bwc9876.TimeSaver").join("config.json")).unwrap();
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, false).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
-        toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, true, false).unwrap();
+        toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            true,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
     }
 
+    fn setup_dependent() -> TestContext {
+        let mut ctx = TestContext::new();
+        let mut new_mod = ctx.install_test_zip("Bwc9876.TimeSaver.zip", false);
+        ctx.install_test_zip("Bwc9876.SaveEditor.zip", true);
+        new_mod.manifest.dependencies = Some(vec!["Bwc9876.SaveEditor".to_string()]);
+        *ctx.local_db
+            .mods
+            .get_mut(&String::from("Bwc9876.TimeSaver"))
+            .unwrap() = UnsafeLocalMod::Valid(Box::new(new_mod));
+        ctx
+    }
+
+    #[test]
+    fn test_toggle_mod_refuses_enabled_dependents() {
+        let mut ctx = setup_dependent();
+        let err = toggle_mod(
+            "Bwc9876.SaveEditor",
+            &ctx.local_db,
+            false,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap_err();
+        let err = err.downcast_ref::<EnabledDependentsError>().unwrap();
+        assert_eq!(err.dependents, vec!["Bwc9876.TimeSaver".to_string()]);
+        ctx.fetch_local_db();
+        assert!(ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap().enabled);
+    }
+
+    #[test]
+    fn test_toggle_mod_cascade() {
+        let mut ctx = setup_dependent();
+        toggle_mod(
+            "Bwc9876.SaveEditor",
+            &ctx.local_db,
+            false,
+            false,
+            DependentsMode::Cascade,
+        )
+        .unwrap();
+        ctx.fetch_local_db();
+        assert!(!ctx.local_db.get_mod("Bwc9876.SaveEditor").unwrap().enabled);
+        assert!(!ctx.local_db.get_mod("Bwc9876.TimeSaver").unwrap().enabled);
+    }
+
     #[test]
     fn test_toggle_mod_has_prepatcher() {
         let mut ctx = TestContext::new();
@@ -283,10 +413,24 @@ mod tests {
             .mods
             .get_mut(&String::from("Bwc9876.TimeSaver"))
             .unwrap() = UnsafeLocalMod::Valid(Box::new(local_mod));
-        let show_warnings = toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, false, false).unwrap();
+        let show_warnings = toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            false,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert_eq!(show_warnings[0], "Bwc9876.TimeSaver");
-        let show_warnings = toggle_mod("Bwc9876.TimeSaver", &ctx.local_db, true, false).unwrap();
+        let show_warnings = toggle_mod(
+            "Bwc9876.TimeSaver",
+            &ctx.local_db,
+            true,
+            false,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         assert!(show_warnings.is_empty());
         ctx.fetch_local_db();
     }
@@ -304,11 +448,25 @@ mod tests {
         local_mod_2.manifest.dependencies = Some(vec!["Bwc9876.TimeSaver".to_string()]);
         local_mod_2.manifest.patcher = Some("SomePatcher.dll".to_string());
         ctx.insert_test_mod(&local_mod_2);
-        let show_warnings = toggle_mod("Bwc9876.SaveEditor", &ctx.local_db, false, true).unwrap();
+        let show_warnings = toggle_mod(
+            "Bwc9876.SaveEditor",
+            &ctx.local_db,
+            false,
+            true,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         ctx.fetch_local_db();
         assert!(show_warnings.contains(&"Bwc9876.TimeSaver".to_string()));
         assert!(show_warnings.contains(&"Bwc9876.SaveEditor".to_string()));
-        let show_warnings = toggle_mod("Bwc9876.SaveEditor", &ctx.local_db, true, true).unwrap();
+        let show_warnings = toggle_mod(
+            "Bwc9876.SaveEditor",
+            &ctx.local_db,
+            true,
+            true,
+            DependentsMode::Refuse,
+        )
+        .unwrap();
         assert!(show_warnings.is_empty());
         ctx.fetch_local_db();
     }
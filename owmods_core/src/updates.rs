@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{fmt::Display, path::PathBuf};
 
 use anyhow::Result;
 use log::{info, warn};
@@ -7,8 +7,8 @@ use versions::Versioning;
 use crate::{
     analytics::{send_analytics_deferred, AnalyticsEventName},
     config::Config,
-    constants::OWML_UNIQUE_NAME,
-    db::{LocalDatabase, RemoteDatabase},
+    constants::{OWML_UNIQUE_NAME, PIN_HOLD},
+    db::{fix_version, LocalDatabase, RemoteDatabase},
     download::{download_and_install_owml, install_mods_parallel},
     file::serialize_to_json,
     mods::{local::LocalMod, remote::RemoteMod},
@@ -70,7 +70,121 @@ pub fn check_mod_needs_update<'a>(
     }
 }
 
+/// A pin that stops a mod from being updated, pins are stored in [Config::pins]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModPin {
+    /// Keep whatever version is installed
+    Hold,
+    /// Only allow this exact version to be installed
+    Version(String),
+}
+
+impl ModPin {
+    /// Parse a pin from its value in the config
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use owmods_core::updates::ModPin;
+    ///
+    /// assert_eq!(ModPin::parse("hold"), ModPin::Hold);
+    /// assert_eq!(ModPin::parse("v1.2.0"), ModPin::Version("1.2.0".to_string()));
+    /// ```
+    ///
+    pub fn parse(value: &str) -> Self {
+        if value.trim().eq_ignore_ascii_case(PIN_HOLD) {
+            Self::Hold
+        } else {
+            Self::Version(fix_version(value).to_string())
+        }
+    }
+
+    /// Check if this pin allows the mod to be installed at the given version
+    pub fn allows(&self, version: &str) -> bool {
+        match self {
+            Self::Hold => false,
+            Self::Version(pinned) => pinned == fix_version(version),
+        }
+    }
+}
+
+impl Display for ModPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hold => write!(f, "{PIN_HOLD}"),
+            Self::Version(version) => write!(f, "{version}"),
+        }
+    }
+}
+
+/// Get the pin set for a mod in the config
+///
+/// ## Returns
+///
+/// The mod's pin, or `None` if it isn't pinned
+///
+pub fn get_pin(config: &Config, unique_name: &str) -> Option<ModPin> {
+    config.pins.get(unique_name).map(|p| ModPin::parse(p))
+}
+
+/// Check if changing a mod from `current_version` to `new_version` is blocked by a pin.
+/// Reinstalling the same version is never blocked.
+///
+/// ## Returns
+///
+/// The pin blocking the change, or `None` if the mod can be installed at `new_version`
+///
+/// ## Examples
+///
+/// ```no_run
+/// use owmods_core::config::Config;
+/// use owmods_core::updates::check_pin;
+///
+/// let mut config = Config::get(None).unwrap();
+/// config.pins.insert("Bwc9876.TimeSaver".to_string(), "1.1.0".to_string());
+///
+/// assert!(check_pin(&config, "Bwc9876.TimeSaver", "1.0.0", "1.1.1").is_some());
+/// assert!(check_pin(&config, "Bwc9876.TimeSaver", "1.0.0", "1.1.0").is_none());
+/// ```
+///
+pub fn check_pin(
+    config: &Config,
+    unique_name: &str,
+    current_version: &str,
+    new_version: &str,
+) -> Option<ModPin> {
+    if fix_version(current_version) == fix_version(new_version) {
+        return None;
+    }
+    get_pin(config, unique_name).filter(|p| !p.allows(new_version))
+}
+
+/// Check if a mod has an update that's being held back by a pin.
+///
+/// ## Returns
+///
+/// The remote mod with the newer version if there's an update and it's blocked by a pin, otherwise `None`.
+///
+pub fn check_mod_held_back<'a>(
+    local_mod: &'a LocalMod,
+    remote_db: &'a RemoteDatabase,
+    config: &Config,
+) -> Option<&'a RemoteMod> {
+    match check_mod_needs_update(local_mod, remote_db) {
+        (true, Some(remote_mod)) => check_pin(
+            config,
+            &local_mod.manifest.unique_name,
+            &local_mod.manifest.version,
+            &remote_mod.version,
+        )
+        .map(|_| remote_mod),
+        _ => None,
+    }
+}
+
 /// Check all mods *and OWML* for updates and update them if needed.
+/// Mods are backed up before they're updated so they can be rolled back with [crate::backups::rollback].
+/// Mods that are pinned in the config are skipped.
 ///
 /// ## Returns
 ///
@@ -91,13 +205,24 @@ pub async fn update_all(
     for local_mod in local_db.valid() {
         let (update, remote_mod) = check_mod_needs_update(local_mod, remote_db);
         if update {
+            let remote_mod = remote_mod.unwrap();
+            if let Some(pin) = check_pin(
+                config,
+                &local_mod.manifest.unique_name,
+                &local_mod.manifest.version,
+                &remote_mod.version,
+            ) {
+                info!(
+                    "{}: {} -> {} (pinned to {pin}, skipping)",
+                    local_mod.manifest.name, local_mod.manifest.version, remote_mod.version
+                );
+                continue;
+            }
             info!(
                 "{}: {} -> {}",
-                local_mod.manifest.name,
-                local_mod.manifest.version,
-                remote_mod.unwrap().version
+                local_mod.manifest.name, local_mod.manifest.version, remote_mod.version
             );
-            needs_update.push(remote_mod.unwrap());
+            needs_update.push(remote_mod);
         }
     }
 
@@ -107,7 +232,15 @@ pub async fn update_all(
 
     if let Some(ref owml) = owml {
         let (update, remote_owml) = check_mod_needs_update(owml, remote_db);
-        if update {
+        let pin = remote_owml
+            .and_then(|r| check_pin(config, OWML_UNIQUE_NAME, &owml.manifest.version, &r.version));
+        if let (true, Some(pin)) = (update, pin) {
+            info!(
+                "OWML: {} -> {} (pinned to {pin}, skipping)",
+                owml.manifest.version,
+                remote_owml.unwrap().version
+            );
+        } else if update {
             owml_updated = true;
             info!(
                 "OWML: {} -> {}",
@@ -194,6 +327,76 @@ mod tests {
         assert!(needs_update);
     }
 
+    #[test]
+    fn test_mod_pin_parse() {
+        assert_eq!(ModPin::parse("hold"), ModPin::Hold);
+        assert_eq!(ModPin::parse(" HOLD "), ModPin::Hold);
+        assert_eq!(
+            ModPin::parse("v0.1.0"),
+            ModPin::Version("0.1.0".to_string())
+        );
+        assert!(ModPin::parse("0.1.0").allows("v0.1.0"));
+        assert!(!ModPin::parse("0.1.0").allows("0.2.0"));
+        assert!(!ModPin::Hold.allows("0.2.0"));
+    }
+
+    #[test]
+    fn test_check_pin() {
+        let mut ctx = TestContext::new();
+        let name = "Example.TestMod0";
+        assert!(check_pin(&ctx.config, name, "0.1.0", "0.2.0").is_none());
+        ctx.config.pins.insert(name.to_string(), "hold".to_string());
+        assert_eq!(
+            check_pin(&ctx.config, name, "0.1.0", "0.2.0"),
+            Some(ModPin::Hold)
+        );
+        assert!(check_pin(&ctx.config, name, "0.1.0", "0.1.0").is_none());
+        ctx.config
+            .pins
+            .insert(name.to_string(), "0.2.0".to_string());
+        assert!(check_pin(&ctx.config, name, "0.1.0", "0.2.0").is_none());
+        assert!(check_pin(&ctx.config, name, "0.1.0", "0.3.0").is_some());
+    }
+
+    #[test]
+    fn test_check_mod_held_back() {
+        let mut ctx = TestContext::new();
+        let (new_mod, db) = setup("0.1.0", "0.2.0");
+        assert!(check_mod_held_back(&new_mod, &db, &ctx.config).is_none());
+        ctx.config
+            .pins
+            .insert(new_mod.manifest.unique_name.clone(), "hold".to_string());
+        assert_eq!(
+            check_mod_held_back(&new_mod, &db, &ctx.config)
+                .unwrap()
+                .version,
+            "0.2.0"
+        );
+    }
+
+    #[test]
+    fn test_validate_updates_pinned() {
+        let mut ctx = TestContext::new();
+        let (new_mod, db) = setup("0.1.0", "0.2.0");
+        ctx.insert_test_mod(&new_mod);
+        ctx.config
+            .pins
+            .insert("Example.TestMod0".to_string(), "hold".to_string());
+        ctx.local_db.validate_updates_with_pins(&db, &ctx.config);
+        assert!(ctx
+            .local_db
+            .get_mod("Example.TestMod0")
+            .unwrap()
+            .errors
+            .is_empty());
+        // Pins are ignored without the config
+        ctx.local_db.validate_updates(&db);
+        assert!(matches!(
+            ctx.local_db.get_mod("Example.TestMod0").unwrap().errors[..],
+            [crate::validate::ModValidationError::Outdated(_)]
+        ));
+    }
+
     #[test]
     fn test_check_mod_needs_update_invalid_versions() {
         let (new_mod, db) = setup("burger", "burger");
@@ -1,17 +1,21 @@
 use std::path::PathBuf;
 
 use anyhow::Result;
-use log::info;
+use log::{debug, info, warn};
 use serde::Serialize;
 use typeshare::typeshare;
+use versions::{Requirement, Versioning};
 
 use crate::{
     analytics::{send_analytics_deferred, AnalyticsEventName},
+    autoremove::record_dependency_installs,
     config::Config,
-    db::{LocalDatabase, RemoteDatabase},
+    db::{fix_version, LocalDatabase, RemoteDatabase},
     download::install_mods_parallel,
+    graph::DependentsMode,
     mods::local::LocalMod,
     toggle::toggle_mod,
+    updates::check_pin,
 };
 
 /// Represents an error with a [LocalMod]
@@ -33,6 +37,52 @@ pub enum ModValidationError {
     DuplicateMod(String),
     /// The mod is outdated, contains the newest version
     Outdated(String),
+    /// A dependency of the mod is installed, but its version doesn't meet the requirement in the mod's `dependencyVersions`, contains the unique name of the dep
+    UnsatisfiedDepVersion(String),
+}
+
+/// Check if a version satisfies a dependency version requirement from a manifest's `dependencyVersions`.
+/// Requirements can use `=`, `>`, `>=`, `<`, `<=`, `~`, `^`, or `*`, multiple can be separated with commas,
+/// and a plain version is treated as a minimum version.
+/// Requirements or versions we can't parse are always considered satisfied.
+///
+/// ## Examples
+///
+/// ```
+/// use owmods_core::validate::check_dep_version;
+///
+/// assert!(check_dep_version(">=1.20.0", "1.20.3"));
+/// assert!(check_dep_version("1.20.0", "v1.21.0"));
+/// assert!(check_dep_version(">=1.0.0, <2.0.0", "1.5.0"));
+/// assert!(!check_dep_version(">=1.20.0", "1.19.9"));
+/// assert!(!check_dep_version(">=1.0.0, <2.0.0", "2.0.0"));
+/// ```
+///
+pub fn check_dep_version(requirement: &str, version: &str) -> bool {
+    let Some(version) = Versioning::new(fix_version(version)) else {
+        debug!("Couldn't parse version {version}, assuming it satisfies {requirement}");
+        return true;
+    };
+    requirement
+        .split(',')
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty())
+        .all(|r| {
+            let op_len = r.len() - r.trim_start_matches(['=', '>', '<', '~', '^']).len();
+            let (op, req_version) = r.split_at(op_len);
+            let op = if op.is_empty() && req_version != "*" {
+                ">="
+            } else {
+                op
+            };
+            match Requirement::new(&format!("{op}{}", fix_version(req_version))) {
+                Some(req) => req.matches(&version),
+                None => {
+                    debug!("Couldn't parse version requirement {r}, ignoring it");
+                    true
+                }
+            }
+        })
 }
 
 fn check_mod_dll(local_mod: &LocalMod) -> Option<ModValidationError> {
@@ -56,6 +106,13 @@ fn check_mod_deps(local_mod: &LocalMod, db: &LocalDatabase) -> Vec<ModValidation
                 if !dep_mod.enabled {
                     errors.push(ModValidationError::DisabledDep(unique_name.clone()))
                 }
+                if let Some(requirement) = local_mod.manifest.get_dependency_version(unique_name) {
+                    if !check_dep_version(requirement, &dep_mod.manifest.version) {
+                        errors.push(ModValidationError::UnsatisfiedDepVersion(
+                            unique_name.clone(),
+                        ))
+                    }
+                }
             } else {
                 errors.push(ModValidationError::MissingDep(unique_name.clone()))
             }
@@ -95,7 +152,7 @@ pub fn check_mod(local_mod: &LocalMod, db: &LocalDatabase) -> Vec<ModValidationE
 }
 
 /// Auto-fix dependency issues.
-/// Enables the disabled dependencies and installs missing ones.
+/// Enables the disabled dependencies, installs missing ones, and updates ones that are too old.
 ///
 /// ## Errors
 ///
@@ -108,26 +165,65 @@ pub async fn fix_deps(
     remote_db: &RemoteDatabase,
 ) -> Result<()> {
     let mut missing: Vec<String> = vec![];
+    let mut outdated: Vec<String> = vec![];
     for error in local_mod.errors.iter() {
         match error {
             ModValidationError::DisabledDep(unique_name) => {
                 info!("Enabling {unique_name}");
-                toggle_mod(unique_name, db, true, true)?;
+                toggle_mod(unique_name, db, true, true, DependentsMode::Ignore)?;
             }
             ModValidationError::MissingDep(unique_name) => {
                 info!("Marking {unique_name} For Install");
                 missing.push(unique_name.clone());
             }
+            ModValidationError::UnsatisfiedDepVersion(unique_name) => {
+                let requirement = local_mod
+                    .manifest
+                    .get_dependency_version(unique_name)
+                    .unwrap_or("*");
+                let installed = db.get_mod(unique_name).map(|m| &m.manifest.version);
+                let remote_mod = remote_db.get_mod(unique_name);
+                match (installed, remote_mod) {
+                    (Some(installed), Some(remote_mod))
+                        if check_dep_version(requirement, &remote_mod.version) =>
+                    {
+                        if let Some(pin) =
+                            check_pin(config, unique_name, installed, &remote_mod.version)
+                        {
+                            warn!("{unique_name} needs to be updated to {requirement} but it's pinned to {pin}, skipping");
+                        } else {
+                            info!(
+                                "Marking {unique_name} For Update ({installed} -> {})",
+                                remote_mod.version
+                            );
+                            outdated.push(unique_name.clone());
+                        }
+                    }
+                    _ => {
+                        warn!("No version of {unique_name} that satisfies {requirement} is available, it will need to be fixed manually");
+                    }
+                }
+            }
             _ => {}
         }
     }
     if !missing.is_empty() {
         info!("Installing {} Missing Dependencies", missing.len());
     }
-    install_mods_parallel(missing.clone(), config, remote_db, db).await?;
+    if !outdated.is_empty() {
+        info!("Updating {} Outdated Dependencies", outdated.len());
+    }
+    let to_install = missing.iter().chain(outdated.iter()).cloned().collect();
+    install_mods_parallel(to_install, config, remote_db, db).await?;
+    if let Err(why) = record_dependency_installs(config, &missing) {
+        warn!("Couldn't record dependency installs: {why:?}");
+    }
     for missing_mod in missing {
         send_analytics_deferred(AnalyticsEventName::ModRequiredInstall, &missing_mod, config).await;
     }
+    for outdated_mod in outdated {
+        send_analytics_deferred(AnalyticsEventName::ModUpdate, &outdated_mod, config).await;
+    }
     Ok(())
 }
 
@@ -300,4 +396,45 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_check_dep_version() {
+        assert!(check_dep_version(">=1.20.0", "1.20.0"));
+        assert!(check_dep_version("^1.2.0", "1.3.0"));
+        assert!(!check_dep_version("^1.2.0", "2.0.0"));
+        assert!(check_dep_version("=v1.0.0", "1.0.0"));
+        assert!(check_dep_version("*", "0.0.1"));
+        assert!(check_dep_version("not a version", "1.0.0"));
+        assert!(!check_dep_version("1.20.0", "1.2.0"));
+    }
+
+    #[test]
+    fn test_check_deps_unsatisfied_version() {
+        let mut mod_a = LocalMod::get_test(0);
+        mod_a.manifest.dependencies = Some(vec!["Example.TestMod1".to_string()]);
+        mod_a.manifest.dependency_versions = Some(
+            [("Example.TestMod1".to_string(), ">=3.0.0".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let mod_b = LocalMod::get_test(1);
+        let mut db = LocalDatabase::default();
+        db.mods.insert(
+            mod_a.manifest.unique_name.to_string(),
+            UnsafeLocalMod::Valid(Box::new(mod_a)),
+        );
+        db.mods.insert(
+            mod_b.manifest.unique_name.to_string(),
+            UnsafeLocalMod::Valid(Box::new(mod_b)),
+        );
+        let mod_a = db.get_mod("Example.TestMod0").unwrap();
+        let errors = check_mod_deps(mod_a, &db);
+        assert!(matches!(
+            &errors[..],
+            [ModValidationError::UnsatisfiedDepVersion(unique_name)] if unique_name == "Example.TestMod1"
+        ));
+        db.get_mod_mut("Example.TestMod1").unwrap().manifest.version = "3.1.0".to_string();
+        let mod_a = db.get_mod("Example.TestMod0").unwrap();
+        assert!(check_mod_deps(mod_a, &db).is_empty());
+    }
 }
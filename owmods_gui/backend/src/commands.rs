@@ -2,6 +2,7 @@ use std::{
     fs::File,
     io::{BufWriter, Write},
     path::PathBuf,
+    time::UNIX_EPOCH,
 };
 
 use anyhow::{anyhow, Context};
@@ -9,26 +10,35 @@ use log::{error, info};
 use owmods_core::{
     alerts::{fetch_alert, Alert},
     analytics::{send_analytics_deferred, AnalyticsEventName},
+    autoremove::{autoremove, find_orphans},
+    backups::rollback,
     config::Config,
-    constants::OWML_UNIQUE_NAME,
+    constants::{MANAGER_LOGS_FOLDER_NAME, OWML_UNIQUE_NAME},
     db::{LocalDatabase, RemoteDatabase},
+    diagnostics::SessionReport,
     download::{
         download_and_install_owml, install_mod_from_db, install_mod_from_url, install_mod_from_zip,
-        install_mods_parallel,
+        install_mods_parallel, install_prepared, prepare_install,
     },
     file::get_app_path,
     game::launch_game,
+    game_paths::{find_game_paths, GamePathCandidate},
+    graph::{DependentsMode, EnabledDependentsError},
     mods::{
         local::{LocalMod, UnsafeLocalMod},
         remote::RemoteMod,
     },
     open::{open_github, open_readme, open_shortcut},
     owml::OWMLConfig,
+    prelaunch::{run_prelaunch_checks, PrelaunchCheck, PrelaunchReport},
+    profiles::{list_profiles, Profile},
     progress::bars::{ProgressBar, ProgressBars},
-    protocol::{ProtocolPayload, ProtocolVerb},
+    protocol::{split_url_hash, ProtocolPayload, ProtocolVerb},
     remove::{remove_failed_mod, remove_mod},
+    resolve::{resolve_install, InstallPlan},
+    sessions::{get_session, list_sessions, read_session_file, SessionRecorder},
     socket::{LogServer, SocketMessageType},
-    updates::{check_mod_needs_update, fix_version_post_update},
+    updates::{check_mod_held_back, check_mod_needs_update, check_pin, fix_version_post_update},
     validate::fix_deps,
 };
 use serde::Serialize;
@@ -45,7 +55,7 @@ use crate::{
 };
 use crate::{events::CustomEventListener, game::LogData};
 use crate::{
-    game::{make_log_window, show_warnings, GameMessage},
+    game::{make_log_window, show_warnings, ExceptionCount, GameMessage, LogSessionInfo},
     gui_config::GuiConfig,
     LogPort, State,
 };
@@ -104,8 +114,9 @@ pub fn sync_remote_and_local(handle: &AppHandle) -> Result {
         let state = handle2.state::<State>();
         let mut local_db = state.local_db.write().await;
         let remote_db = state.remote_db.read().await.clone();
+        let config = state.config.read().await.clone();
         if let Some(remote_db) = remote_db.get() {
-            local_db.validate_updates(remote_db);
+            local_db.validate_updates_with_pins(remote_db, &config);
             handle2.typed_emit_all(&Event::LocalRefresh(())).ok();
         }
     });
@@ -194,7 +205,7 @@ pub async fn get_local_mod(
 #[tauri::command]
 pub async fn refresh_remote_db(handle: tauri::AppHandle, state: tauri::State<'_, State>) -> Result {
     let conf = state.config.read().await;
-    let new_db = RemoteDatabase::fetch(&conf.database_url).await;
+    let new_db = RemoteDatabase::fetch_or_cached(&conf).await;
 
     let first_load = {
         let mut remote_db = state.remote_db.write().await;
@@ -287,6 +298,31 @@ pub async fn get_remote_mod(
     }
 }
 
+/// Whether the remote database was fetched or loaded from the copy saved on disk
+#[typeshare]
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteDatabaseStatus {
+    /// Whether the database was loaded from the saved copy
+    pub offline: bool,
+    /// When the database was fetched, in seconds since the unix epoch
+    pub fetched_at: Option<u32>,
+}
+
+#[tauri::command]
+pub async fn get_remote_db_status(
+    state: tauri::State<'_, State>,
+) -> Result<Option<RemoteDatabaseStatus>> {
+    let remote_db = state.remote_db.read().await;
+    Ok(remote_db.get().map(|db| RemoteDatabaseStatus {
+        offline: db.offline,
+        fetched_at: db
+            .fetched_at
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as u32),
+    }))
+}
+
 #[tauri::command]
 pub async fn open_mod_folder(unique_name: &str, state: tauri::State<'_, State>) -> Result {
     let db = state.local_db.read().await;
@@ -295,15 +331,69 @@ pub async fn open_mod_folder(unique_name: &str, state: tauri::State<'_, State>)
     Ok(())
 }
 
+/// If `why` is an [EnabledDependentsError], ask the user whether the dependents should be changed as well.
+fn confirm_cascade(
+    window: &tauri::Window,
+    local_db: &LocalDatabase,
+    why: &anyhow::Error,
+    verb: &str,
+) -> bool {
+    let Some(err) = why.downcast_ref::<EnabledDependentsError>() else {
+        return false;
+    };
+    let name_of = |m: &str| {
+        local_db
+            .get_mod(m)
+            .map(|m| m.manifest.name.clone())
+            .unwrap_or(m.to_string())
+    };
+    window
+        .dialog()
+        .message(format!(
+            "{} is needed by these enabled mods:\n\n{}\n\n{verb} them too?",
+            name_of(&err.unique_name),
+            err.dependents
+                .iter()
+                .map(|d| format!("- {}", name_of(d)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            verb.to_string(),
+            "Cancel".to_string(),
+        ))
+        .title("Mod Is Needed")
+        .blocking_show()
+}
+
 #[tauri::command]
 pub async fn toggle_mod(
     unique_name: &str,
     enabled: bool,
     recursive: bool,
     state: tauri::State<'_, State>,
+    window: tauri::Window,
 ) -> Result<Vec<String>> {
     let db = state.local_db.read().await;
-    let show_warnings_for = owmods_core::toggle::toggle_mod(unique_name, &db, enabled, recursive)?;
+    let show_warnings_for = match owmods_core::toggle::toggle_mod(
+        unique_name,
+        &db,
+        enabled,
+        recursive,
+        DependentsMode::Refuse,
+    ) {
+        Err(why) if confirm_cascade(&window, &db, &why, "Disable") => {
+            owmods_core::toggle::toggle_mod(
+                unique_name,
+                &db,
+                enabled,
+                recursive,
+                DependentsMode::Cascade,
+            )?
+        }
+        res => res?,
+    };
     Ok(show_warnings_for)
 }
 
@@ -317,11 +407,35 @@ pub async fn toggle_all(enabled: bool, state: tauri::State<'_, State>) -> Result
             &local_db,
             enabled,
             false,
+            DependentsMode::Ignore,
         )?);
     }
     Ok(show_warnings_for)
 }
 
+fn format_install_plan(unique_name: &str, plan: &InstallPlan) -> String {
+    let deps = plan
+        .mods
+        .iter()
+        .filter(|m| m.unique_name != unique_name)
+        .map(|m| format!("- {} {}", m.name, m.version))
+        .collect::<Vec<_>>();
+    let mut msg = format!(
+        "The following mods will also be installed:\n{}\n\nTotal size: {:.1} MB",
+        deps.join("\n"),
+        plan.total_size as f64 / (1024.0 * 1024.0)
+    );
+    if !plan.problems.is_empty() {
+        let problems = plan
+            .problems
+            .iter()
+            .map(|p| format!("- {p}"))
+            .collect::<Vec<_>>();
+        msg.push_str(&format!("\n\nWarnings:\n{}", problems.join("\n")));
+    }
+    msg
+}
+
 #[tauri::command]
 pub async fn install_mod(
     unique_name: &str,
@@ -351,19 +465,41 @@ pub async fn install_mod(
             .title("Reinstall?")
             .blocking_show();
     }
-    let res = if should_install {
-        install_mod_from_db(
-            &unique_name.to_string(),
+    let mut prepared = None;
+    if should_install {
+        let res = prepare_install(
+            unique_name,
             &conf,
             remote_db,
             &local_db,
-            true,
             prerelease.unwrap_or(false),
         )
-        .await
-        .map(|_| ())
-    } else {
-        Ok(())
+        .await;
+        let plan = match res {
+            Ok(res) => &prepared.insert(res).plan,
+            Err(why) => {
+                mark_mod_busy(unique_name, false, true, &state, &handle).await;
+                return Err(why.into());
+            }
+        };
+        if !plan.dependencies().is_empty() || !plan.problems.is_empty() {
+            should_install = window
+                .dialog()
+                .message(format_install_plan(unique_name, plan))
+                .kind(MessageDialogKind::Info)
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Install".to_string(),
+                    "Cancel".to_string(),
+                ))
+                .title("Install Dependencies?")
+                .blocking_show();
+        }
+    }
+    let res = match prepared {
+        Some(prepared) if should_install => install_prepared(prepared, &conf, &local_db)
+            .await
+            .map(|_| ()),
+        _ => Ok(()),
     };
     mark_mod_busy(unique_name, false, true, &state, &handle).await;
     res?;
@@ -378,7 +514,8 @@ pub async fn install_url(
 ) -> Result {
     let conf = state.config.read().await.clone();
     let db = state.local_db.read().await.clone();
-    install_mod_from_url(url, None, &conf, &db).await?;
+    let (url, expected_hash) = split_url_hash(url);
+    install_mod_from_url(url, None, expected_hash, &conf, &db).await?;
 
     Ok(())
 }
@@ -402,16 +539,42 @@ pub async fn uninstall_mod(
     unique_name: &str,
     state: tauri::State<'_, State>,
     _handle: tauri::AppHandle,
+    window: tauri::Window,
 ) -> Result<Vec<String>> {
     let db = state.local_db.read().await;
     let local_mod = db
         .get_mod(unique_name)
         .with_context(|| format!("Mod {unique_name} not found"))?;
-    let warnings = remove_mod(local_mod, &db, false)?;
+    let warnings = match remove_mod(local_mod, &db, false, DependentsMode::Refuse) {
+        Err(why) if confirm_cascade(&window, &db, &why, "Uninstall") => {
+            remove_mod(local_mod, &db, false, DependentsMode::Cascade)?
+        }
+        res => res?,
+    };
 
     Ok(warnings)
 }
 
+#[tauri::command]
+pub async fn get_orphaned_mods(state: tauri::State<'_, State>) -> Result<Vec<String>> {
+    let config = state.config.read().await.clone();
+    let db = state.local_db.read().await;
+    let orphans = find_orphans(&config, &db)?;
+    Ok(orphans.iter().map(|m| m.manifest.name.clone()).collect())
+}
+
+#[tauri::command]
+pub async fn autoremove_mods(state: tauri::State<'_, State>) -> Result<Vec<String>> {
+    let config = state.config.read().await.clone();
+    let db = state.local_db.read().await;
+    let removed = autoremove(&config, &db)?;
+    Ok(removed
+        .into_iter()
+        .filter(|m| m.manifest.patcher.is_some())
+        .map(|m| m.manifest.name)
+        .collect())
+}
+
 #[tauri::command]
 pub async fn uninstall_broken_mod(mod_path: &str, state: tauri::State<'_, State>) -> Result {
     let db = state.local_db.read().await;
@@ -577,13 +740,13 @@ pub async fn get_updatable_mods(
 
         for local_mod in mods {
             let (needs_update, _) = check_mod_needs_update(local_mod, remote_db);
-            if needs_update {
+            if needs_update && check_mod_held_back(local_mod, remote_db, &config).is_none() {
                 updates.push(local_mod.manifest.unique_name.clone());
             }
         }
         if let Some(owml) = LocalDatabase::get_owml(&config.owml_path) {
             let (needs_update, _) = check_mod_needs_update(&owml, remote_db);
-            if needs_update {
+            if needs_update && check_mod_held_back(&owml, remote_db, &config).is_none() {
                 updates.push(OWML_UNIQUE_NAME.to_string());
             }
         }
@@ -614,7 +777,19 @@ pub async fn update_mod(
     };
 
     let res = if unique_name == OWML_UNIQUE_NAME {
-        download_and_install_owml(&config, remote_mod, false).await
+        let pin = LocalDatabase::get_owml(&config.owml_path).and_then(|owml| {
+            check_pin(
+                &config,
+                OWML_UNIQUE_NAME,
+                &owml.manifest.version,
+                &remote_mod.version,
+            )
+        });
+        if let Some(pin) = pin {
+            Err(anyhow!("OWML is pinned to {pin}, unpin it to update"))
+        } else {
+            download_and_install_owml(&config, remote_mod, false).await
+        }
     } else {
         install_mod_from_db(
             &unique_name.to_string(),
@@ -643,10 +818,19 @@ pub async fn update_all_mods(
     let remote_db = state.remote_db.read().await.clone();
     let remote_db = remote_db.try_get()?;
     let mut busy_mods = state.mods_in_progress.write().await;
-    let owml_in_list = unique_names.contains(&OWML_UNIQUE_NAME.to_string());
+    let owml = LocalDatabase::get_owml(&config.owml_path);
+    let owml_in_list = unique_names.contains(&OWML_UNIQUE_NAME.to_string())
+        && owml
+            .as_ref()
+            .is_none_or(|owml| check_mod_held_back(owml, remote_db, &config).is_none());
     let unique_names: Vec<String> = unique_names
         .iter()
         .filter(|m| !busy_mods.contains(m) && m != &&OWML_UNIQUE_NAME.to_string())
+        .filter(|m| {
+            local_db.get_mod(m).is_none_or(|local_mod| {
+                check_mod_held_back(local_mod, remote_db, &config).is_none()
+            })
+        })
         .cloned()
         .collect();
     busy_mods.extend(unique_names.clone());
@@ -688,24 +872,96 @@ pub async fn update_all_mods(
 }
 
 #[tauri::command]
-pub async fn start_logs(state: tauri::State<'_, State>, handle: tauri::AppHandle) -> Result {
-    let game_logs = state.game_log.read().await;
-    let gui_config = state.gui_config.read().await;
+pub async fn get_held_update(
+    unique_name: &str,
+    state: tauri::State<'_, State>,
+) -> Result<Option<String>> {
+    let config = state.config.read().await.clone();
+    let remote_db = state.remote_db.read().await.clone();
+    let Some(remote_db) = remote_db.get() else {
+        return Ok(None);
+    };
+    let local_mod = if unique_name == OWML_UNIQUE_NAME {
+        LocalDatabase::get_owml(&config.owml_path)
+    } else {
+        state.local_db.read().await.get_mod(unique_name).cloned()
+    };
+    Ok(local_mod.and_then(|local_mod| {
+        check_mod_held_back(&local_mod, remote_db, &config).map(|r| r.version.clone())
+    }))
+}
+
+#[tauri::command]
+pub async fn get_install_plan(
+    unique_name: &str,
+    prerelease: Option<bool>,
+    state: tauri::State<'_, State>,
+) -> Result<InstallPlan> {
     let config = state.config.read().await.clone();
+    let local_db = state.local_db.read().await;
+    let remote_db = state.remote_db.read().await;
+    let remote_db = remote_db.try_get()?;
+    Ok(resolve_install(
+        &[unique_name.to_string()],
+        &config,
+        remote_db,
+        &local_db,
+        prerelease.unwrap_or(false),
+    ))
+}
+
+// Run the pre-launch checks and show any mod warnings that haven't been shown yet, the same as the log window does before launching
+async fn prepare_launch(
+    state: &tauri::State<'_, State>,
+    window: &tauri::Window,
+    handle: &tauri::AppHandle,
+) -> Result<Config> {
+    let config = state.config.read().await.clone();
+    let local_db = state.local_db.read().await;
+    let remote_db = state.remote_db.read().await;
+    let report = run_prelaunch_checks(&config, &local_db, remote_db.get());
+    let new_config = show_warnings(window, &report, &config)?;
+
+    new_config.save()?;
+    {
+        let mut config = state.config.write().await;
+        *config = new_config.clone();
+    }
+    handle.typed_emit_all(&Event::ConfigReload(())).ok();
+    Ok(new_config)
+}
+
+#[tauri::command]
+pub async fn start_logs(
+    state: tauri::State<'_, State>,
+    window: tauri::Window,
+    handle: tauri::AppHandle,
+) -> Result {
+    // Recorded sessions also have ports, but there's no log server listening on them
+    let live_port = state
+        .game_log
+        .read()
+        .await
+        .iter()
+        .find(|(_, log_data)| log_data.is_live())
+        .map(|(port, _)| *port);
+    let gui_config = state.gui_config.read().await;
     if gui_config.no_log_server {
         drop(gui_config);
+        let config = prepare_launch(&state, &window, &handle).await?;
         launch_game(&config, true, None).await?;
         return Ok(());
-    } else if gui_config.log_multi_window || game_logs.keys().count() == 0 {
-        drop(game_logs);
-        drop(gui_config);
-        make_log_window(&handle).await?;
-    } else {
-        drop(gui_config);
-        let config = state.config.read().await.clone();
-        let port = *game_logs.keys().next().unwrap_or(&0);
-        drop(game_logs);
-        launch_game(&config, false, Some(&port)).await?;
+    }
+    match live_port {
+        Some(port) if !gui_config.log_multi_window => {
+            drop(gui_config);
+            let config = prepare_launch(&state, &window, &handle).await?;
+            launch_game(&config, false, Some(&port)).await?;
+        }
+        _ => {
+            drop(gui_config);
+            make_log_window(&handle, None).await?;
+        }
     }
     Ok(())
 }
@@ -721,25 +977,17 @@ pub async fn run_game(
     window: tauri::Window,
     handle: tauri::AppHandle,
 ) -> Result {
-    let config = state.config.read().await.clone();
-    {
-        let local_db = state.local_db.read().await;
-        let new_config = show_warnings(&window, &local_db, &config)?;
+    let config = prepare_launch(&state, &window, &handle).await?;
 
-        new_config.save()?;
-        {
-            let mut config = state.config.write().await;
-            *config = new_config;
-        }
-        handle.typed_emit_all(&Event::ConfigReload(())).ok();
-    }
-
-    let log_server = LogServer::new(0).await?;
+    let mut log_server = LogServer::new(0).await?;
+    log_server.overflow_policy = config.log_overflow_policy;
     let port = log_server.port;
 
     {
+        let mut log_data = LogData::new(port, &handle)?;
+        log_data.set_launch_db(state.local_db.read().await.clone());
         let mut game_log = state.game_log.write().await;
-        game_log.insert(port, LogData::new(port, &handle)?);
+        game_log.insert(port, log_data);
     }
 
     let close_handle = handle.clone();
@@ -759,11 +1007,20 @@ pub async fn run_game(
 
     let (tx, mut rx) = mpsc::channel(32);
 
+    let mut recorder = SessionRecorder::start(&config)
+        .map_err(|e| error!("Couldn't start recording logs: {e:?}"))
+        .ok()
+        .flatten();
+
     let log_handler = async {
         loop {
             select! {
                 msg = rx.recv() => {
                     if let Some(msg) = msg {
+                        if let Some(why) = recorder.as_mut().and_then(|r| r.record(&msg).err()) {
+                            error!("Couldn't record log, no more logs will be recorded this session: {why:?}");
+                            recorder = None;
+                        }
                         let mut game_log = state.game_log.write().await;
                         if let Some(log_data) = game_log.get_mut(&port) {
                             log_data.take_message(msg);
@@ -792,6 +1049,65 @@ pub async fn run_game(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_log_sessions(state: tauri::State<'_, State>) -> Result<Vec<LogSessionInfo>> {
+    let config = state.config.read().await.clone();
+    let sessions = list_sessions(&config)?;
+    Ok(sessions.iter().map(LogSessionInfo::from).collect())
+}
+
+#[tauri::command]
+pub async fn show_log_session(id: &str, handle: tauri::AppHandle) -> Result {
+    make_log_window(&handle, Some(id)).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn open_log_session(
+    id: &str,
+    state: tauri::State<'_, State>,
+    window: tauri::Window,
+    handle: tauri::AppHandle,
+) -> Result {
+    let config = state.config.read().await.clone();
+    // Sessions shared by other people are opened by their path instead of an ID
+    let path = PathBuf::from(id);
+    let recorded = if path.is_file() {
+        read_session_file(&path)?
+    } else {
+        get_session(&config, id)?.read_messages()?
+    };
+
+    // Recorded sessions don't have a log server, so give them a port below 1024 as those are never
+    // assigned to servers we start
+    let port = {
+        let mut game_log = state.game_log.write().await;
+        let port = (1..1024)
+            .find(|p| !game_log.contains_key(p))
+            .ok_or_else(|| anyhow!("Too many log sessions open"))?;
+        let mut log_data = LogData::new(port, &handle)?;
+        log_data.load_recorded(recorded);
+        game_log.insert(port, log_data);
+        port
+    };
+
+    let close_handle = handle.clone();
+
+    window.on_window_event(move |e| {
+        if let WindowEvent::CloseRequested { .. } = e {
+            let handle = close_handle.clone();
+            async_runtime::spawn(async move {
+                let state = handle.state::<State>();
+                let mut logs = state.game_log.write().await;
+                logs.remove(&port);
+            });
+        }
+    });
+
+    window.typed_emit(&Event::GameStart(port)).ok();
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn clear_logs(port: LogPort, state: tauri::State<'_, State>) -> Result {
     let mut data = state.game_log.write().await;
@@ -818,6 +1134,38 @@ pub async fn get_log_lines(
     }
 }
 
+#[tauri::command]
+pub async fn get_exception_counts(
+    port: LogPort,
+    state: tauri::State<'_, State>,
+) -> Result<Vec<ExceptionCount>> {
+    let logs = state.game_log.read().await;
+    if let Some(log_data) = logs.get(&port) {
+        Ok(log_data.get_exception_counts())
+    } else {
+        Err(Error(anyhow!("Log Server Not Running")))
+    }
+}
+
+#[tauri::command]
+pub async fn get_session_report(
+    port: LogPort,
+    state: tauri::State<'_, State>,
+) -> Result<SessionReport> {
+    let logs = state.game_log.read().await;
+    if let Some(log_data) = logs.get(&port) {
+        let local_db = state.local_db.read().await;
+        Ok(log_data.get_session_report(&local_db))
+    } else {
+        Err(Error(anyhow!("Log Server Not Running")))
+    }
+}
+
+#[tauri::command]
+pub async fn get_game_path_candidates() -> Result<Vec<GamePathCandidate>> {
+    Ok(find_game_paths())
+}
+
 #[tauri::command]
 pub async fn get_game_message(
     port: LogPort,
@@ -874,6 +1222,45 @@ pub async fn import_mods(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_profiles(state: tauri::State<'_, State>) -> Result<Vec<Profile>> {
+    let config = state.config.read().await;
+    let profiles = list_profiles(&config)?;
+    Ok(profiles)
+}
+
+#[tauri::command]
+pub async fn save_profile(
+    name: &str,
+    include_settings: bool,
+    state: tauri::State<'_, State>,
+) -> Result {
+    let config = state.config.read().await;
+    let local_db = state.local_db.read().await;
+    let profile = Profile::from_local_db(name, &local_db, include_settings)?;
+    profile.save(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn apply_profile(name: &str, state: tauri::State<'_, State>) -> Result<Vec<String>> {
+    let config = state.config.read().await;
+    let local_db = state.local_db.read().await;
+    let remote_db = state.remote_db.read().await;
+    let remote_db = remote_db.try_get()?;
+    let profile = Profile::load(name, &config)?;
+    let show_warnings_for =
+        owmods_core::profiles::apply_profile(&profile, &config, &local_db, remote_db).await?;
+    Ok(show_warnings_for)
+}
+
+#[tauri::command]
+pub async fn delete_profile(name: &str, state: tauri::State<'_, State>) -> Result {
+    let config = state.config.read().await;
+    owmods_core::profiles::delete_profile(name, &config)?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn fix_mod_deps(
     unique_name: &str,
@@ -896,45 +1283,61 @@ pub async fn fix_mod_deps(
 }
 
 #[tauri::command]
-pub async fn db_has_issues(state: tauri::State<'_, State>, window: tauri::Window) -> Result<bool> {
+pub async fn rollback_mod(
+    unique_name: &str,
+    state: tauri::State<'_, State>,
+    handle: tauri::AppHandle,
+) -> Result {
+    let config = state.config.read().await.clone();
+    let local_db = state.local_db.read().await.clone();
+
+    mark_mod_busy(unique_name, true, true, &state, &handle).await;
+    let res = rollback(&config, &local_db, unique_name);
+    mark_mod_busy(unique_name, false, true, &state, &handle).await;
+    res?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn check_before_launch(
+    state: tauri::State<'_, State>,
+    window: tauri::Window,
+) -> Result<PrelaunchReport> {
     let local_db = state.local_db.read().await.clone();
     let config = state.config.read().await.clone();
-    let mut has_errors =
-        local_db.active().any(|m| !m.errors.is_empty()) || local_db.invalid().count() > 0;
+    let remote_db = state.remote_db.read().await.clone();
+    let mut report = run_prelaunch_checks(&config, &local_db, remote_db.get());
 
-    let owml = LocalDatabase::get_owml(&config.owml_path);
-    if let Some(owml) = owml {
-        let remote_db = state.remote_db.read().await.clone();
-        if let Some(remote_db) = remote_db.get() {
-            let (needs_update, remote_owml) = check_mod_needs_update(&owml, remote_db);
-            if needs_update {
-                let answer = window
-                    .dialog()
-                    .message(format!(
-                        "OWML is out of date, update it? (You have {} installed)",
-                        owml.manifest.version
-                    ))
-                    .kind(MessageDialogKind::Info)
-                    .buttons(MessageDialogButtons::OkCancelCustom(
-                        "Yes".to_string(),
-                        "No".to_string(),
-                    ))
-                    .title("Update OWML?")
-                    .blocking_show();
-                if answer {
-                    let handle = window.app_handle();
-                    mark_mod_busy(OWML_UNIQUE_NAME, true, true, &state, handle).await;
-                    download_and_install_owml(&config, remote_owml.unwrap(), false).await?;
-                    mark_mod_busy(OWML_UNIQUE_NAME, false, true, &state, handle).await;
-                    let event = Event::RequestReload("LOCAL".to_string());
-                    handle.typed_emit_all(&event).unwrap();
-                } else {
-                    has_errors = true;
-                }
-            }
+    let outdated_owml = report.findings.iter().find_map(|f| match &f.check {
+        PrelaunchCheck::OutdatedOwml { installed, .. } => Some(installed.clone()),
+        _ => None,
+    });
+    if let (Some(installed), Some(remote_owml)) =
+        (outdated_owml, remote_db.get().and_then(|db| db.get_owml()))
+    {
+        let answer = window
+            .dialog()
+            .message(format!(
+                "OWML is out of date, update it? (You have {installed} installed)"
+            ))
+            .kind(MessageDialogKind::Info)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Yes".to_string(),
+                "No".to_string(),
+            ))
+            .title("Update OWML?")
+            .blocking_show();
+        if answer {
+            let handle = window.app_handle();
+            mark_mod_busy(OWML_UNIQUE_NAME, true, true, &state, handle).await;
+            download_and_install_owml(&config, remote_owml, false).await?;
+            mark_mod_busy(OWML_UNIQUE_NAME, false, true, &state, handle).await;
+            let event = Event::RequestReload("LOCAL".to_string());
+            handle.typed_emit_all(&event).unwrap();
+            report = run_prelaunch_checks(&config, &local_db, remote_db.get());
         }
     }
-    Ok(has_errors)
+    Ok(report)
 }
 
 #[tauri::command]
@@ -1135,6 +1538,6 @@ pub async fn open_mod_github(unique_name: &str, state: tauri::State<'_, State>)
 #[tauri::command]
 pub async fn show_log_folder() -> Result {
     let path = get_app_path()?;
-    opener::open(path.join("logs")).ok();
+    opener::open(path.join(MANAGER_LOGS_FOLDER_NAME)).ok();
     Ok(())
 }
@@ -1,18 +1,24 @@
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{
+    cmp::Reverse,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Result;
 use log::error;
 use owmods_core::{
-    alerts::get_warnings,
     config::Config,
     db::LocalDatabase,
-    search::matches_query,
+    diagnostics::{analyze_session, SessionReport},
+    exceptions::LogException,
+    log_query::LogQuery,
+    prelaunch::PrelaunchReport,
+    sessions::{LogSession, RecordedMessage},
     socket::{SocketMessage, SocketMessageType},
 };
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, WebviewWindow, WebviewWindowBuilder, Window};
 use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
-use time::{macros::format_description, OffsetDateTime};
+use time::{macros::format_description, OffsetDateTime, UtcOffset};
 use typeshare::typeshare;
 
 use crate::{
@@ -27,25 +33,97 @@ pub struct GameMessage {
     pub message: SocketMessage,
     pub amount: u32,
     pub timestamp: String,
+    pub exception: Option<LogException>,
 }
 
 impl GameMessage {
-    fn get_timestamp() -> String {
-        let now = OffsetDateTime::now_local().unwrap_or(OffsetDateTime::now_utc());
-        now.format(format_description!(
+    fn format_timestamp(time: OffsetDateTime) -> String {
+        time.format(format_description!(
             "[hour repr:12]:[minute]:[second] [period] (UTC[offset_hour sign:mandatory])"
         ))
         .unwrap_or("Unknown".to_string())
     }
 
+    fn get_timestamp() -> String {
+        let now = OffsetDateTime::now_local().unwrap_or(OffsetDateTime::now_utc());
+        Self::format_timestamp(now)
+    }
+
     pub fn new(port: LogPort, message: SocketMessage) -> Self {
         Self {
             port,
+            exception: LogException::parse(&message),
             message,
             amount: 1,
             timestamp: Self::get_timestamp(),
         }
     }
+
+    // When the game sends an exception's stack trace as separate messages, they're added to the exception's message
+    fn try_merge(&mut self, message: &SocketMessage) -> bool {
+        let merged = self
+            .exception
+            .as_mut()
+            .is_some_and(|exception| exception.extend(message));
+        if merged {
+            self.message.message.push('\n');
+            self.message.message.push_str(&message.message);
+        }
+        merged
+    }
+
+    pub fn from_recorded(port: LogPort, recorded: RecordedMessage) -> Self {
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let timestamp =
+            OffsetDateTime::from_unix_timestamp_nanos(recorded.timestamp as i128 * 1_000_000)
+                .map(|t| Self::format_timestamp(t.to_offset(offset)))
+                .unwrap_or("Unknown".to_string());
+        Self {
+            port,
+            exception: LogException::parse(&recorded.message),
+            message: recorded.message,
+            amount: 1,
+            timestamp,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionCount {
+    pub source: String,
+    pub count: u32,
+}
+
+#[typeshare]
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSessionInfo {
+    pub id: String,
+    pub started: String,
+    pub size_kb: u32,
+}
+
+impl From<&LogSession> for LogSessionInfo {
+    fn from(session: &LogSession) -> Self {
+        let started =
+            OffsetDateTime::from_unix_timestamp_nanos(session.started as i128 * 1_000_000)
+                .map(|t| {
+                    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+                    t.to_offset(offset)
+                        .format(format_description!(
+                            "[year]-[month]-[day] [hour repr:12]:[minute] [period]"
+                        ))
+                        .unwrap_or("Unknown".to_string())
+                })
+                .unwrap_or("Unknown".to_string());
+        Self {
+            id: session.id.clone(),
+            started,
+            size_kb: (session.size / 1024) as u32,
+        }
+    }
 }
 
 pub struct LogData {
@@ -54,12 +132,17 @@ pub struct LogData {
     indices: Vec<usize>,
     active_filter: Option<SocketMessageType>,
     active_search: String,
+    active_query: LogQuery,
     app_handle: AppHandle,
     message_tracker: (u32, Instant),
     // If the usize is None, it's a regular update
     // If it's Some, it's a count update
     // Fatal emits always happen instantly
     queued_emits: Vec<Option<u32>>,
+    // The mods that were installed when the game was launched, used for session reports
+    launch_db: Option<LocalDatabase>,
+    // Whether this is a recorded session, which has no log server behind its port
+    recorded: bool,
 }
 
 impl LogData {
@@ -73,12 +156,24 @@ impl LogData {
             indices: vec![],
             active_filter: None,
             active_search: String::new(),
+            active_query: LogQuery::default(),
             app_handle: handle.clone(),
             message_tracker: (0, Instant::now()),
             queued_emits: vec![],
+            launch_db: None,
+            recorded: false,
         })
     }
 
+    pub fn set_launch_db(&mut self, local_db: LocalDatabase) {
+        self.launch_db = Some(local_db);
+    }
+
+    /// Whether a log server is listening on this port, recorded sessions only use it as an ID
+    pub fn is_live(&self) -> bool {
+        !self.recorded
+    }
+
     fn emit_update(&self) {
         let res = self.app_handle.typed_emit_all(&Event::LogUpdate(self.port));
         if let Err(why) = res {
@@ -146,6 +241,9 @@ impl LogData {
         if self.active_filter != active_filter || self.active_search != *active_search {
             self.active_filter = active_filter;
             self.active_search = active_search.to_string();
+            // While a query is being typed it might not parse yet, so just search for the text
+            self.active_query =
+                LogQuery::parse(active_search).unwrap_or_else(|_| LogQuery::text(active_search));
             self.eval_indices();
         } else if self.indices.is_empty() {
             self.eval_indices();
@@ -161,30 +259,31 @@ impl LogData {
         self.messages.iter().map(|msg| msg.amount).sum()
     }
 
+    fn is_visible(&self, msg: &GameMessage) -> bool {
+        self.active_filter
+            .as_ref()
+            .is_none_or(|filter| msg.message.message_type == *filter)
+            && self.active_query.matches(&msg.message)
+    }
+
     fn eval_indices(&mut self) {
         self.indices = self
             .messages
             .iter()
             .enumerate()
-            .filter(|(_, msg)| {
-                if let Some(filter) = self.active_filter.as_ref() {
-                    msg.message.message_type == *filter
-                } else {
-                    true
-                }
-            })
-            .filter(|(_, msg)| {
-                if !self.active_search.is_empty() {
-                    matches_query(&msg.message, &self.active_search)
-                } else {
-                    true
-                }
-            })
+            .filter(|(_, msg)| self.is_visible(msg))
             .map(|(i, _)| i)
             .collect();
     }
 
     pub fn take_message(&mut self, msg: SocketMessage) {
+        if let Some(last) = self.messages.last_mut() {
+            if last.try_merge(&msg) {
+                self.emit_count_update((self.messages.len() - 1) as u32);
+                self.emit_update();
+                return;
+            }
+        }
         let msg = GameMessage::new(self.port, msg);
         // Reset the message tracker every second
         if self.message_tracker.1.elapsed().as_secs_f32() > 1.0 {
@@ -211,11 +310,7 @@ impl LogData {
         if msg.message.message_type == SocketMessageType::Fatal {
             self.emit_fatal_alert(&msg);
         }
-        let msg_type = msg.message.message_type.clone();
-        if (self.active_filter.is_none() && self.active_search.is_empty())
-            || (self.active_filter == Some(msg_type)
-                && matches_query(&msg.message, &self.active_search))
-        {
+        if self.is_visible(&msg) {
             self.indices.push(self.messages.len());
         }
         self.messages.push(msg);
@@ -235,6 +330,56 @@ impl LogData {
         }
     }
 
+    // Repeated messages are collapsed just like they are when received live
+    pub fn load_recorded(&mut self, recorded: Vec<RecordedMessage>) {
+        self.recorded = true;
+        for recorded in recorded {
+            if let Some(last) = self.messages.last_mut() {
+                if last.try_merge(&recorded.message) {
+                    continue;
+                }
+            }
+            let msg = GameMessage::from_recorded(self.port, recorded);
+            match self.messages.last_mut() {
+                Some(last) if last.message == msg.message => {
+                    last.amount = last.amount.saturating_add(1);
+                }
+                _ => self.messages.push(msg),
+            }
+        }
+        self.eval_indices();
+        self.emit_update();
+    }
+
+    pub fn get_exception_counts(&self) -> Vec<ExceptionCount> {
+        let mut counts: Vec<ExceptionCount> = vec![];
+        for (exception, amount) in self
+            .messages
+            .iter()
+            .filter_map(|msg| Some((msg.exception.as_ref()?, msg.amount)))
+        {
+            let source = exception.source_name();
+            match counts.iter_mut().find(|c| c.source == source) {
+                Some(count) => count.count = count.count.saturating_add(amount),
+                None => counts.push(ExceptionCount {
+                    source: source.to_string(),
+                    count: amount,
+                }),
+            }
+        }
+        counts.sort_by_key(|c| Reverse(c.count));
+        counts
+    }
+
+    // Recorded sessions don't know what was installed at launch, so they fall back to what's installed now
+    pub fn get_session_report(&self, local_db: &LocalDatabase) -> SessionReport {
+        let messages = self
+            .messages
+            .iter()
+            .flat_map(|msg| std::iter::repeat_n(&msg.message, msg.amount as usize));
+        analyze_session(messages, self.launch_db.as_ref().unwrap_or(local_db))
+    }
+
     pub fn clear(&mut self) {
         // First make the UI not render any rows to avoid errors
         self.indices.clear();
@@ -245,15 +390,24 @@ impl LogData {
     }
 }
 
-pub async fn make_log_window(handle: &AppHandle) -> Result<WebviewWindow> {
+pub async fn make_log_window(handle: &AppHandle, session: Option<&str>) -> Result<WebviewWindow> {
     let epoch = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis();
+    let url = match session {
+        Some(session) => {
+            // Sessions can be paths to files, so they need to be encoded
+            let mut url = tauri::Url::parse("tauri://localhost/logs/index.html")?;
+            url.query_pairs_mut().append_pair("session", session);
+            format!("{}?{}", url.path(), url.query().unwrap_or_default())
+        }
+        None => "/logs/index.html".to_string(),
+    };
     let log_window = WebviewWindowBuilder::new(
         handle,
         format!("game-{epoch}"),
-        tauri::WebviewUrl::App("/logs/index.html".parse()?),
+        tauri::WebviewUrl::App(url.parse()?),
     );
     let window = log_window
         .center()
@@ -264,13 +418,9 @@ pub async fn make_log_window(handle: &AppHandle) -> Result<WebviewWindow> {
     Ok(window)
 }
 
-pub fn show_warnings(window: &Window, local_db: &LocalDatabase, config: &Config) -> Result<Config> {
-    let warnings = get_warnings(
-        local_db.active().collect(),
-        config.viewed_alerts.iter().map(|s| s.as_str()).collect(),
-    );
+pub fn show_warnings(window: &Window, report: &PrelaunchReport, config: &Config) -> Result<Config> {
     let mut config = config.clone();
-    for (unique_name, warning) in warnings {
+    for (_, warning) in report.unseen_warnings() {
         //::message(Some(window), &warning.title, &warning.body);
         window
             .dialog()
@@ -279,7 +429,7 @@ pub fn show_warnings(window: &Window, local_db: &LocalDatabase, config: &Config)
             .kind(MessageDialogKind::Warning)
             .title(&warning.title)
             .blocking_show();
-        config.set_warning_shown(unique_name);
     }
+    report.mark_warnings_shown(&mut config);
     Ok(config)
 }
@@ -7,6 +7,7 @@ use std::{
 use crate::format_description;
 use anyhow::Result;
 use log::{warn, Level, STATIC_MAX_LEVEL};
+use owmods_core::constants::MANAGER_LOGS_FOLDER_NAME;
 use owmods_core::file::get_app_path;
 use owmods_core::progress::ProgressPayload;
 use serde::Serialize;
@@ -41,7 +42,7 @@ impl Logger {
             let now = OffsetDateTime::now_utc();
             let logs_path = get_app_path()
                 .expect("Couldn't Make Log File")
-                .join("logs")
+                .join(MANAGER_LOGS_FOLDER_NAME)
                 .join(
                     now.format(format_description!("[year]-[month]-[day]"))
                         .unwrap(),
@@ -293,7 +293,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             export_mods,
             import_mods,
             fix_mod_deps,
-            db_has_issues,
+            check_before_launch,
             get_alert,
             dismiss_alert,
             pop_protocol_url,
@@ -310,7 +310,23 @@ fn main() -> Result<(), Box<dyn Error>> {
             get_db_tags,
             open_mod_github,
             force_log_update,
-            show_log_folder
+            show_log_folder,
+            get_profiles,
+            save_profile,
+            apply_profile,
+            delete_profile,
+            get_remote_db_status,
+            rollback_mod,
+            get_held_update,
+            get_install_plan,
+            get_orphaned_mods,
+            autoremove_mods,
+            get_log_sessions,
+            show_log_session,
+            open_log_session,
+            get_exception_counts,
+            get_session_report,
+            get_game_path_candidates
         ])
         .run(tauri::generate_context!());
 
@@ -11,7 +11,15 @@ import {
     ProgressBars,
     ProgressBar,
     Event,
-    RemoteModOption
+    RemoteModOption,
+    Profile,
+    RemoteDatabaseStatus,
+    InstallPlan,
+    LogSessionInfo,
+    ExceptionCount,
+    SessionReport,
+    PrelaunchReport,
+    GamePathCandidate
 } from "@types";
 
 type CommandInfo<P, R> = [P, R];
@@ -51,6 +59,8 @@ const commandInfo = {
     openModReadme: $<ModAction>("open_mod_readme"),
     openOwml: $<EmptyCommand>("open_owml"),
     uninstallMod: $<ModCommand<string[]>>("uninstall_mod"),
+    getOrphanedMods: $<GetCommand<string[]>>("get_orphaned_mods"),
+    autoremoveMods: $<GetCommand<string[]>>("autoremove_mods"),
     uninstallBrokenMod: $<ActionCommand<{ modPath: string }>>("uninstall_broken_mod"),
     installMod: $<CommandInfo<{ uniqueName: string; prerelease?: boolean }, void>>("install_mod"),
     installUrl: $<ActionCommand<{ url: string }>>("install_url"),
@@ -78,8 +88,19 @@ const commandInfo = {
     >("get_log_lines"),
     exportMods: $<ActionCommand<{ path: string }>>("export_mods"),
     importMods: $<ActionCommand<{ path: string; disableMissing: boolean }>>("import_mods"),
+    getProfiles: $<GetCommand<Profile[]>>("get_profiles"),
+    saveProfile: $<ActionCommand<{ name: string; includeSettings: boolean }>>("save_profile"),
+    applyProfile: $<CommandInfo<{ name: string }, string[]>>("apply_profile"),
+    deleteProfile: $<ActionCommand<{ name: string }>>("delete_profile"),
+    getRemoteDbStatus: $<GetCommand<RemoteDatabaseStatus | null>>("get_remote_db_status"),
     fixDeps: $<ActionCommand<{ uniqueName: string }>>("fix_mod_deps"),
-    checkDBForIssues: $<GetCommand<boolean>>("db_has_issues"),
+    rollbackMod: $<ModAction>("rollback_mod"),
+    getHeldUpdate: $<ModCommand<string | null>>("get_held_update"),
+    getInstallPlan:
+        $<CommandInfo<{ uniqueName: string; prerelease?: boolean }, InstallPlan>>(
+            "get_install_plan"
+        ),
+    checkBeforeLaunch: $<GetCommand<PrelaunchReport>>("check_before_launch"),
     getAlert: $<GetCommand<Alert>>("get_alert"),
     dismissAlert: $<ActionCommand<{ alert: Alert }>>("dismiss_alert"),
     popProtocolURL: $<ActionCommand<{ id: string }>>("pop_protocol_url"),
@@ -94,6 +115,12 @@ const commandInfo = {
     getDbTags: $<GetCommand<string[]>>("get_db_tags"),
     logError: $<ActionCommand<{ err: string }>>("log_error"),
     forceLogUpdate: $<ActionCommand<{ port: number }>>("force_log_update"),
+    getLogSessions: $<GetCommand<LogSessionInfo[]>>("get_log_sessions"),
+    showLogSession: $<ActionCommand<{ id: string }>>("show_log_session"),
+    openLogSession: $<ActionCommand<{ id: string }>>("open_log_session"),
+    getExceptionCounts: $<CommandInfo<{ port: number }, ExceptionCount[]>>("get_exception_counts"),
+    getSessionReport: $<CommandInfo<{ port: number }, SessionReport>>("get_session_report"),
+    getGamePathCandidates: $<GetCommand<GamePathCandidate[]>>("get_game_path_candidates"),
     showLogsFolder: $<EmptyCommand>("show_log_folder")
 };
 
@@ -4,7 +4,7 @@ import BaseApp from "@components/common/BaseApp";
 import { TranslationMap } from "@components/common/TranslationContext";
 import { useGetTranslation } from "@hooks";
 import * as dialog from "@tauri-apps/plugin-dialog";
-import { SocketMessageType } from "@types";
+import { ExceptionCount, SocketMessageType } from "@types";
 import * as window from "@tauri-apps/api/window";
 import LogHeader from "./LogHeader";
 import { Container, useTheme } from "@mui/material";
@@ -31,6 +31,7 @@ const InnerLogApp = memo(function InnerLogApp({ port }: { port: number }) {
     const [logsBehind, setLogsBehind] = useState<boolean>(false);
     const [logLines, setLogLines] = useState<LogLines>([]);
     const [logTotal, setLogTotal] = useState<number>(0);
+    const [exceptionCounts, setExceptionCounts] = useState<ExceptionCount[]>([]);
     const forceLogUpdateTimeout = useRef<ReturnType<typeof setTimeout> | null>(null);
     const getTranslation = useGetTranslation();
     const theme = useTheme();
@@ -43,6 +44,10 @@ const InnerLogApp = memo(function InnerLogApp({ port }: { port: number }) {
                 setLogTotal(total);
             })
             .catch(() => null);
+        commands
+            .getExceptionCounts({ port })
+            .then(setExceptionCounts)
+            .catch(() => null);
     }, [activeFilter, activeSearch, port]);
 
     const onClear = useCallback(() => {
@@ -107,8 +112,10 @@ const InnerLogApp = memo(function InnerLogApp({ port }: { port: number }) {
             }}
         >
             <LogHeader
+                port={port}
                 onClear={onClear}
                 logsLen={logTotal}
+                exceptionCounts={exceptionCounts}
                 activeSearch={activeSearch}
                 setActiveSearch={setActiveSearch}
                 activeFilter={activeFilter}
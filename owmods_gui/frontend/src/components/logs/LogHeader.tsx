@@ -1,13 +1,17 @@
 import { useGetTranslation } from "@hooks";
 import { memo } from "react";
 import { LogFilter } from "./LogApp";
-import { Box, IconButton, Paper, Toolbar, Typography, useTheme } from "@mui/material";
-import { DeleteSweepRounded, WarningAmberRounded } from "@mui/icons-material";
+import { Box, Chip, IconButton, Paper, Toolbar, Typography, useTheme } from "@mui/material";
+import { BugReportRounded, DeleteSweepRounded, WarningAmberRounded } from "@mui/icons-material";
+import { ExceptionCount } from "@types";
 import ODTooltip from "@components/common/ODTooltip";
 import LogFilters from "./LogFilters";
+import SessionReport from "./SessionReport";
 
 export interface LogHeaderProps {
+    port: number;
     logsLen: number;
+    exceptionCounts: ExceptionCount[];
     activeFilter: LogFilter;
     setActiveFilter: (filter: LogFilter) => void;
     activeSearch: string;
@@ -43,6 +47,27 @@ const Counter = memo(function BehindWarning(props: { isBehind: boolean; count: n
     );
 });
 
+const ExceptionCounter = memo(function ExceptionCounter(props: { counts: ExceptionCount[] }) {
+    const getTranslation = useGetTranslation();
+
+    const total = props.counts.reduce((sum, c) => sum + c.count, 0);
+
+    if (total === 0) return null;
+
+    return (
+        <Box paddingLeft={2}>
+            <ODTooltip title={props.counts.map((c) => `${c.source}: ${c.count}`).join("\n")}>
+                <Chip
+                    color="error"
+                    variant="outlined"
+                    icon={<BugReportRounded />}
+                    label={getTranslation("EXCEPTION_COUNT", { count: total.toString() })}
+                />
+            </ODTooltip>
+        </Box>
+    );
+});
+
 const LogHeader = memo(function LogHeader(props: LogHeaderProps) {
     const getTranslation = useGetTranslation();
 
@@ -56,7 +81,9 @@ const LogHeader = memo(function LogHeader(props: LogHeaderProps) {
                     setActiveSearch={props.setActiveSearch}
                 />
                 <Counter isBehind={props.isBehind} count={props.logsLen} />
+                <ExceptionCounter counts={props.exceptionCounts} />
                 <Box paddingLeft={2}>
+                    <SessionReport port={props.port} />
                     <ODTooltip title={getTranslation("CLEAR_LOGS")}>
                         <IconButton onClick={props.onClear}>
                             <DeleteSweepRounded />
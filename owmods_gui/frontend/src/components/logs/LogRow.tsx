@@ -1,9 +1,20 @@
 import { hooks } from "@commands";
 import ODTooltip from "@components/common/ODTooltip";
 import StyledErrorBoundary from "@components/common/StyledErrorBoundary";
-import { Box, Chip, Palette, Skeleton, TableCell, Typography, useTheme } from "@mui/material";
-import { LogLineCountUpdatePayload, SocketMessageType } from "@types";
-import { Fragment, memo, useLayoutEffect, useMemo } from "react";
+import { useGetTranslation } from "@hooks";
+import { ExpandLessRounded, ExpandMoreRounded } from "@mui/icons-material";
+import {
+    Box,
+    Chip,
+    Collapse,
+    Palette,
+    Skeleton,
+    TableCell,
+    Typography,
+    useTheme
+} from "@mui/material";
+import { LogException, LogLineCountUpdatePayload, SocketMessageType } from "@types";
+import { Fragment, memo, useCallback, useLayoutEffect, useMemo, useState } from "react";
 
 export interface LogRowProps {
     port: number;
@@ -29,6 +40,59 @@ const getColor = (palette: Palette, messageType: SocketMessageType) => {
     }
 };
 
+const ExceptionText = memo(function ExceptionText(props: {
+    exception: LogException;
+    color: string;
+}) {
+    const theme = useTheme();
+    const getTranslation = useGetTranslation();
+    const [expanded, setExpanded] = useState(false);
+
+    const toggle = useCallback(() => {
+        setExpanded((e) => !e);
+    }, []);
+
+    const header =
+        props.exception.message.length === 0
+            ? props.exception.exceptionType
+            : `${props.exception.exceptionType}: ${props.exception.message}`;
+
+    return (
+        <>
+            <Typography whiteSpace="pre-wrap" minWidth={0} color={props.color}>
+                {header}
+            </Typography>
+            {props.exception.frames.length !== 0 && (
+                <>
+                    <Chip
+                        sx={{ marginTop: 0.5 }}
+                        size="small"
+                        variant="outlined"
+                        onClick={toggle}
+                        icon={expanded ? <ExpandLessRounded /> : <ExpandMoreRounded />}
+                        label={getTranslation("STACK_FRAMES", {
+                            count: props.exception.frames.length.toString()
+                        })}
+                    />
+                    <Collapse in={expanded}>
+                        {props.exception.frames.map((frame, i) => (
+                            <Typography
+                                key={`${i}-${frame.method}`}
+                                variant="body2"
+                                paddingLeft={2}
+                                color={theme.palette.text.secondary}
+                            >
+                                at {frame.method}
+                                {frame.location && ` (${frame.location})`}
+                            </Typography>
+                        ))}
+                    </Collapse>
+                </>
+            )}
+        </>
+    );
+});
+
 const InnerLogRow = memo(function LogRow(props: LogRowProps) {
     const theme = useTheme();
 
@@ -96,6 +160,11 @@ const InnerLogRow = memo(function LogRow(props: LogRowProps) {
                                 <Skeleton width={150} />
                                 <Skeleton width={102} />
                             </>
+                        ) : logLine?.exception ? (
+                            <ExceptionText
+                                exception={logLine.exception}
+                                color={getColor(theme.palette, messageType)}
+                            />
                         ) : (
                             <Typography
                                 whiteSpace="pre-wrap"
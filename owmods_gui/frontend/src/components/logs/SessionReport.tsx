@@ -0,0 +1,148 @@
+import { useGetTranslation } from "@hooks";
+import { memo, ReactNode, useCallback, useState } from "react";
+import { AssessmentRounded } from "@mui/icons-material";
+import {
+    Box,
+    Button,
+    Dialog,
+    DialogActions,
+    DialogContent,
+    DialogContentText,
+    DialogTitle,
+    IconButton,
+    List,
+    ListItem,
+    ListItemText,
+    Typography
+} from "@mui/material";
+import { commands } from "@commands";
+import { SessionReport as Report, SocketMessage } from "@types";
+import ODTooltip from "@components/common/ODTooltip";
+import { simpleOnError } from "../../errorHandling";
+
+const ReportSection = memo(function ReportSection(props: { title: string; children: ReactNode }) {
+    return (
+        <Box marginBottom={2}>
+            <Typography variant="subtitle1">{props.title}</Typography>
+            {props.children}
+        </Box>
+    );
+});
+
+const ReportMessage = memo(function ReportMessage(props: { message: SocketMessage }) {
+    return (
+        <Typography variant="body2" color="error" whiteSpace="pre-wrap" fontFamily="monospace">
+            [{props.message.senderName ?? "Unknown"}]: {props.message.message}
+        </Typography>
+    );
+});
+
+const SessionReport = memo(function SessionReport({ port }: { port: number }) {
+    const getTranslation = useGetTranslation();
+    const [report, setReport] = useState<Report | null>(null);
+
+    const onOpen = useCallback(() => {
+        commands.getSessionReport({ port }).then(setReport).catch(simpleOnError);
+    }, [port]);
+
+    const onClose = useCallback(() => {
+        setReport(null);
+    }, []);
+
+    // None is sent as null, so check for either
+    const hasProblems = !!report?.firstError || (report?.validationIssues.length ?? 0) > 0;
+
+    return (
+        <>
+            <ODTooltip title={getTranslation("SESSION_REPORT")}>
+                <IconButton onClick={onOpen}>
+                    <AssessmentRounded />
+                </IconButton>
+            </ODTooltip>
+            <Dialog fullWidth maxWidth="sm" open={report !== null} onClose={onClose}>
+                <DialogTitle>{getTranslation("SESSION_REPORT")}</DialogTitle>
+                <DialogContent dividers>
+                    {!report || !hasProblems ? (
+                        <DialogContentText>
+                            {getTranslation("SESSION_NO_PROBLEMS", {
+                                count: (report?.messageCount ?? 0).toString()
+                            })}
+                        </DialogContentText>
+                    ) : (
+                        <>
+                            {report.firstFatal && (
+                                <ReportSection title={getTranslation("FIRST_FATAL_ERROR")}>
+                                    <ReportMessage message={report.firstFatal} />
+                                </ReportSection>
+                            )}
+                            {report.firstError && (
+                                <ReportSection title={getTranslation("FIRST_ERROR")}>
+                                    <ReportMessage message={report.firstError} />
+                                </ReportSection>
+                            )}
+                            {report.suspects.length > 0 && (
+                                <ReportSection title={getTranslation("SUSPECTED_MODS")}>
+                                    <List dense disablePadding>
+                                        {report.suspects.map((suspect) => (
+                                            <ListItem key={suspect.uniqueName} disableGutters>
+                                                <ListItemText
+                                                    primary={suspect.name}
+                                                    secondary={suspect.reasons
+                                                        .map((r) => getTranslation(`SUSPECT_${r}`))
+                                                        .join(", ")}
+                                                />
+                                            </ListItem>
+                                        ))}
+                                    </List>
+                                </ReportSection>
+                            )}
+                            {report.modErrors.length > 0 && (
+                                <ReportSection title={getTranslation("ERRORS_PER_MOD")}>
+                                    <List dense disablePadding>
+                                        {report.modErrors.map((count) => (
+                                            <ListItem key={count.senderName} disableGutters>
+                                                <ListItemText
+                                                    primary={count.senderName}
+                                                    secondary={getTranslation("MOD_ERROR_COUNT", {
+                                                        errors: count.errors.toString(),
+                                                        fatal: count.fatal.toString(),
+                                                        exceptions: count.exceptions.toString()
+                                                    })}
+                                                />
+                                            </ListItem>
+                                        ))}
+                                    </List>
+                                </ReportSection>
+                            )}
+                            {report.validationIssues.length > 0 && (
+                                <ReportSection title={getTranslation("LAUNCH_ISSUES")}>
+                                    <List dense disablePadding>
+                                        {report.validationIssues.map((issues) => (
+                                            <ListItem key={issues.uniqueName} disableGutters>
+                                                <ListItemText
+                                                    primary={issues.name}
+                                                    secondary={issues.errors
+                                                        .map((e) =>
+                                                            getTranslation(e.errorType, {
+                                                                payload: e.payload ?? ""
+                                                            })
+                                                        )
+                                                        .join(", ")}
+                                                />
+                                            </ListItem>
+                                        ))}
+                                    </List>
+                                </ReportSection>
+                            )}
+                        </>
+                    )}
+                </DialogContent>
+                <DialogActions>
+                    <Button onClick={onClose}>{getTranslation("DISMISS")}</Button>
+                </DialogActions>
+            </Dialog>
+        </>
+    );
+});
+
+export default SessionReport;
@@ -64,6 +64,7 @@ export interface ModRowProps {
     children?: ReactNode;
     requiresDlc?: boolean;
     isOutdated?: boolean;
+    isPinned?: boolean;
     errorLevel?: "warn" | "err";
 }
 
@@ -215,7 +216,8 @@ const ModRow = memo(function GenericModRow(props: ModRowProps) {
                         <span>
                             {props.version}
                             <br />
-                            {props.isOutdated && getTranslation("OUTDATED")}
+                            {props.isOutdated &&
+                                getTranslation(props.isPinned ? "OUTDATED_PINNED" : "OUTDATED")}
                         </span>
                     }
                 />
@@ -4,7 +4,8 @@ import {
     DescriptionRounded,
     DownloadRounded,
     FolderRounded,
-    GitHub
+    GitHub,
+    HistoryRounded
 } from "@mui/icons-material";
 import { Checkbox, useTheme } from "@mui/material";
 import { memo, useRef } from "react";
@@ -28,6 +29,7 @@ export interface LocalModActionsProps {
     onGithub: () => void;
     onUninstall: () => void;
     onReinstall: () => void;
+    onRollback: () => void;
 }
 
 const LocalModActions = memo(function LocalModTools(props: LocalModActionsProps) {
@@ -106,6 +108,13 @@ const LocalModActions = memo(function LocalModTools(props: LocalModActionsProps)
                         />
                     </>
                 )}
+                <ModActionOverflowItem
+                    label={getTranslation("ROLLBACK")}
+                    icon={<HistoryRounded />}
+                    disabled={isBusy ?? true}
+                    onClick={props.onRollback}
+                    onClose={onClose}
+                />
                 <ModActionOverflowItem
                     label={getTranslation("UNINSTALL")}
                     icon={<DeleteRounded />}
@@ -58,6 +58,7 @@ const canFix = (mod?: UnsafeLocalMod): boolean => {
         (e) =>
             e.errorType === "MissingDep" ||
             e.errorType === "DisabledDep" ||
+            e.errorType === "UnsatisfiedDepVersion" ||
             e.errorType === "Outdated"
     );
 };
@@ -74,6 +75,9 @@ const LocalModRow = memo(function LocalModRow(props: LocalModRowProps) {
     // Fetch data
     const [status1, local] = hooks.getLocalMod("localRefresh", { ...props });
     const remoteOpt = hooks.getRemoteMod("remoteRefresh", { ...props })[1];
+    const heldUpdate = hooks.getHeldUpdate(["localRefresh", "remoteRefresh", "configReload"], {
+        uniqueName: props.uniqueName
+    })[1];
     const guiConfig = hooks.getGuiConfig("guiConfigReload")[1];
     const autoEnableDeps = guiConfig?.autoEnableDeps ?? false;
     const autoDisableDeps = guiConfig?.autoDisableDeps ?? false;
@@ -115,6 +119,14 @@ const LocalModRow = memo(function LocalModRow(props: LocalModRowProps) {
             })
             .catch(simpleOnError);
     }, [props.uniqueName]);
+    const onRollback = useCallback(() => {
+        commands
+            .rollbackMod({ uniqueName: props.uniqueName })
+            .then(() => {
+                commands.refreshLocalDb().catch(simpleOnError);
+            })
+            .catch(simpleOnError);
+    }, [props.uniqueName]);
     const onToggle = useCallback(
         (newVal: boolean) => {
             const task = async () => {
@@ -193,6 +205,7 @@ const LocalModRow = memo(function LocalModRow(props: LocalModRowProps) {
                 onUninstall={onUninstall}
                 onGithub={onGithub}
                 onReinstall={onReinstall}
+                onRollback={onRollback}
             />
         ),
         [
@@ -208,7 +221,8 @@ const LocalModRow = memo(function LocalModRow(props: LocalModRowProps) {
             onFolder,
             onUninstall,
             onGithub,
-            onReinstall
+            onReinstall,
+            onRollback
         ]
     );
 
@@ -224,7 +238,8 @@ const LocalModRow = memo(function LocalModRow(props: LocalModRowProps) {
             version={version}
             showThumbnail={props.showThumbnail}
             requiresDlc={remote?.tags?.includes("requires-dlc") ?? false}
-            isOutdated={outdated}
+            isOutdated={outdated || !!heldUpdate}
+            isPinned={!!heldUpdate}
             isLoading={status1 === "Loading" && local === null}
             remoteIsLoading={(remoteOpt?.type ?? "loading") === "loading"}
             description={
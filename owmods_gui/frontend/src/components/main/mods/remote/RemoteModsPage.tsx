@@ -2,9 +2,9 @@ import { commands, hooks } from "@commands";
 import { memo, useEffect, useMemo } from "react";
 import ModsPage from "../ModsPage";
 import RemoteModRow from "./RemoteModRow";
-import { Button } from "@mui/material";
+import { Button, Chip } from "@mui/material";
 import { useGetTranslation } from "@hooks";
-import { PublicRounded } from "@mui/icons-material";
+import { CloudOffRounded, PublicRounded } from "@mui/icons-material";
 import * as shell from "@tauri-apps/plugin-shell";
 import { useErrorBoundary } from "react-error-boundary";
 
@@ -40,6 +40,20 @@ const RemoteModsPage = memo(
             }
         );
 
+        const dbStatus = hooks.getRemoteDbStatus("remoteRefresh")[1];
+
+        const offlineChip = useMemo(() => {
+            if (!dbStatus?.offline || dbStatus.fetchedAt === undefined) return null;
+            const date = new Date(dbStatus.fetchedAt * 1000).toLocaleString();
+            return (
+                <Chip
+                    icon={<CloudOffRounded />}
+                    label={getTranslation("OFFLINE", { date })}
+                    variant="outlined"
+                />
+            );
+        }, [dbStatus, getTranslation]);
+
         const modsWebsiteButton = useMemo(
             () => (
                 <Button
@@ -70,6 +84,7 @@ const RemoteModsPage = memo(
                 hideTags={guiConfig?.hideDlc ? ["requires-dlc"] : []}
                 onSelectedTagsChanged={props.onTagsChanged}
             >
+                {offlineChip}
                 {modsWebsiteButton}
             </ModsPage>
         );
@@ -3,10 +3,37 @@ import { PlayArrow as PlayIcon } from "@mui/icons-material";
 import { useCallback, useEffect, useState } from "react";
 import { commands } from "@commands";
 import { useGetTranslation } from "@hooks";
+import { GamePathProblem, PrelaunchCheck, PrelaunchSeverity } from "@types";
 import * as dialog from "@tauri-apps/plugin-dialog";
 import { simpleOnError } from "../../../errorHandling";
 import { listen } from "@events";
 
+// Only checks that can be errors are listed when asking to launch anyway
+const describeCheck = (
+    check: PrelaunchCheck,
+    getTranslation: ReturnType<typeof useGetTranslation>
+): string | null => {
+    switch (check.checkType) {
+        case "InvalidMod":
+            return getTranslation("PRELAUNCH_INVALID_MOD", { name: check.payload.name });
+        case "Conflict":
+            return getTranslation("PRELAUNCH_CONFLICT", {
+                name: check.payload.uniqueName,
+                conflictsWith: check.payload.conflictsWith
+            });
+        case "MissingOwml":
+            return getTranslation("PRELAUNCH_MISSING_OWML");
+        case "InvalidGamePath":
+            return check.payload.problems.includes(GamePathProblem.OuterWorlds)
+                ? getTranslation("PRELAUNCH_OUTER_WORLDS")
+                : getTranslation("PRELAUNCH_INVALID_GAME_PATH", { path: check.payload.gamePath });
+        case "MissingMono":
+            return getTranslation("PRELAUNCH_MISSING_MONO");
+        default:
+            return null;
+    }
+};
+
 const StartGameButton = () => {
     const getTranslation = useGetTranslation();
     const [areLogsStarting, setLogsStarting] = useState(false);
@@ -19,10 +46,15 @@ const StartGameButton = () => {
                 .finally(() => setLogsStarting(false));
         setLogsStarting(true);
         const task = async () => {
-            const hasIssues = await commands.checkDBForIssues();
+            const report = await commands.checkBeforeLaunch();
             const skipWarning = (await commands.getGuiConfig()).noWarning;
-            if (!skipWarning && hasIssues) {
-                const yes = await dialog.ask(getTranslation("LAUNCH_ANYWAY"), {
+            const errors = report.findings
+                .filter((f) => f.severity === PrelaunchSeverity.Error)
+                .map((f) => describeCheck(f.check, getTranslation))
+                .filter((d) => d !== null);
+            if (!skipWarning && errors.length > 0) {
+                const details = errors.map((e) => `- ${e}`).join("\n");
+                const yes = await dialog.ask(`${details}\n\n${getTranslation("LAUNCH_ANYWAY")}`, {
                     kind: "warning",
                     title: getTranslation("CONFIRM")
                 });
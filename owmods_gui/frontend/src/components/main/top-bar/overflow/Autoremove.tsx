@@ -0,0 +1,56 @@
+import { useGetTranslation } from "@hooks";
+import { ModalProps } from "./About";
+import { memo, useCallback } from "react";
+import { CleaningServicesRounded } from "@mui/icons-material";
+import { MenuItem, ListItemIcon, ListItemText } from "@mui/material";
+import * as dialog from "@tauri-apps/plugin-dialog";
+import { commands } from "@commands";
+import { simpleOnError } from "../../../../errorHandling";
+
+const Autoremove = memo(function Autoremove({ onClick }: ModalProps) {
+    const getTranslation = useGetTranslation();
+
+    const onAutoremove = useCallback(() => {
+        const task = async () => {
+            const orphans = await commands.getOrphanedMods();
+            if (orphans.length === 0) {
+                dialog.message(getTranslation("AUTOREMOVE_NONE"), {
+                    title: getTranslation("AUTOREMOVE")
+                });
+                return;
+            }
+            const yes = await dialog.ask(
+                getTranslation("AUTOREMOVE_CONFIRM", { mods: orphans.join(", ") }),
+                {
+                    title: getTranslation("AUTOREMOVE")
+                }
+            );
+            if (!yes) return;
+            const warnings = await commands.autoremoveMods();
+            await commands.refreshLocalDb();
+            for (const modName of warnings) {
+                dialog.message(getTranslation("PREPATCHER_WARNING", { name: modName }), {
+                    kind: "warning",
+                    title: getTranslation("PREPATCHER_WARNING_TITLE", { name: modName })
+                });
+            }
+        };
+        task().catch(simpleOnError);
+    }, [getTranslation]);
+
+    const handleClick = useCallback(() => {
+        onAutoremove();
+        onClick?.();
+    }, [onClick, onAutoremove]);
+
+    return (
+        <MenuItem onClick={handleClick}>
+            <ListItemIcon>
+                <CleaningServicesRounded fontSize="small" />
+            </ListItemIcon>
+            <ListItemText>{getTranslation("AUTOREMOVE")}</ListItemText>
+        </MenuItem>
+    );
+});
+
+export default Autoremove;
@@ -20,6 +20,8 @@ const About = lazy(() => import("./About"));
 const Import = lazy(() => import("./Import"));
 const Export = lazy(() => import("./Export"));
 const InstallFrom = lazy(() => import("./InstallFrom"));
+const Autoremove = lazy(() => import("./Autoremove"));
+const PastLogs = lazy(() => import("./PastLogs"));
 
 const OverflowMenu = () => {
     const getTranslation = useGetTranslation();
@@ -86,6 +88,7 @@ const OverflowMenu = () => {
                     <InstallFrom onClick={onClose} />
                     <Export onClick={onClose} />
                     <Import onClick={onClose} />
+                    <Autoremove onClick={onClose} />
                 </Suspense>
                 <MenuItem onClick={onOpenOwml}>
                     <ListItemIcon>
@@ -105,6 +108,9 @@ const OverflowMenu = () => {
                     </ListItemIcon>
                     <ListItemText>{getTranslation("LOGS")}</ListItemText>
                 </MenuItem>
+                <Suspense>
+                    <PastLogs onClick={onClose} />
+                </Suspense>
                 <MenuItem onClick={onHelp}>
                     <ListItemIcon>
                         <HelpRounded fontSize="small" />
@@ -0,0 +1,110 @@
+import { useGetTranslation } from "@hooks";
+import { ModalProps } from "./About";
+import { memo, useCallback, useState } from "react";
+import { HistoryRounded } from "@mui/icons-material";
+import * as dialog from "@tauri-apps/plugin-dialog";
+import {
+    MenuItem,
+    ListItemIcon,
+    ListItemText,
+    Dialog,
+    DialogTitle,
+    DialogContent,
+    DialogActions,
+    DialogContentText,
+    Button,
+    List,
+    ListItemButton
+} from "@mui/material";
+import { commands } from "@commands";
+import { LogSessionInfo } from "@types";
+import { simpleOnError } from "../../../../errorHandling";
+
+const PastLogs = memo(function PastLogs({ onClick }: ModalProps) {
+    const getTranslation = useGetTranslation();
+    const [open, setOpen] = useState(false);
+    const [sessions, setSessions] = useState<LogSessionInfo[]>([]);
+
+    const handleClick = useCallback(() => {
+        commands
+            .getLogSessions()
+            .then((sessions) => {
+                setSessions(sessions);
+                setOpen(true);
+            })
+            .catch(simpleOnError);
+        onClick?.();
+    }, [onClick]);
+
+    const onClose = useCallback(() => {
+        setOpen(false);
+    }, []);
+
+    const onOpenSession = useCallback(
+        (id: string) => {
+            commands.showLogSession({ id }).catch(simpleOnError);
+            onClose();
+        },
+        [onClose]
+    );
+
+    const onOpenFile = useCallback(() => {
+        dialog
+            .open({
+                title: getTranslation("OPEN_LOG_FILE"),
+                filters: [
+                    {
+                        name: "Log Session",
+                        extensions: ["jsonl"]
+                    }
+                ]
+            })
+            .then((path) => {
+                if (path) {
+                    onOpenSession(path);
+                }
+            })
+            .catch(simpleOnError);
+    }, [getTranslation, onOpenSession]);
+
+    return (
+        <>
+            <MenuItem onClick={handleClick}>
+                <ListItemIcon>
+                    <HistoryRounded fontSize="small" />
+                </ListItemIcon>
+                <ListItemText>{getTranslation("PAST_LOGS")}</ListItemText>
+            </MenuItem>
+            <Dialog fullWidth maxWidth="xs" open={open} onClose={onClose}>
+                <DialogTitle>{getTranslation("PAST_LOGS")}</DialogTitle>
+                <DialogContent dividers>
+                    {sessions.length === 0 ? (
+                        <DialogContentText>{getTranslation("NO_PAST_LOGS")}</DialogContentText>
+                    ) : (
+                        <List disablePadding>
+                            {sessions.map((session) => (
+                                <ListItemButton
+                                    key={session.id}
+                                    onClick={() => onOpenSession(session.id)}
+                                >
+                                    <ListItemText
+                                        primary={session.started}
+                                        secondary={getTranslation("LOG_SESSION_SIZE", {
+                                            size: session.sizeKb.toString()
+                                        })}
+                                    />
+                                </ListItemButton>
+                            ))}
+                        </List>
+                    )}
+                </DialogContent>
+                <DialogActions>
+                    <Button onClick={onOpenFile}>{getTranslation("OPEN_LOG_FILE")}</Button>
+                    <Button onClick={onClose}>{getTranslation("DISMISS")}</Button>
+                </DialogActions>
+            </Dialog>
+        </>
+    );
+});
+
+export default PastLogs;
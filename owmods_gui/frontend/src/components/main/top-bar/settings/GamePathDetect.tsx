@@ -0,0 +1,75 @@
+import { useGetTranslation } from "@hooks";
+import { Button, ListItemText, Menu, MenuItem } from "@mui/material";
+import { memo, useCallback, useState, MouseEvent } from "react";
+import { TravelExploreRounded } from "@mui/icons-material";
+import { commands } from "@commands";
+import { GamePathCandidate } from "@types";
+import ODTooltip from "@components/common/ODTooltip";
+import { simpleOnError } from "../../../../errorHandling";
+
+export interface GamePathDetectProps {
+    onChange: (id: string, newVal: string) => void;
+}
+
+const GamePathDetect = memo(function GamePathDetect(props: GamePathDetectProps) {
+    const getTranslation = useGetTranslation();
+
+    const [anchorEl, setAnchorEl] = useState<HTMLElement | null>(null);
+    const [candidates, setCandidates] = useState<GamePathCandidate[]>([]);
+    const open = Boolean(anchorEl);
+
+    const onClick = useCallback((event: MouseEvent<HTMLButtonElement>) => {
+        const target = event.currentTarget;
+        commands
+            .getGamePathCandidates()
+            .then((found) => {
+                setCandidates(found);
+                setAnchorEl(target);
+            })
+            .catch(simpleOnError);
+    }, []);
+
+    const onClose = useCallback(() => {
+        setAnchorEl(null);
+    }, []);
+
+    return (
+        <>
+            <ODTooltip title={getTranslation("TOOLTIP_DETECT_GAME_PATH")}>
+                <Button
+                    color="neutral"
+                    startIcon={<TravelExploreRounded />}
+                    onClick={onClick}
+                    id="detect-game-path-button"
+                    aria-controls={open ? "detect-game-path-menu" : undefined}
+                    aria-haspopup="true"
+                    aria-expanded={open ? "true" : undefined}
+                >
+                    {getTranslation("DETECT_GAME_PATH")}
+                </Button>
+            </ODTooltip>
+            <Menu id="detect-game-path-menu" anchorEl={anchorEl} open={open} onClose={onClose}>
+                {candidates.length === 0 ? (
+                    <MenuItem disabled>{getTranslation("NO_GAME_PATHS_FOUND")}</MenuItem>
+                ) : (
+                    candidates.map((candidate) => (
+                        <MenuItem
+                            key={candidate.path}
+                            onClick={() => {
+                                props.onChange("gamePath", candidate.path);
+                                onClose();
+                            }}
+                        >
+                            <ListItemText
+                                primary={candidate.path}
+                                secondary={getTranslation(`GAME_SOURCE_${candidate.source}`)}
+                            />
+                        </MenuItem>
+                    ))
+                )}
+            </Menu>
+        </>
+    );
+});
+
+export default GamePathDetect;
@@ -7,6 +7,7 @@ import ODTooltip from "@components/common/ODTooltip";
 import * as os from "@tauri-apps/plugin-os";
 import { Box, Button, useTheme } from "@mui/material";
 import SettingsFolder from "./SettingsFolder";
+import GamePathDetect from "./GamePathDetect";
 import SettingsSelect from "./SettingsSelect";
 import SettingsText from "./SettingsText";
 import SettingsCheck from "./SettingsCheck";
@@ -234,6 +235,7 @@ const SettingsForm = forwardRef(function SettingsForm(props: SettingsFormProps,
                 id="gamePath"
                 tooltip={getTranslation("TOOLTIP_GAME_PATH")}
             />
+            <GamePathDetect onChange={handleOwml} />
             <Box
                 rowGap={1}
                 columnGap={2}
@@ -271,6 +273,13 @@ const SettingsForm = forwardRef(function SettingsForm(props: SettingsFormProps,
                 id="sendAnalytics"
                 tooltip={getTranslation("TOOLTIP_ANALYTICS")}
             />
+            <SettingsCheck
+                onChange={handleConf}
+                value={config.offline}
+                label={getTranslation("OFFLINE_MODE")}
+                id="offline"
+                tooltip={getTranslation("TOOLTIP_OFFLINE_MODE")}
+            />
             <SettingsText
                 onChange={handleConf}
                 value={config.databaseUrl}
@@ -21,4 +21,10 @@ listen("gameStart", (inPort) => {
     );
 });
 
-commands.runGame().catch(simpleOnError);
+const session = new URLSearchParams(window.location.search).get("session");
+
+if (session) {
+    commands.openLogSession({ id: session }).catch(simpleOnError);
+} else {
+    commands.runGame().catch(simpleOnError);
+}
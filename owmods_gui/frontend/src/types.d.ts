@@ -18,6 +18,26 @@ export interface Alert {
     urlLabel?: string;
 }
 
+/** How downloads are retried when they fail because of a transient error (dropped connections, server errors, etc.) */
+export interface RetryPolicy {
+    /** How many times to retry a download before giving up, 0 disables retrying */
+    maxRetries: number;
+    /** How long to wait before the first retry in milliseconds, this doubles with each retry */
+    initialBackoffMs: number;
+    /** The longest we'll ever wait between retries in milliseconds */
+    maxBackoffMs: number;
+}
+
+/** What the log server does when logs come in faster than they're taken out of the channel */
+export enum LogOverflowPolicy {
+    /** Wait for room in the channel, this stops reading from the game until there's room */
+    Block = "Block",
+    /** Write logs that don't fit to a buffer on disk, they're sent in order once there's room */
+    Spill = "Spill",
+    /** Drop logs that don't fit, a message saying how many were dropped is sent along with the next log that fits */
+    Drop = "Drop"
+}
+
 /** Represents the core config, contains critical info needed by the core API */
 export interface Config {
     /** The path to the OWML install, defaults to `~/.local/share/OuterWildsModManager/OWML` */
@@ -32,6 +52,25 @@ export interface Config {
     lastViewedDbAlert?: string;
     /** Whether or not to send analytics events */
     sendAnalytics: boolean;
+    /** How downloads should be retried if they fail */
+    retryPolicy: RetryPolicy;
+    /** The most space downloaded archives can take up in the cache in megabytes, 0 disables the cache */
+    maxCacheSizeMb: number;
+    /** How many backups to keep for each mod when updating, 0 disables backups */
+    maxBackups: number;
+    /** How many recorded game log sessions to keep, 0 disables recording */
+    maxLogSessions: number;
+    /** How many days to keep recorded game log sessions for, 0 keeps them regardless of age */
+    maxLogSessionAgeDays: number;
+    /** What the log server does when the game sends logs faster than they can be shown */
+    logOverflowPolicy: LogOverflowPolicy;
+    /**
+     * Mods that shouldn't be updated, maps unique names to either the exact version the mod should stay on
+     * or `"hold"` to keep whatever version is installed
+     */
+    pins: Record<string, string>;
+    /** Whether to skip fetching the remote database and use the last fetched copy instead */
+    offline: boolean;
 }
 
 /** Represents an error with a [LocalMod] */
@@ -49,7 +88,9 @@ export type ModValidationError =
     /** There's another mod already in the DB with this mod's unique name, contains the path of the other mod that has the same unique name */
     | { errorType: "DuplicateMod"; payload: string }
     /** The mod is outdated, contains the newest version */
-    | { errorType: "Outdated"; payload: string };
+    | { errorType: "Outdated"; payload: string }
+    /** A dependency of the mod is installed, but its version doesn't meet the requirement in the mod's `dependencyVersions`, contains the unique name of the dep */
+    | { errorType: "UnsatisfiedDepVersion"; payload: string };
 
 /** Represents a mod that completely failed to load */
 export interface FailedMod {
@@ -92,11 +133,105 @@ export interface SocketMessage {
     messageType: SocketMessageType;
 }
 
+/** A single frame of a stack trace */
+export interface StackFrame {
+    /** The method that was running, including its arguments, like `Foo.Bar (System.String name)` */
+    method: string;
+    /** The file and line of the method if the game knew it, like `Foo.cs:12` */
+    location?: string;
+}
+
+/** An exception the game logged, along with its stack trace */
+export interface LogException {
+    /** The type of the exception, like `System.NullReferenceException` */
+    exceptionType: string;
+    /** The exception's message, this may be empty */
+    message: string;
+    /** The stack trace of the exception, innermost frame first */
+    frames: StackFrame[];
+    /** The name of the mod that logged the exception, if the game told us */
+    source?: string;
+}
+
 export interface GameMessage {
     port: LogPort;
     message: SocketMessage;
     amount: number;
     timestamp: string;
+    exception?: LogException;
+}
+
+export interface ExceptionCount {
+    source: string;
+    count: number;
+}
+
+export interface LogSessionInfo {
+    id: string;
+    started: string;
+    sizeKb: number;
+}
+
+/** How many errors a sender logged during a session */
+export interface ModErrorCount {
+    /** The name the errors were sent with */
+    senderName: string;
+    /** The unique name of the installed mod the sender name belongs to, if we could find one */
+    uniqueName?: string;
+    /** How many errors (including fatal errors) were sent */
+    errors: number;
+    /** How many of the errors were fatal */
+    fatal: number;
+    /** How many of the errors were exceptions */
+    exceptions: number;
+}
+
+/** The validation issues a mod had when the game was launched */
+export interface ModIssues {
+    /** The unique name of the mod, or its path if it failed to load */
+    uniqueName: string;
+    /** The name of the mod */
+    name: string;
+    /** The issues with the mod */
+    errors: ModValidationError[];
+}
+
+/** Why a mod is suspected of causing problems in a game session */
+export enum SuspectReason {
+    /** The mod sent the first fatal error */
+    SentFatal = "SentFatal",
+    /** The mod sent the first error */
+    SentFirstError = "SentFirstError",
+    /** The mod sent more errors than any other mod */
+    MostErrors = "MostErrors",
+    /** The mod had validation issues when the game was launched */
+    ValidationIssues = "ValidationIssues"
+}
+
+/** A mod that may have caused problems in a session */
+export interface SuspectMod {
+    /** The unique name of the mod */
+    uniqueName: string;
+    /** The name of the mod */
+    name: string;
+    /** Why the mod is a suspect, most important first */
+    reasons: SuspectReason[];
+}
+
+/** A report on what went wrong in a game session, see [analyze_session] */
+export interface SessionReport {
+    /** How many messages the game sent */
+    messageCount: number;
+    /** The first fatal error the game sent */
+    firstFatal?: SocketMessage;
+    /** The first error (fatal or not) the game sent */
+    firstError?: SocketMessage;
+    /** How many errors each sender logged, most errors first */
+    modErrors: ModErrorCount[];
+    /** Mods that may have caused problems, most suspicious first */
+    suspects: SuspectMod[];
+    /** Validation issues mods had when the game was launched */
+    validationIssues: ModIssues[];
 }
 
 export enum Language {
@@ -163,6 +298,11 @@ export interface ModManifest {
     owmlVersion?: string;
     /** The dependencies of the mod */
     dependencies?: string[];
+    /**
+     * Version requirements for the mod's dependencies, maps unique names in `dependencies` to a requirement like `>=1.2.0`.
+     * Multiple requirements can be separated with commas, and a plain version is treated as a minimum version.
+     */
+    dependencyVersions?: Record<string, string>;
     /** The mods this mod will conflict with */
     conflicts?: string[];
     /** The paths to preserve when updating the mod */
@@ -277,6 +417,90 @@ export interface OWMLConfig {
     socketPort: number;
 }
 
+/** Where a possible game install was found, in order of how much we trust it */
+export enum GamePathSource {
+    /** A Steam library that has the game installed */
+    Steam = "Steam",
+    /** A Steam library of the Flatpak version of Steam */
+    SteamFlatpak = "SteamFlatpak",
+    /** The Epic Games Launcher's install manifests */
+    Epic = "Epic",
+    /** The Heroic Games Launcher's list of installed Epic games */
+    Heroic = "Heroic",
+    /** A common install location that wasn't found through a launcher */
+    Common = "Common"
+}
+
+/** A folder Outer Wilds is installed in */
+export interface GamePathCandidate {
+    /** The path to the folder containing `OuterWilds.exe` */
+    path: string;
+    /** Where we found it */
+    source: GamePathSource;
+}
+
+/** Something wrong with a game install, see [validate_game_path] */
+export enum GamePathProblem {
+    /** The folder doesn't exist */
+    NotFound = "NotFound",
+    /** The folder is an install of The Outer Worlds, not Outer Wilds */
+    OuterWorlds = "OuterWorlds",
+    /** `OuterWilds.exe` is missing */
+    MissingExe = "MissingExe",
+    /** The `OuterWilds_Data` folder is missing */
+    MissingData = "MissingData"
+}
+
+/** What we know about the game installed in a folder, see [validate_game_path] */
+export interface GameInstall {
+    /** The folder that was checked */
+    path: string;
+    /** Everything wrong with the install, empty if it's valid */
+    problems: GamePathProblem[];
+    /**
+     * The version of Unity the game was built with, if we could read it.
+     * This is *not* the game's own version, which we don't read.
+     */
+    unityVersion?: string;
+    /**
+     * Whether anything that looks like the Echoes of the Eye DLC's content was found.
+     * This is a best guess from asset bundle names, not a check of the DLC's files or whether it's owned.
+     */
+    hasDlc: boolean;
+}
+
+/** How serious a pre-launch finding is */
+export enum PrelaunchSeverity {
+    /** Good to know, but nothing is wrong */
+    Info = "Info",
+    /** Something the user should see, but the game can still launch */
+    Warning = "Warning",
+    /** The game or some mods probably won't work, launching is refused unless forced */
+    Error = "Error"
+}
+
+/** A single finding from [run_prelaunch_checks] */
+export interface PrelaunchFinding {
+    /** How serious the finding is */
+    severity: PrelaunchSeverity;
+    /** What was found */
+    check: PrelaunchCheck;
+}
+
+/** Everything the pre-launch checks found, most severe first */
+export interface PrelaunchReport {
+    /** The findings, sorted from most to least severe */
+    findings: PrelaunchFinding[];
+}
+
+/** A named set of enabled mods, optionally with the settings each mod should use. */
+export interface Profile {
+    /** The name of the profile, also used as its file name */
+    name: string;
+    /** The unique names of the mods that should be enabled, all others will be disabled */
+    enabled: string[];
+}
+
 /** Represents a progress bar */
 export interface ProgressBar {
     /** The ID of the progress bar */
@@ -381,10 +605,62 @@ export interface ProtocolPayload {
     payload: string;
 }
 
+/** A mod that will be downloaded and installed when executing an [InstallPlan] */
+export interface PlannedMod {
+    /** The unique name of the mod */
+    uniqueName: string;
+    /** The name of the mod */
+    name: string;
+    /** The version that will be installed */
+    version: string;
+    /** The URL the mod will be downloaded from */
+    downloadUrl: string;
+    /** The hash the archive is expected to have, if known */
+    hash?: string;
+    /** The size of the archive in bytes, only known once the archive is downloaded */
+    size?: number;
+    /** Whether the archive is already downloaded (in the cache or by [crate::download::prepare_install]) */
+    cached: boolean;
+    /**
+     * Whether the mod's dependencies and conflicts are known.
+     * They're read from the mod's manifest, which we only have once its archive is downloaded or if the same version is installed.
+     */
+    dependenciesKnown: boolean;
+    /** The version that's currently installed, if the mod is installed */
+    installedVersion?: string;
+    /** Why this mod is in the plan */
+    reason: PlanReason;
+}
+
+/** An ordered plan of mods to install, dependencies are resolved from the mods' manifests. */
+export interface InstallPlan {
+    /** The mods to install, dependencies always come before the mods that need them */
+    mods: PlannedMod[];
+    /** Problems found while resolving the plan */
+    problems: ResolveProblem[];
+    /** The total size of the archives in the plan in bytes, not counting archives that aren't downloaded yet */
+    totalSize: number;
+    /** The number of mods that still need to be downloaded, their sizes aren't known until they are */
+    unknownSizeCount: number;
+}
+
+/** Whether the remote database was fetched or loaded from the copy saved on disk */
+export interface RemoteDatabaseStatus {
+    /** Whether the database was loaded from the saved copy */
+    offline: boolean;
+    /** When the database was fetched, in seconds since the unix epoch */
+    fetchedAt?: number;
+}
+
 /** Represents a mod in the remote database */
 export interface RemoteMod {
     /** The URL to download the mod from, always GitHub */
     downloadUrl: string;
+    /**
+     * The SHA-256 hash of the archive at `download_url`, if the database provides one.
+     * Installs from the database will be verified against this.
+     */
+    hash?: string;
     /** The number of times the mod has been downloaded, this uses GitHub releases */
     downloadCount: number;
     /** The version of the mod, usually in the format `major.minor.patch` */
@@ -442,11 +718,117 @@ export type Event =
     /** Purposefully never used, some hooks only need to run once */
     | { name: "none"; params: EmptyParams };
 
+/** Why a mod is part of an [InstallPlan] */
+export type PlanReason =
+    /** The mod was requested directly */
+    | { reason: "Requested"; payload?: undefined }
+    /** The mod is a dependency of another mod in the plan, contains the unique name of that mod */
+    | { reason: "Dependency"; payload: string };
+
+/** Something a pre-launch check found */
+export type PrelaunchCheck =
+    /** An enabled mod has a warning the user hasn't seen yet */
+    | {
+          checkType: "UnseenWarning";
+          payload: {
+              /** The unique name of the mod */
+              uniqueName: string;
+              /** The warning to show */
+              warning: ModWarning;
+          };
+      }
+    /**
+     * A mod failed to load or an enabled mod has issues, like a missing DLL or a missing or disabled dependency.
+     * Conflicts and updates are reported separately.
+     */
+    | { checkType: "InvalidMod"; payload: ModIssues }
+    /** Two enabled mods conflict with each other */
+    | {
+          checkType: "Conflict";
+          payload: {
+              /** The unique name of the mod that declares the conflict */
+              uniqueName: string;
+              /** The unique name of the mod it conflicts with */
+              conflictsWith: string;
+          };
+      }
+    /** An enabled mod has an update that isn't held back by a pin */
+    | {
+          checkType: "OutdatedMod";
+          payload: {
+              /** The unique name of the mod */
+              uniqueName: string;
+              /** The version that's installed */
+              installed: string;
+              /** The newest version */
+              latest: string;
+          };
+      }
+    /** OWML isn't installed */
+    | { checkType: "MissingOwml"; payload?: undefined }
+    /** OWML has an update that isn't held back by a pin */
+    | {
+          checkType: "OutdatedOwml";
+          payload: {
+              /** The version that's installed */
+              installed: string;
+              /** The newest version */
+              latest: string;
+          };
+      }
+    /** OWML's game path doesn't point to a usable Outer Wilds install */
+    | {
+          checkType: "InvalidGamePath";
+          payload: {
+              /** The game path in OWML's config */
+              gamePath: string;
+              /** What's wrong with it */
+              problems: GamePathProblem[];
+          };
+      }
+    /** An enabled mod needs the Echoes of the Eye DLC, but it doesn't seem to be installed */
+    | {
+          checkType: "MissingDlc";
+          payload: {
+              /** The unique name of the mod */
+              uniqueName: string;
+          };
+      }
+    /** Mono couldn't be found, it's needed to launch the game on Linux and macOS */
+    | { checkType: "MissingMono"; payload?: undefined };
+
 export type RemoteModOption =
     | { type: "loading"; data?: undefined }
     | { type: "connected"; data?: RemoteMod }
     | { type: "err"; data: Error };
 
+/** A problem found while resolving an [InstallPlan] */
+export type ResolveProblem =
+    /** A mod isn't installed and isn't in the database */
+    | {
+          problemType: "Missing";
+          payload: {
+              /** The unique name of the missing mod */
+              uniqueName: string;
+              /** The mod that needs it, if it isn't one of the requested mods */
+              requiredBy?: string;
+          };
+      }
+    /** A prerelease was requested for a mod that doesn't have one, contains the unique name of the mod */
+    | { problemType: "NoPrerelease"; payload: string }
+    /** Some mods in the plan depend on each other in a cycle, contains the unique names in the cycle */
+    | { problemType: "Cycle"; payload: string[] }
+    /** A mod in the plan conflicts with another mod that's enabled or in the plan */
+    | {
+          problemType: "Conflict";
+          payload: {
+              /** The unique name of the mod that declares the conflict */
+              uniqueName: string;
+              /** The unique name of the mod it conflicts with */
+              conflictsWith: string;
+          };
+      };
+
 /** Represents a `LocalMod` that we aren't sure loaded successfully */
 export type UnsafeLocalMod =
     /** A mod was loaded successfully */
@@ -1,28 +1,45 @@
 use std::io::prelude::*;
 use std::net::TcpStream;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 
+// Hard limit to save your computer
+const MAX_MESSAGES: usize = 20000;
+
 pub fn spam_logs(port: u16) -> Result<()> {
     let mut stream = TcpStream::connect(format!("127.0.0.1:{port}"))?;
-    let mut i = 1;
-    // Hard limit to save your computer
-    while i < 20000 {
-        println!("Message {i}, {}", i == usize::MAX);
+    let delay: f32 = std::env::args()
+        .nth(3)
+        .unwrap_or("0.01666666666666667".to_string())
+        .parse()
+        .unwrap();
+    let count = std::env::args()
+        .nth(4)
+        .map(|c| c.parse::<usize>().unwrap())
+        .unwrap_or(MAX_MESSAGES)
+        .min(MAX_MESSAGES);
+    let start = Instant::now();
+    for i in 1..=count {
+        if i % 1000 == 0 {
+            println!("Message {i}");
+        }
         // I just want to easily change stuff when testing so im leaving the format here
         #[allow(clippy::useless_format)]
         let msg = format!("{{\"type\": 0, \"message\": \"Line {i}\", \"senderName\": \"xtask\", \"senderType\": \"log_spammer\"}}\n");
         stream.write_all(msg.as_bytes())?;
-        i += 1;
-        std::thread::sleep(Duration::from_secs_f32(
-            std::env::args()
-                .nth(3)
-                .unwrap_or("0.01666666666666667".to_string())
-                .parse()
-                .unwrap(),
-        ));
+        if delay > 0.0 {
+            std::thread::sleep(Duration::from_secs_f32(delay));
+        }
+    }
+    // Writes only block once the server stops reading (the block overflow policy), so this measures how fast it reads
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "Sent {count} messages in {elapsed:.2}s ({:.0} messages/s)",
+        count as f64 / elapsed
+    );
+    if count == MAX_MESSAGES {
+        println!("Hard Limit Reached");
     }
-    println!("Hard Limit Reached");
     Ok(())
 }